@@ -0,0 +1,27 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use phoenix::interpreter;
+
+fn while_loop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("while_loop");
+    for iterations in [1_000, 10_000] {
+        let script = format!(
+            "let i = 0; let sum = 0; while i < {} then {{ let sum = sum + i * 2; let i = i + 1 }}",
+            iterations
+        );
+        group.bench_with_input(
+            BenchmarkId::from_parameter(iterations),
+            &script,
+            |b, script| {
+                b.iter(|| {
+                    let res =
+                        interpreter::run(black_box(script.clone()), "<bench>".to_string(), None);
+                    assert!(res.res.is_ok());
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, while_loop);
+criterion_main!(benches);
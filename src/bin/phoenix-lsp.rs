@@ -0,0 +1,342 @@
+//! A language server for phoenix which speaks LSP over stdio
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as NotificationTrait, PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest, Request as RequestTrait,
+};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    Diagnostic, DiagnosticSeverity, DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams,
+    HoverProviderCapability, Location, MarkupContent, MarkupKind, OneOf, Position, Range,
+    ServerCapabilities, SymbolKind, TextDocumentSyncCapability, TextDocumentSyncKind, Uri,
+};
+use phoenix::interpreter;
+use phoenix::interpreter::analysis::{self, Analysis, Definition, DefinitionKind, Severity};
+use phoenix::interpreter::{builtins, Type};
+use std::collections::HashMap;
+use std::error::Error;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Sync + Send>>;
+
+struct Document {
+    text: String,
+    analysis: Analysis,
+    // the analysis of the last version without syntax errors, used for
+    // completion while typing
+    last_good: Option<Analysis>,
+}
+
+impl Document {
+    fn new(uri: &Uri, text: String, previous: Option<Document>) -> Self {
+        let analysis = analysis::analyze(&text, uri.as_str().to_string());
+        let last_good = if analysis.is_parsed() {
+            None
+        } else {
+            previous.and_then(|p| p.last_good.or(Some(p.analysis)))
+        };
+        Self {
+            text,
+            analysis,
+            last_good,
+        }
+    }
+
+    /// the analysis with the most information
+    fn best(&self) -> &Analysis {
+        match &self.last_good {
+            Some(last_good) if self.analysis.get_definitions().is_empty() => last_good,
+            _ => &self.analysis,
+        }
+    }
+
+    /// converts a byte offset to a position in utf-16 code units
+    fn position(&self, index: usize) -> Position {
+        let index = index.min(self.text.len());
+        let before = &self.text[..index];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Position::new(
+            before.matches('\n').count() as u32,
+            before[line_start..].encode_utf16().count() as u32,
+        )
+    }
+
+    fn range(&self, pos: &interpreter::Position) -> Range {
+        let start = pos.index().max(0) as usize;
+        Range::new(
+            self.position(start),
+            self.position(start + pos.len() as usize),
+        )
+    }
+
+    /// converts a position in utf-16 code units to a byte offset
+    fn index(&self, position: Position) -> usize {
+        let mut index = 0;
+        for (i, line) in self.text.split_inclusive('\n').enumerate() {
+            if i == position.line as usize {
+                let mut units = 0;
+                for (offset, c) in line.char_indices() {
+                    if units >= position.character as usize {
+                        return index + offset;
+                    }
+                    units += c.len_utf16();
+                }
+                return index + line.trim_end_matches('\n').len();
+            }
+            index += line.len();
+        }
+        index
+    }
+}
+
+struct Server {
+    connection: Connection,
+    documents: HashMap<String, Document>,
+}
+
+impl Server {
+    fn document(&self, uri: &Uri) -> Option<&Document> {
+        self.documents.get(uri.as_str())
+    }
+
+    fn update(&mut self, uri: Uri, text: String) -> Result<()> {
+        let previous = self.documents.remove(uri.as_str());
+        let document = Document::new(&uri, text, previous);
+        let diagnostics = document
+            .analysis
+            .get_diagnostics()
+            .iter()
+            .map(|d| Diagnostic {
+                range: document.range(d.get_pos()),
+                severity: Some(match d.get_severity() {
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                }),
+                source: Some("phoenix".to_string()),
+                message: d.get_message().to_string(),
+                ..Default::default()
+            })
+            .collect();
+        self.documents.insert(uri.as_str().to_string(), document);
+        let params = lsp_types::PublishDiagnosticsParams::new(uri, diagnostics, None);
+        self.connection
+            .sender
+            .send(Message::Notification(Notification::new(
+                PublishDiagnostics::METHOD.to_string(),
+                params,
+            )))?;
+        Ok(())
+    }
+
+    fn notification(&mut self, notification: Notification) -> Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                if let Some(params) = params::<DidOpenTextDocument>(notification) {
+                    self.update(params.text_document.uri, params.text_document.text)?;
+                }
+            }
+            DidChangeTextDocument::METHOD => {
+                // only full syncs are requested
+                if let Some(params) = params::<DidChangeTextDocument>(notification) {
+                    if let Some(change) = params.content_changes.into_iter().last() {
+                        self.update(params.text_document.uri, change.text)?;
+                    }
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                if let Some(params) = params::<DidCloseTextDocument>(notification) {
+                    self.documents.remove(params.text_document.uri.as_str());
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn request(&self, request: Request) -> Result<()> {
+        let id = request.id.clone();
+        let result = match request.method.as_str() {
+            HoverRequest::METHOD => respond::<HoverRequest>(request, |p| self.hover(p)),
+            GotoDefinition::METHOD => respond::<GotoDefinition>(request, |p| self.definition(p)),
+            DocumentSymbolRequest::METHOD => {
+                respond::<DocumentSymbolRequest>(request, |p| self.symbols(p))
+            }
+            Completion::METHOD => respond::<Completion>(request, |p| self.completion(p)),
+            method => Err((
+                ErrorCode::MethodNotFound,
+                format!("{} is not supported", method),
+            )),
+        };
+        let response = match result {
+            Ok(result) => Response::new_ok(id, result),
+            Err((code, message)) => Response::new_err(id, code as i32, message),
+        };
+        self.connection.sender.send(Message::Response(response))?;
+        Ok(())
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let params = params.text_document_position_params;
+        let document = self.document(&params.text_document.uri)?;
+        let index = document.index(params.position);
+        let definition = document.analysis.definition_at(index)?;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```phoenix\n{}\n```", definition.get_detail()),
+            }),
+            range: None,
+        })
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let params = params.text_document_position_params;
+        let uri = params.text_document.uri;
+        let document = self.document(&uri)?;
+        let index = document.index(params.position);
+        let definition = document.analysis.definition_at(index)?;
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            uri.clone(),
+            document.range(definition.get_pos()),
+        )))
+    }
+
+    fn symbols(&self, params: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+        let document = self.document(&params.text_document.uri)?;
+        let symbols = document
+            .analysis
+            .symbols()
+            .into_iter()
+            .map(|definition| {
+                let range = document.range(definition.get_pos());
+                #[allow(deprecated)]
+                DocumentSymbol {
+                    name: definition.get_name().to_string(),
+                    detail: Some(definition.get_detail().to_string()),
+                    kind: match definition.get_kind() {
+                        DefinitionKind::Function => SymbolKind::FUNCTION,
+                        DefinitionKind::Struct => SymbolKind::STRUCT,
+                        DefinitionKind::Enum => SymbolKind::ENUM,
+                        _ => SymbolKind::VARIABLE,
+                    },
+                    tags: None,
+                    deprecated: None,
+                    range,
+                    selection_range: range,
+                    children: None,
+                }
+            })
+            .collect();
+        Some(DocumentSymbolResponse::Nested(symbols))
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let params = params.text_document_position;
+        let document = self.document(&params.text_document.uri)?;
+        let index = document.index(params.position);
+        let keywords = interpreter::keywords()
+            .into_iter()
+            .map(|keyword| CompletionItem {
+                label: keyword,
+                kind: Some(CompletionItemKind::KEYWORD),
+                ..Default::default()
+            });
+        let builtins = builtins::values()
+            .into_iter()
+            .map(|(name, value)| CompletionItem {
+                label: name.to_string(),
+                kind: Some(match value {
+                    Type::Builtin(_) => CompletionItemKind::FUNCTION,
+                    Type::Module(_) => CompletionItemKind::MODULE,
+                    _ => CompletionItemKind::CONSTANT,
+                }),
+                detail: Some(value.to_string()),
+                ..Default::default()
+            });
+        let prelude = builtins::prelude::names()
+            .into_iter()
+            .map(|name| CompletionItem {
+                label: name,
+                kind: Some(CompletionItemKind::FUNCTION),
+                ..Default::default()
+            });
+        let names = document
+            .best()
+            .names_at(index)
+            .into_iter()
+            .map(completion_item);
+        Some(CompletionResponse::Array(
+            (names.chain(builtins).chain(prelude).chain(keywords)).collect(),
+        ))
+    }
+}
+
+/// the result of `handler` for the params of the request `R`, or the error
+/// code and message to answer with if the params are invalid
+fn respond<R: RequestTrait>(
+    request: Request,
+    handler: impl FnOnce(R::Params) -> R::Result,
+) -> std::result::Result<serde_json::Value, (ErrorCode, String)> {
+    let params = serde_json::from_value(request.params)
+        .map_err(|e| (ErrorCode::InvalidParams, e.to_string()))?;
+    serde_json::to_value(handler(params)).map_err(|e| (ErrorCode::InternalError, e.to_string()))
+}
+
+/// the params of the notification `N`, clients can't be told about invalid
+/// ones, so they are only logged and the notification is dropped
+fn params<N: NotificationTrait>(notification: Notification) -> Option<N::Params> {
+    serde_json::from_value(notification.params)
+        .map_err(|e| eprintln!("invalid params for {}: {}", N::METHOD, e))
+        .ok()
+}
+
+fn completion_item(definition: &Definition) -> CompletionItem {
+    CompletionItem {
+        label: definition.get_name().to_string(),
+        kind: Some(match definition.get_kind() {
+            DefinitionKind::Function => CompletionItemKind::FUNCTION,
+            DefinitionKind::Struct => CompletionItemKind::STRUCT,
+            DefinitionKind::Enum => CompletionItemKind::ENUM,
+            _ => CompletionItemKind::VARIABLE,
+        }),
+        detail: Some(definition.get_detail().to_string()),
+        ..Default::default()
+    }
+}
+
+fn main() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut server = Server {
+        connection,
+        documents: HashMap::new(),
+    };
+    while let Ok(message) = server.connection.receiver.recv() {
+        match message {
+            Message::Request(request) => {
+                if server.connection.handle_shutdown(&request)? {
+                    break;
+                }
+                server.request(request)?;
+            }
+            Message::Notification(notification) => server.notification(notification)?,
+            Message::Response(_) => {}
+        }
+    }
+    drop(server);
+    io_threads.join()?;
+    Ok(())
+}
@@ -0,0 +1,358 @@
+//! The Debug Adapter Protocol over stdio, so editors can attach
+
+use crate::debug::{frame_name, Sources};
+use phoenix::interpreter;
+use phoenix::interpreter::capabilities::Capabilities;
+use phoenix::interpreter::console::Console;
+use phoenix::interpreter::debug::{self, Breakpoint, Debugger, Frontend, Pause, Reason, Step};
+use phoenix::interpreter::{ErrorKind, Type};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Read, Write};
+use std::process::exit;
+use std::rc::Rc;
+
+// variable references are `frame * SCOPES + scope + 1`
+const SCOPES: usize = 1000;
+// phoenix has no threads, but the protocol needs one
+const THREAD: u64 = 1;
+
+struct Adapter {
+    seq: u64,
+    program: String,
+    sources: Sources,
+    stop_on_entry: bool,
+    breakpoints: BTreeSet<Breakpoint>,
+}
+
+/// reads a message, exits when the client closed the connection
+fn read() -> Value {
+    let stdin = std::io::stdin();
+    let mut stdin = stdin.lock();
+    let mut len = None;
+    loop {
+        let mut header = String::new();
+        match stdin.read_line(&mut header) {
+            Ok(0) | Err(_) => exit(0),
+            Ok(_) => {}
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            len = value.trim().parse().ok();
+        }
+    }
+    let mut body = vec![0; len.unwrap_or(0)];
+    if stdin.read_exact(&mut body).is_err() {
+        exit(0)
+    }
+    serde_json::from_slice(&body).unwrap_or(Value::Null)
+}
+
+impl Adapter {
+    fn send(&mut self, mut message: Value) {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        let body = message.to_string();
+        let mut stdout = std::io::stdout();
+        let _ = write!(stdout, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+        let _ = stdout.flush();
+    }
+
+    fn respond(&mut self, request: &Value, body: Value) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }));
+    }
+
+    fn fail(&mut self, request: &Value, message: &str) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }));
+    }
+
+    fn event(&mut self, event: &str, body: Value) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+
+    fn output(&mut self, category: &str, output: String) {
+        self.event("output", json!({ "category": category, "output": output }));
+    }
+
+    /// replaces the breakpoints of the source the request names
+    fn set_breakpoints(&mut self, request: &Value, breakpoints: &mut BTreeSet<Breakpoint>) {
+        let file = request["arguments"]["source"]["path"]
+            .as_str()
+            .unwrap_or(&self.program);
+        let file = debug::resolve(file);
+        let lines: Vec<u64> = request["arguments"]["breakpoints"]
+            .as_array()
+            .map(|breakpoints| {
+                breakpoints
+                    .iter()
+                    .filter_map(|b| b["line"].as_u64())
+                    .collect()
+            })
+            .unwrap_or_default();
+        breakpoints.retain(|breakpoint| breakpoint.get_file() != file);
+        breakpoints.extend(lines.iter().map(|line| Breakpoint::new(&file, *line)));
+        let verified: Vec<Value> = lines
+            .iter()
+            .map(|line| json!({ "verified": true, "line": line }))
+            .collect();
+        self.respond(request, json!({ "breakpoints": verified }));
+    }
+
+    /// handles the requests which can be answered at any time, returns false
+    /// for unknown ones
+    fn common(&mut self, request: &Value) -> bool {
+        match request["command"].as_str().unwrap_or_default() {
+            "threads" => self.respond(
+                request,
+                json!({ "threads": [{ "id": THREAD, "name": "main" }] }),
+            ),
+            "disconnect" | "terminate" => {
+                self.respond(request, Value::Null);
+                exit(0)
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// handles the requests until the client has configured everything
+    fn configure(&mut self) {
+        loop {
+            let request = read();
+            match request["command"].as_str().unwrap_or_default() {
+                "initialize" => {
+                    self.respond(
+                        &request,
+                        json!({ "supportsConfigurationDoneRequest": true }),
+                    );
+                    self.event("initialized", Value::Null);
+                }
+                "launch" => {
+                    let arguments = &request["arguments"];
+                    self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+                    let program = arguments["program"].as_str().unwrap_or_default();
+                    match std::fs::read_to_string(program) {
+                        Ok(text) => {
+                            self.program = program.to_string();
+                            self.sources = Sources::new(program, text);
+                            self.respond(&request, Value::Null);
+                        }
+                        Err(e) => self.fail(&request, &format!("can't read {}: {}", program, e)),
+                    }
+                }
+                "setBreakpoints" => {
+                    let mut breakpoints = std::mem::take(&mut self.breakpoints);
+                    self.set_breakpoints(&request, &mut breakpoints);
+                    self.breakpoints = breakpoints;
+                }
+                "configurationDone" => {
+                    self.respond(&request, Value::Null);
+                    if !self.program.is_empty() {
+                        return;
+                    }
+                }
+                _ if self.common(&request) => {}
+                _ => self.fail(&request, "not supported before the program runs"),
+            }
+        }
+    }
+
+    fn stack_trace(&mut self, request: &Value, pause: &Pause) {
+        let frames: Vec<Value> = pause
+            .get_frames()
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                json!({
+                    "id": i,
+                    "name": frame_name(&mut self.sources, frame),
+                    "source": { "path": frame.get_pos().filename() },
+                    "line": frame.get_pos().line() + 1,
+                    "column": frame.get_pos().column() + 1,
+                })
+            })
+            .collect();
+        let total = frames.len();
+        self.respond(
+            request,
+            json!({ "stackFrames": frames, "totalFrames": total }),
+        );
+    }
+
+    fn scopes(&mut self, request: &Value, pause: &Pause) {
+        let id = request["arguments"]["frameId"].as_u64().unwrap_or(0) as usize;
+        let count = match pause.get_frames().get(id) {
+            Some(frame) => frame.scopes().len(),
+            None => return self.fail(request, "unknown frame"),
+        };
+        let scopes: Vec<Value> = (0..count)
+            .map(|i| {
+                let name = if i + 1 == count {
+                    "Globals"
+                } else if i == 0 {
+                    "Locals"
+                } else {
+                    "Closure"
+                };
+                json!({
+                    "name": name,
+                    "variablesReference": id * SCOPES + i + 1,
+                    "expensive": false,
+                })
+            })
+            .collect();
+        self.respond(request, json!({ "scopes": scopes }));
+    }
+
+    fn variables(&mut self, request: &Value, pause: &Pause) {
+        let reference = request["arguments"]["variablesReference"]
+            .as_u64()
+            .unwrap_or(0) as usize;
+        let scope = reference.checked_sub(1).and_then(|r| {
+            pause
+                .get_frames()
+                .get(r / SCOPES)?
+                .scopes()
+                .get(r % SCOPES)
+                .cloned()
+        });
+        let variables: Vec<Value> = scope
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, value)| {
+                json!({
+                    "name": name,
+                    "value": value.to_string(),
+                    "type": value.type_name(),
+                    "variablesReference": 0,
+                })
+            })
+            .collect();
+        self.respond(request, json!({ "variables": variables }));
+    }
+}
+
+impl Frontend for Adapter {
+    fn pause(&mut self, pause: &Pause, breakpoints: &mut BTreeSet<Breakpoint>) -> Step {
+        let reason = match pause.get_reason() {
+            Reason::Entry => "entry",
+            Reason::Step => "step",
+            Reason::Breakpoint => "breakpoint",
+        };
+        self.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD, "allThreadsStopped": true }),
+        );
+        loop {
+            let request = read();
+            let step = match request["command"].as_str().unwrap_or_default() {
+                "continue" => Step::Continue,
+                "next" => Step::Over,
+                "stepIn" => Step::In,
+                "stepOut" => Step::Out,
+                "stackTrace" => {
+                    self.stack_trace(&request, pause);
+                    continue;
+                }
+                "scopes" => {
+                    self.scopes(&request, pause);
+                    continue;
+                }
+                "variables" => {
+                    self.variables(&request, pause);
+                    continue;
+                }
+                "setBreakpoints" => {
+                    self.set_breakpoints(&request, breakpoints);
+                    continue;
+                }
+                _ if self.common(&request) => continue,
+                _ => {
+                    self.fail(&request, "not supported");
+                    continue;
+                }
+            };
+            self.respond(&request, json!({ "allThreadsContinued": true }));
+            return step;
+        }
+    }
+}
+
+/// stdout is used by the protocol, so the output of the script is sent as
+/// output events and there is no input
+struct Output(Rc<Debugger<Adapter>>);
+
+impl Console for Output {
+    fn write(&self, text: &str) -> io::Result<()> {
+        self.0.frontend().output("stdout", text.to_string());
+        Ok(())
+    }
+
+    fn read_line(&self) -> io::Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+/// serves one debug session, returns the exit code
+pub fn run() -> i32 {
+    let mut adapter = Adapter {
+        seq: 0,
+        program: String::new(),
+        sources: Sources::default(),
+        stop_on_entry: false,
+        breakpoints: BTreeSet::new(),
+    };
+    adapter.configure();
+
+    let program = adapter.program.clone();
+    let text = adapter.sources.get(&program).to_string();
+    let breakpoints = std::mem::take(&mut adapter.breakpoints);
+    let stop_on_entry = adapter.stop_on_entry;
+    let debugger = Rc::new(Debugger::new(adapter, breakpoints, stop_on_entry));
+    let options = interpreter::Options::new()
+        .with_capabilities(Capabilities::all())
+        .with_console(Rc::new(Output(debugger.clone())))
+        .with_hook(debugger.clone());
+    let res = interpreter::run_with(text, program, None, options).res;
+
+    let mut adapter = debugger.frontend();
+    let code = match res {
+        Ok(ty) => {
+            if ty != Type::none() {
+                adapter.output("stdout", format!("{}\n", ty));
+            }
+            0
+        }
+        Err(e) => match e.kind() {
+            ErrorKind::Exit(code) => *code as i32,
+            _ => {
+                adapter.output("stderr", format!("{}\n", e));
+                1
+            }
+        },
+    };
+    adapter.event("exited", json!({ "exitCode": code }));
+    adapter.event("terminated", Value::Null);
+    // wait for the client to disconnect
+    loop {
+        let request = read();
+        if !adapter.common(&request) {
+            adapter.fail(&request, "the program has finished");
+        }
+    }
+}
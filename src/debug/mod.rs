@@ -0,0 +1,264 @@
+mod dap;
+
+use phoenix::interpreter;
+use phoenix::interpreter::capabilities::Capabilities;
+use phoenix::interpreter::debug::{Breakpoint, Debugger, Frame, Frontend, Pause, Reason, Step};
+use phoenix::interpreter::{ErrorKind, Position, Type};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::collections::{BTreeSet, HashMap};
+use std::process::exit;
+use std::rc::Rc;
+
+const HELP: &str = "\
+Commands:
+  s, step          run until the next line, entering called functions
+  n, next          run until the next line of the current function
+  o, out           run until the current function returned
+  c, continue      run until the next breakpoint
+  b, break LINE    pause whenever LINE of the current file is reached,
+                   FILE:LINE for a line of another file
+  d, delete LINE   remove the breakpoint at LINE or FILE:LINE
+  v, vars          show the variables of the current function
+  bt, backtrace    show the calls that led to the current line
+  l, list          show the source around the current line
+  q, quit          stop the script
+  h, help          show this help
+An empty line repeats the last step.";
+
+/// the text at `pos` up to the end of its line, e.g. the callee of a call
+pub fn snippet<'a>(text: &'a str, pos: &Position) -> &'a str {
+    let start = (pos.index().max(0) as usize).min(text.len());
+    let end = (start + pos.len() as usize).min(text.len());
+    text[start..end].lines().next().unwrap_or_default()
+}
+
+/// The text of the script and of the modules it imported, read when a
+/// position in them is shown first
+#[derive(Default)]
+pub struct Sources(HashMap<String, String>);
+
+impl Sources {
+    pub fn new(file_name: &str, text: String) -> Self {
+        Self(HashMap::from([(file_name.to_string(), text)]))
+    }
+
+    /// the text of `file`, empty if it can't be read
+    pub fn get(&mut self, file: &str) -> &str {
+        (self.0.entry(file.to_string()))
+            .or_insert_with(|| std::fs::read_to_string(file).unwrap_or_default())
+    }
+}
+
+/// the name of the function a frame is running
+pub fn frame_name(sources: &mut Sources, frame: &Frame) -> String {
+    match frame.get_call() {
+        Some(call) => {
+            let call = snippet(sources.get(call.filename()), call);
+            call.rfind('(').map_or(call, |i| &call[..i]).to_string()
+        }
+        None => "<script>".to_string(),
+    }
+}
+
+/// the breakpoint `LINE` in `file` or `FILE:LINE`
+fn parse_breakpoint(arg: &str, file: &str) -> Option<Breakpoint> {
+    let (file, line) = arg.rsplit_once(':').unwrap_or((file, arg));
+    match line.parse() {
+        Ok(line) if line > 0 => Some(Breakpoint::new(file, line)),
+        _ => None,
+    }
+}
+
+struct Cli {
+    sources: Sources,
+    editor: DefaultEditor,
+    last_step: Step,
+}
+
+impl Cli {
+    fn list_line(&mut self, pos: &Position) {
+        let line = pos.line() + 1;
+        if let Some(text) = self
+            .sources
+            .get(pos.filename())
+            .lines()
+            .nth(line as usize - 1)
+        {
+            println!("{:>4} | {}", line, text);
+        }
+    }
+
+    fn list(&mut self, pos: &Position, breakpoints: &BTreeSet<Breakpoint>) {
+        let (file, line) = (pos.filename(), pos.line() + 1);
+        let first = line.saturating_sub(3).max(1);
+        for (number, text) in (self.sources.get(file).lines().enumerate())
+            .skip(first as usize - 1)
+            .take(7)
+        {
+            let number = number as u64 + 1;
+            let breakpoint = breakpoints.contains(&Breakpoint::new(file, number));
+            let marker = match (number == line, breakpoint) {
+                (true, _) => "->",
+                (false, true) => " *",
+                (false, false) => "  ",
+            };
+            println!("{} {:>4} | {}", marker, number, text);
+        }
+    }
+
+    fn location(&self, pos: &Position) -> String {
+        format!("{}:{}", pos.filename(), pos.line() + 1)
+    }
+}
+
+impl Frontend for Cli {
+    fn pause(&mut self, pause: &Pause, breakpoints: &mut BTreeSet<Breakpoint>) -> Step {
+        let pos = pause.get_pos();
+        let reason = match pause.get_reason() {
+            Reason::Breakpoint => "breakpoint at ",
+            _ => "",
+        };
+        println!("{}{}", reason, self.location(pos));
+        self.list_line(pos);
+        loop {
+            let input = match self.editor.readline("(debug) ") {
+                Ok(input) => input,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => exit(0),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1)
+                }
+            };
+            let _ = self.editor.add_history_entry(input.as_str());
+            let (command, arg) = match input.trim().split_once(char::is_whitespace) {
+                Some((command, arg)) => (command, arg.trim()),
+                None => (input.trim(), ""),
+            };
+            let step = match command {
+                "" => Some(self.last_step),
+                "s" | "step" => Some(Step::In),
+                "n" | "next" => Some(Step::Over),
+                "o" | "out" => Some(Step::Out),
+                "c" | "continue" => Some(Step::Continue),
+                "b" | "break" | "d" | "delete" => {
+                    match parse_breakpoint(arg, pos.filename()) {
+                        Some(breakpoint) => {
+                            if command.starts_with('b') {
+                                breakpoints.insert(breakpoint);
+                            } else if !breakpoints.remove(&breakpoint) {
+                                println!("there is no breakpoint at {}", arg);
+                            }
+                        }
+                        None => println!("{} needs a line number", command),
+                    }
+                    None
+                }
+                "v" | "vars" => {
+                    print_scopes(&pause.get_frames()[0]);
+                    None
+                }
+                "bt" | "backtrace" => {
+                    for (i, frame) in pause.get_frames().iter().enumerate() {
+                        println!(
+                            "#{} {} at {}",
+                            i,
+                            frame_name(&mut self.sources, frame),
+                            self.location(frame.get_pos())
+                        );
+                    }
+                    None
+                }
+                "l" | "list" => {
+                    self.list(pos, breakpoints);
+                    None
+                }
+                "q" | "quit" => exit(0),
+                "h" | "help" => {
+                    println!("{}", HELP);
+                    None
+                }
+                _ => {
+                    println!("unknown command {}, see help", command);
+                    None
+                }
+            };
+            if let Some(step) = step {
+                self.last_step = step;
+                return step;
+            }
+        }
+    }
+}
+
+fn print_scopes(frame: &Frame) {
+    let scopes = frame.scopes();
+    for (i, scope) in scopes.iter().enumerate() {
+        let name = if i + 1 == scopes.len() {
+            "globals"
+        } else if i == 0 {
+            "locals"
+        } else {
+            "closure"
+        };
+        println!("{}:", name);
+        for (name, value) in scope {
+            println!("  {}: {} = {}", name, value.type_name(), value);
+        }
+    }
+}
+
+/// runs the debugger for the arguments after `phoenix debug`, returns the exit code
+pub fn run(args: impl Iterator<Item = String>) -> i32 {
+    let mut lines = Vec::new();
+    let mut file = None;
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dap" => return dap::run(),
+            "-b" | "--break" => match args.next() {
+                Some(line) => lines.push(line),
+                None => crate::fail(&format!("{} needs a line number", arg)),
+            },
+            // stdin is needed for the commands
+            option if option.starts_with('-') => crate::fail(&format!("unknown option {}", option)),
+            _ if file.is_some() => crate::fail("only one file can be given"),
+            _ => file = Some(arg),
+        }
+    }
+    let file = file.unwrap_or_else(|| crate::fail("debug needs a file"));
+    let (file_name, text) = crate::read_source(&file);
+    let breakpoints = (lines.iter())
+        .map(|line| {
+            parse_breakpoint(line, &file_name)
+                .unwrap_or_else(|| crate::fail(&format!("{} is no line number", line)))
+        })
+        .collect();
+
+    let cli = Cli {
+        sources: Sources::new(&file_name, text.clone()),
+        editor: DefaultEditor::new().expect("Error initializing the terminal"),
+        last_step: Step::Over,
+    };
+    println!("type help to list the commands");
+    let debugger = Rc::new(Debugger::new(cli, breakpoints, true));
+    let options = interpreter::Options::new()
+        .with_capabilities(Capabilities::all())
+        .with_hook(debugger);
+    match interpreter::run_with(text, file_name, None, options).res {
+        Ok(ty) => {
+            if ty != Type::none() {
+                println!("{}", ty);
+            }
+            0
+        }
+        Err(e) => match e.kind() {
+            ErrorKind::Exit(code) => *code as i32,
+            _ => {
+                eprintln!("{}", e);
+                1
+            }
+        },
+    }
+}
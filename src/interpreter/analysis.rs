@@ -0,0 +1,512 @@
+use crate::interpreter::ast::nodes::{Node, NodeType, OperationType, Pattern, Variable};
+use crate::interpreter::check::{self, Checked};
+use crate::interpreter::{builtins, limits, parse, Position};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DefinitionKind {
+    Variable,
+    Function,
+    Parameter,
+    Struct,
+    Enum,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    severity: Severity,
+    message: String,
+    pos: Position,
+}
+
+impl Diagnostic {
+    pub fn get_severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn get_pos(&self) -> &Position {
+        &self.pos
+    }
+}
+
+/// A place where a name gets a value: a `let`, a function, a parameter, a
+/// struct, an enum or a pattern
+#[derive(Clone, Debug)]
+pub struct Definition {
+    name: String,
+    kind: DefinitionKind,
+    pos: Position,
+    // the definition is only visible for uses after this byte offset
+    visible_from: usize,
+    scope: usize,
+    detail: String,
+}
+
+impl Definition {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_kind(&self) -> DefinitionKind {
+        self.kind
+    }
+
+    /// the position of the name
+    pub fn get_pos(&self) -> &Position {
+        &self.pos
+    }
+
+    /// a short description with the inferred type, e.g. `fn f(a) -> int`
+    pub fn get_detail(&self) -> &str {
+        &self.detail
+    }
+}
+
+/// Functions get their own scope, blocks share the one they are in
+#[derive(Debug)]
+struct Scope {
+    parent: Option<usize>,
+    start: usize,
+    end: usize,
+}
+
+/// What an editor wants to know about a script: the errors in it and where
+/// every name is defined
+#[derive(Debug, Default)]
+pub struct Analysis {
+    diagnostics: Vec<Diagnostic>,
+    definitions: Vec<Definition>,
+    // the definition every used name refers to
+    references: Vec<(Position, Option<usize>)>,
+    scopes: Vec<Scope>,
+    // false if the script has syntax errors
+    parsed: bool,
+}
+
+/// lexes, parses and analyzes `text` without running it
+pub fn analyze(text: &str, file_name: String) -> Analysis {
+    let ast = match parse(text, file_name) {
+        Ok(ast) => ast,
+        Err(e) => {
+            let pos = e
+                .position()
+                .cloned()
+                .unwrap_or_else(|| Position::new(0, "".into(), 0, 0, 0));
+            return Analysis {
+                diagnostics: vec![Diagnostic {
+                    severity: Severity::Error,
+                    message: format!("{:?}: {}", e.kind(), e.message()),
+                    pos,
+                }],
+                ..Default::default()
+            };
+        }
+    };
+
+    let mut collector = Collector {
+        scopes: vec![Scope {
+            parent: None,
+            start: 0,
+            end: text.len(),
+        }],
+        current: 0,
+        definitions: Vec::new(),
+        uses: Vec::new(),
+    };
+    collector.visit(&ast);
+    let checked = check::infer(&ast, false);
+    let mut analysis = collector.finish(&checked);
+    for e in checked.get_errors() {
+        if let Some(pos) = e.position() {
+            analysis.diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                message: format!("{:?}: {}", e.kind(), e.message()),
+                pos: pos.clone(),
+            });
+        }
+    }
+    analysis
+}
+
+impl Analysis {
+    pub fn get_diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// whether the script could be parsed, it may still have type errors
+    pub fn is_parsed(&self) -> bool {
+        self.parsed
+    }
+
+    pub fn get_definitions(&self) -> &[Definition] {
+        &self.definitions
+    }
+
+    /// the definition of the name at the byte offset `index`, which is
+    /// either a use of the name or the definition itself
+    pub fn definition_at(&self, index: usize) -> Option<&Definition> {
+        let contains = |pos: &Position| {
+            (pos.index as usize..=(pos.index as u64 + pos.len()) as usize).contains(&index)
+        };
+        if let Some(definition) = self.definitions.iter().find(|d| contains(&d.pos)) {
+            return Some(definition);
+        }
+        self.references
+            .iter()
+            .find(|(pos, _)| contains(pos))
+            .and_then(|(_, definition)| self.definitions.get((*definition)?))
+    }
+
+    /// the functions and variables of the script, every name only once
+    pub fn symbols(&self) -> Vec<&Definition> {
+        let mut symbols: Vec<&Definition> = Vec::new();
+        for definition in self.definitions.iter().filter(|d| d.scope == 0) {
+            if !symbols.iter().any(|s| s.name == definition.name) {
+                symbols.push(definition);
+            }
+        }
+        symbols
+    }
+
+    /// all definitions which can be used at the byte offset `index`, the
+    /// innermost first and every name only once
+    pub fn names_at(&self, index: usize) -> Vec<&Definition> {
+        let mut scope = self
+            .scopes
+            .iter()
+            .enumerate()
+            .filter(|(_, scope)| scope.start <= index && index <= scope.end)
+            .map(|(i, _)| i)
+            .next_back()
+            // the text may have changed since, use the global scope then
+            .or_else(|| (!self.scopes.is_empty()).then_some(0));
+        let mut names: Vec<&Definition> = Vec::new();
+        while let Some(current) = scope {
+            for definition in self.definitions.iter().filter(|d| d.scope == current) {
+                if !names.iter().any(|n| n.name == definition.name) {
+                    names.push(definition);
+                }
+            }
+            scope = self.scopes[current].parent;
+        }
+        names
+    }
+}
+
+/// walks the ast once to find all scopes, definitions and uses of names
+struct Collector<'a> {
+    scopes: Vec<Scope>,
+    current: usize,
+    // every definition with the node that gives it its value
+    definitions: Vec<(Definition, Option<&'a Node>)>,
+    uses: Vec<(&'a Variable, usize)>,
+}
+
+impl<'a> Collector<'a> {
+    fn define(
+        &mut self,
+        var: &Variable,
+        kind: DefinitionKind,
+        visible_from: usize,
+        value: Option<&'a Node>,
+    ) {
+        self.definitions.push((
+            Definition {
+                name: var.get_name().to_string(),
+                kind,
+                pos: var.get_pos(),
+                visible_from,
+                scope: self.current,
+                detail: String::new(),
+            },
+            value,
+        ));
+    }
+
+    /// defines the parameters of a function in a new scope and visits its body
+    fn function(&mut self, node: &'a Node, params: &[Variable], body: &'a Node) {
+        self.scopes.push(Scope {
+            parent: Some(self.current),
+            start: node.get_pos().index as usize,
+            end: node.end_index(),
+        });
+        let parent = self.current;
+        self.current = self.scopes.len() - 1;
+        for param in params {
+            self.define(param, DefinitionKind::Parameter, 0, None);
+        }
+        self.visit(body);
+        self.current = parent;
+    }
+
+    /// defines the names a pattern binds, struct and enum names are uses
+    fn pattern(&mut self, pattern: &'a Pattern, visible_from: usize) {
+        match pattern {
+            Pattern::Binding(var) => self.define(var, DefinitionKind::Variable, visible_from, None),
+            Pattern::Tuple(patterns, _) => {
+                for pattern in patterns {
+                    self.pattern(pattern, visible_from);
+                }
+            }
+            Pattern::List(list) => {
+                for pattern in list.get_items().iter().chain(list.get_rest()) {
+                    self.pattern(pattern, visible_from);
+                }
+            }
+            Pattern::Constructor(constructor) => {
+                self.uses.push((constructor.get_type(), self.current));
+                for arg in constructor.get_args().unwrap_or_default() {
+                    self.pattern(arg, visible_from);
+                }
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(..) => {}
+        }
+    }
+
+    fn visit(&mut self, node: &'a Node) {
+        limits::grow(|| self.visit_node(node))
+    }
+
+    fn visit_node(&mut self, node: &'a Node) {
+        match node.get_type() {
+            NodeType::Node(_) => {}
+            NodeType::Var(var) => self.uses.push((var, self.current)),
+            NodeType::Operation(OperationType::BinaryOperationNode(op)) => {
+                self.visit(op.get_left());
+                self.visit(op.get_right());
+            }
+            NodeType::Operation(OperationType::UnaryOperationNode(op)) => self.visit(op.get_node()),
+            NodeType::Assign(assign) => {
+                self.visit(assign.get_expr());
+                let (var, value) = (assign.get_var(), assign.get_expr());
+                self.define(var, DefinitionKind::Variable, node.end_index(), Some(value));
+            }
+            NodeType::Destructure(destructure) => {
+                self.visit(destructure.get_expr());
+                self.pattern(destructure.get_pattern(), node.end_index());
+            }
+            NodeType::Tuple(tuple) => {
+                for item in tuple.get_items() {
+                    self.visit(item);
+                }
+            }
+            NodeType::IfNode(node) => {
+                for (condition, expr) in node.get_cases() {
+                    self.visit(condition);
+                    self.visit(expr);
+                }
+                if let Some(else_case) = node.get_else_case() {
+                    self.visit(else_case);
+                }
+            }
+            NodeType::ForNode(for_node) => {
+                let (var, start, end, body) = for_node.get_all();
+                self.visit(start);
+                let visible_from = match end {
+                    Some(end) => {
+                        self.visit(end);
+                        end.end_index()
+                    }
+                    None => start.end_index(),
+                };
+                // iterating gives the items, not the iterated value
+                let value = end.map(|_| start);
+                self.define(var, DefinitionKind::Variable, visible_from, value);
+                self.visit(body);
+            }
+            NodeType::WhileNode(node) => {
+                let (cond, body) = node.get_all();
+                self.visit(cond);
+                self.visit(body);
+            }
+            NodeType::FuncDefNode(func) => {
+                let (name, params, body) = func.get_all();
+                if let Some(name) = name {
+                    // functions can be called before they are defined
+                    let visible_from = self.scopes[self.current].start;
+                    self.define(name, DefinitionKind::Function, visible_from, Some(node));
+                }
+                self.function(node, params, body);
+            }
+            NodeType::StructDef(def) => {
+                let visible_from = self.scopes[self.current].start;
+                self.define(
+                    def.get_name(),
+                    DefinitionKind::Struct,
+                    visible_from,
+                    Some(node),
+                );
+                for method in def.get_methods() {
+                    if let NodeType::FuncDefNode(func) = method.get_type() {
+                        let (_, params, body) = func.get_all();
+                        self.function(method, params, body);
+                    }
+                }
+            }
+            NodeType::EnumDef(def) => {
+                let visible_from = self.scopes[self.current].start;
+                self.define(
+                    def.get_name(),
+                    DefinitionKind::Enum,
+                    visible_from,
+                    Some(node),
+                );
+            }
+            NodeType::Match(match_node) => {
+                self.visit(match_node.get_value());
+                for arm in match_node.get_arms() {
+                    let pattern = arm.get_pattern();
+                    let visible_from = pattern.get_pos().index as usize;
+                    self.pattern(pattern, visible_from);
+                    if let Some(guard) = arm.get_guard() {
+                        self.visit(guard);
+                    }
+                    self.visit(arm.get_body());
+                }
+            }
+            NodeType::CallNode(call) => {
+                let (callee, args) = call.get_all();
+                self.visit(callee);
+                for arg in args {
+                    self.visit(arg);
+                }
+                for (_, arg) in call.get_named() {
+                    self.visit(arg);
+                }
+            }
+            NodeType::AttributeNode(attribute) => self.visit(attribute.get_value()),
+            NodeType::IndexNode(index) => {
+                let (value, index) = index.get_all();
+                self.visit(value);
+                self.visit(index);
+            }
+            NodeType::SetAttribute(set) => {
+                self.visit(set.get_attribute().get_value());
+                self.visit(set.get_expr());
+            }
+            NodeType::Interpolation(interpolation) => {
+                for expr in interpolation.get_exprs() {
+                    self.visit(expr);
+                }
+            }
+            NodeType::Import(import) => {
+                for var in import.get_vars() {
+                    self.define(var, DefinitionKind::Variable, node.end_index(), None);
+                }
+            }
+            NodeType::Statements(statements) => {
+                for statement in statements.get_nodes() {
+                    self.visit(statement);
+                }
+            }
+        }
+    }
+
+    /// finds the definition a use refers to: the last one before it in the
+    /// innermost scope which has the name, or the first one if it is only
+    /// defined later on
+    fn lookup(&self, var: &Variable, mut scope: usize) -> Option<usize> {
+        let name = var.get_name().as_str();
+        let index = var.get_pos().index as usize;
+        loop {
+            let candidates: Vec<usize> = (0..self.definitions.len())
+                .filter(|i| {
+                    let definition = &self.definitions[*i].0;
+                    definition.scope == scope && definition.name == name
+                })
+                .collect();
+            let visible = candidates
+                .iter()
+                .rev()
+                .find(|i| self.definitions[**i].0.visible_from <= index);
+            if let Some(definition) = visible.or(candidates.first()) {
+                return Some(*definition);
+            }
+            scope = self.scopes[scope].parent?;
+        }
+    }
+
+    fn finish(self, checked: &Checked) -> Analysis {
+        let mut diagnostics = Vec::new();
+        let mut references = Vec::new();
+        let builtins = builtins::names();
+        let prelude = builtins::prelude::names();
+        for (var, scope) in &self.uses {
+            let definition = self.lookup(var, *scope);
+            let name = var.get_name().as_str();
+            let is_predefined = builtins.contains(&name) || prelude.iter().any(|n| n == name);
+            if definition.is_none() && !is_predefined {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    message: format!("{} is not defined", name),
+                    pos: var.get_pos(),
+                });
+            }
+            references.push((var.get_pos(), definition));
+        }
+
+        let definitions = self
+            .definitions
+            .into_iter()
+            .map(|(definition, value)| Definition {
+                detail: detail(&definition, value, checked),
+                ..definition
+            })
+            .collect();
+
+        Analysis {
+            diagnostics,
+            definitions,
+            references,
+            scopes: self.scopes,
+            parsed: true,
+        }
+    }
+}
+
+/// a short description of the definition with the type the checker inferred
+fn detail(definition: &Definition, value: Option<&Node>, checked: &Checked) -> String {
+    let ty = checked.type_of(&definition.pos);
+    match (definition.kind, value.map(Node::get_type)) {
+        (DefinitionKind::Function, Some(NodeType::FuncDefNode(func))) => {
+            let (_, params, _) = func.get_all();
+            let (types, _) = func.get_types();
+            let params: Vec<String> = (params.iter().zip(types))
+                .map(|(param, ty)| match ty {
+                    Some(ty) => format!("{}: {}", param, ty),
+                    None => param.to_string(),
+                })
+                .collect();
+            let returns = checked.result_of(&definition.pos);
+            format!(
+                "fn {}({}) -> {}",
+                definition.name,
+                params.join(", "),
+                returns.as_deref().unwrap_or("unknown")
+            )
+        }
+        (DefinitionKind::Struct, Some(NodeType::StructDef(def))) => {
+            let fields: Vec<String> = def.get_fields().iter().map(|f| f.to_string()).collect();
+            format!("struct {} {{ {} }}", definition.name, fields.join(", "))
+        }
+        (DefinitionKind::Enum, Some(NodeType::EnumDef(def))) => def.to_string(),
+        (DefinitionKind::Parameter, _) => match ty {
+            Some(ty) => format!("{}: {} (parameter)", definition.name, ty),
+            None => format!("{} (parameter)", definition.name),
+        },
+        (_, _) => format!(
+            "let {}: {}",
+            definition.name,
+            ty.as_deref().unwrap_or("unknown")
+        ),
+    }
+}
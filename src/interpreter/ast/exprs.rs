@@ -1,60 +1,103 @@
 use crate::interpreter::ast::nodes::{
-    Assignment, ForNode, IfNode, Node, NodeType, OperationType, UnaryOperationNode, WhileNode,
+    Arm, Assignment, Constructor, Destructure, EnumDefNode, ForNode, FuncDefNode, IfNode, Import,
+    Imported, Interpolation, ListPattern, MatchNode, Node, NodeType, OperationType, Part, Pattern,
+    SetAttributeNode, Source, Statements, StructDefNode, UnaryOperationNode, Variable, Variant,
+    WhileNode,
 };
 use crate::interpreter::ast::Parser;
+use crate::interpreter::token::ident::Ident;
 use crate::interpreter::token::keyword::Keyword;
-use crate::interpreter::token::Token;
-use crate::interpreter::{Error, ErrorKind, Position};
+use crate::interpreter::token::types::{Float, Integer, Type};
+use crate::interpreter::token::{Segment, Token};
+use crate::interpreter::{limits, Error, ErrorKind, Position};
 
 pub fn expr(parser: &mut Parser) -> Result<Node, Error> {
+    parser.nested(let_expr)
+}
+
+fn let_expr(parser: &mut Parser) -> Result<Node, Error> {
     let current_token = parser.current_token();
     if current_token.is_some() && current_token.unwrap().0 == Token::Keyword(Keyword::Let) {
         parser.advance();
+        if let Some((Token::LeftParenthesis, _)) | Some((Token::LeftSquareBrackets, _)) =
+            parser.current_token()
+        {
+            return destructure(parser);
+        }
         if let Some(c) = parser.current_token() {
             let pos = c.1;
             if let Token::Ident(i) = c.0 {
                 let name = i;
                 parser.advance();
+                let ty = annotation(parser)?;
                 if let Some(c) = parser.current_token() {
                     if Token::Equal == c.0 {
                         parser.advance();
                         let expr = expr(parser)?;
                         return Ok(Node::new(
-                            NodeType::Assign(Assignment::new(name, expr)),
+                            NodeType::Assign(
+                                Assignment::new(Variable::new(name, pos.clone()), expr)
+                                    .with_type(ty),
+                            ),
                             pos,
                         ));
                     }
                 }
-                Err(Error::new(ErrorKind::SyntaxError, "expected =", Some(pos)))
+                Err(parser.expected("expected =", Some(pos)))
             } else {
-                Err(Error::new(
-                    ErrorKind::SyntaxError,
-                    "expected identifier",
-                    Some(pos),
-                ))
+                Err(parser.expected("expected identifier", Some(pos)))
             }
         } else {
-            Err(Error::new(
-                ErrorKind::SyntaxError,
-                "expected expression",
-                None,
-            ))
+            Err(parser.expected("expected expression", None))
         }
     } else {
-        parser.binary_operation(&mut comp_expr, vec![Token::DoubleAnd, Token::DoubleOr])
+        let node =
+            parser.binary_operation(&mut comp_expr, vec![Token::DoubleAnd, Token::DoubleOr])?;
+        match (node.get_type(), parser.current_token()) {
+            (NodeType::AttributeNode(attribute), Some((Token::Equal, _))) => {
+                parser.advance();
+                let expr = expr(parser)?;
+                Ok(Node::new(
+                    NodeType::SetAttribute(SetAttributeNode::new(attribute.clone(), expr)),
+                    node.get_pos(),
+                ))
+            }
+            _ => Ok(node),
+        }
     }
 }
 
-pub fn if_expr(parser: &mut Parser, position: Position) -> Result<Node, Error> {
-    parser.advance();
+/// parses `(x, y) = expr` or `[first, ...rest] = expr` after `let`
+fn destructure(parser: &mut Parser) -> Result<Node, Error> {
+    let pattern = pattern(parser)?;
+    let pos = pattern.get_pos();
+    match parser.current_token() {
+        Some((Token::Equal, _)) => parser.advance(),
+        _ => return Err(parser.expected("expected =", Some(pos))),
+    }
+    let expr = expr(parser)?;
+    Ok(Node::new(
+        NodeType::Destructure(Destructure::new(pattern, expr)),
+        pos,
+    ))
+}
+
+/// parses the condition and the expression of a case in an if expression, `then` is optional
+fn if_case(parser: &mut Parser) -> Result<(Node, Node), Error> {
     let condition = expr(parser)?;
+    if let Some((Token::Keyword(Keyword::Then), _)) = parser.current_token() {
+        parser.advance();
+    }
     let expression = expr(parser)?;
-    let mut cases = vec![(condition, expression)];
+    Ok((condition, expression))
+}
+
+pub fn if_expr(parser: &mut Parser, position: Position) -> Result<Node, Error> {
+    parser.advance();
+    let mut cases = vec![if_case(parser)?];
     while let Some((Token::Keyword(Keyword::Elif), _pos)) = parser.current_token() {
         parser.advance();
-        let condition = expr(parser)?;
-        let expression = expr(parser)?;
-        cases.push((condition, expression));
+        cases.push(if_case(parser)?);
     }
     let else_case = if let Some((Token::Keyword(Keyword::Else), _pos)) = parser.current_token() {
         parser.advance();
@@ -85,67 +128,643 @@ pub fn while_expr(parser: &mut Parser, position: Position) -> Result<Node, Error
             pos,
         ))
     } else {
-        Err(Error::new(
-            ErrorKind::SyntaxError,
-            "expected 'then'",
-            Some(position),
-        ))
+        Err(parser.expected("expected 'then'", Some(position)))
     }
 }
 
 pub fn for_expr(parser: &mut Parser, position: Position) -> Result<Node, Error> {
     // TODO improve position of Errors
     parser.advance();
-    if let Some((Token::Ident(var_name), _)) = parser.current_token() {
+    if let Some((Token::Ident(var_name), var_pos)) = parser.current_token() {
         parser.advance();
         if let Some((Token::Keyword(Keyword::In), _)) = parser.current_token() {
             parser.advance();
             let start = expr(parser)?;
-            if let Some((Token::Keyword(Keyword::To), _)) = parser.current_token() {
+            let end = if let Some((Token::Keyword(Keyword::To), _)) = parser.current_token() {
                 parser.advance();
-                let end = expr(parser)?;
-                if let Some((Token::Keyword(Keyword::Then), _)) = parser.current_token() {
-                    parser.advance();
-                    let body = expr(parser)?;
-                    let pos = position.combine(end.get_pos());
-                    Ok(Node::new(
-                        NodeType::ForNode(ForNode::new(var_name, start, end, body)),
-                        pos,
-                    ))
-                } else {
-                    Err(Error::new(
-                        ErrorKind::SyntaxError,
-                        "expected 'then'",
-                        Some(position),
-                    ))
-                }
+                Some(expr(parser)?)
             } else {
-                Err(Error::new(
-                    ErrorKind::SyntaxError,
-                    "expected 'to'",
-                    Some(position),
+                None
+            };
+            if let Some((Token::Keyword(Keyword::Then), _)) = parser.current_token() {
+                parser.advance();
+                let body = expr(parser)?;
+                let pos = position.combine(end.as_ref().unwrap_or(&start).get_pos());
+                Ok(Node::new(
+                    NodeType::ForNode(ForNode::new(
+                        Variable::new(var_name, var_pos),
+                        start,
+                        end,
+                        body,
+                    )),
+                    pos,
                 ))
+            } else {
+                Err(parser.expected("expected 'to' or 'then'", Some(position)))
             }
         } else {
-            Err(Error::new(
+            Err(parser.expected("expected 'in'", Some(position)))
+        }
+    } else {
+        Err(parser.expected("expected ident", Some(position)))
+    }
+}
+
+/// parses the expressions in the braces of a string
+pub fn interpolation(
+    parser: &mut Parser,
+    segments: &[Segment],
+    position: Position,
+) -> Result<Node, Error> {
+    let mut parts = Vec::new();
+    for segment in segments {
+        parts.push(match segment {
+            Segment::Text(text, pos) => Part::Text(text.clone(), pos.clone()),
+            Segment::Expr(tokens) => {
+                let mut embedded = Parser::new(tokens.clone()).with_nesting(parser.nesting);
+                embedded.depth = parser.depth;
+                let node = embedded
+                    .nested(expr)
+                    .and_then(|node| match embedded.current_token() {
+                        Some((Token::EOF, _)) => Ok(node),
+                        token => Err(Error::new(
+                            ErrorKind::SyntaxError,
+                            "expected } after the expression",
+                            token.map(|(_, pos)| pos),
+                        )),
+                    });
+                // the string is closed, so nothing missing can follow anymore
+                let node = node.map_err(|e| match e.kind() {
+                    ErrorKind::EndOfFile => {
+                        Error::new(ErrorKind::SyntaxError, e.message(), e.position().cloned())
+                    }
+                    _ => e,
+                })?;
+                parser.enums.extend(embedded.enums);
+                parser.variants.extend(embedded.variants);
+                parser.matches.extend(embedded.matches);
+                Part::Expr(Box::new(node))
+            }
+        });
+    }
+    Ok(Node::new(
+        NodeType::Interpolation(Interpolation::new(parts)),
+        position,
+    ))
+}
+
+pub fn fn_expr(parser: &mut Parser, position: Position) -> Result<Node, Error> {
+    parser.advance();
+    let name = if let Some((Token::Ident(name), pos)) = parser.current_token() {
+        parser.advance();
+        Some(Variable::new(name, pos))
+    } else {
+        None
+    };
+    if let Some((Token::LeftParenthesis, _)) = parser.current_token() {
+        parser.advance();
+    } else {
+        return Err(parser.expected("expected (", Some(position)));
+    }
+    let (params, types) = typed_params(parser)?;
+    let returns = annotation(parser)?;
+    if let Some((Token::Arrow, _)) = parser.current_token() {
+        parser.advance();
+        let body = expr(parser)?;
+        let pos = position.combine(body.get_pos());
+        Ok(Node::new(
+            NodeType::FuncDefNode(FuncDefNode::new(name, params, body).with_types(types, returns)),
+            pos,
+        ))
+    } else {
+        Err(parser.expected("expected '->'", Some(position)))
+    }
+}
+
+pub fn struct_expr(parser: &mut Parser, position: Position) -> Result<Node, Error> {
+    parser.advance();
+    let name = match parser.current_token() {
+        Some((Token::Ident(name), pos)) => {
+            parser.advance();
+            Variable::new(name, pos)
+        }
+        token => return Err(parser.expected("expected a name", token.map(|(_, pos)| pos))),
+    };
+    match parser.current_token() {
+        Some((Token::LeftCurlyBrackets, _)) => parser.advance(),
+        token => return Err(parser.expected("expected {", token.map(|(_, pos)| pos))),
+    }
+    let mut fields: Vec<Variable> = Vec::new();
+    let mut methods = Vec::new();
+    let mut names: Vec<Ident> = Vec::new();
+    loop {
+        while let Some((Token::Comma, _))
+        | Some((Token::Semicolon, _))
+        | Some((Token::Newline, _)) = parser.current_token()
+        {
+            parser.advance();
+        }
+        let (member, pos) = match parser.current_token() {
+            Some((Token::RightCurlyBrackets, end)) => {
+                parser.advance();
+                let node = StructDefNode::new(name, fields, methods);
+                return Ok(Node::new(NodeType::StructDef(node), position.combine(end)));
+            }
+            Some((Token::Ident(field), pos)) => {
+                parser.advance();
+                fields.push(Variable::new(field.clone(), pos.clone()));
+                (field, pos)
+            }
+            Some((Token::Keyword(Keyword::Fn), pos)) => {
+                let method = fn_expr(parser, pos.clone())?;
+                let (method_name, params, _) = match method.get_type() {
+                    NodeType::FuncDefNode(func) => func.get_all(),
+                    _ => unreachable!(),
+                };
+                let method_name = match method_name {
+                    Some(method_name) => method_name.clone(),
+                    None => return Err(parser.expected("methods need a name", Some(pos))),
+                };
+                if params.first().map(|p| p.get_name().as_str()) != Some("self") {
+                    return Err(Error::new(
+                        ErrorKind::SyntaxError,
+                        "methods take self as their first parameter",
+                        Some(method_name.get_pos()),
+                    ));
+                }
+                methods.push(method);
+                (method_name.get_name().clone(), method_name.get_pos())
+            }
+            token => {
+                return Err(
+                    parser.expected("expected a field, a method or }", token.map(|(_, pos)| pos))
+                )
+            }
+        };
+        if names.contains(&member) {
+            return Err(Error::new(
                 ErrorKind::SyntaxError,
-                "expected 'in'",
-                Some(position),
+                &format!("{} is defined more than once in {}", member, name),
+                Some(pos),
+            ));
+        }
+        names.push(member);
+        if !matches!(
+            parser.current_token(),
+            Some((Token::Comma, _))
+                | Some((Token::Semicolon, _))
+                | Some((Token::Newline, _))
+                | Some((Token::RightCurlyBrackets, _))
+        ) {
+            return Err(parser.expected(
+                "expected , or }",
+                parser.current_token().map(|(_, pos)| pos),
+            ));
+        }
+    }
+}
+
+/// skips the commas, semicolons and newlines between members of a definition
+fn separators(parser: &mut Parser) {
+    while let Some((Token::Comma, _)) | Some((Token::Semicolon, _)) | Some((Token::Newline, _)) =
+        parser.current_token()
+    {
+        parser.advance();
+    }
+}
+
+/// fails if the current token can't follow a member of a definition
+fn member_end(parser: &Parser) -> Result<(), Error> {
+    match parser.current_token() {
+        Some((Token::Comma, _))
+        | Some((Token::Semicolon, _))
+        | Some((Token::Newline, _))
+        | Some((Token::RightCurlyBrackets, _)) => Ok(()),
+        token => Err(parser.expected("expected , or }", token.map(|(_, pos)| pos))),
+    }
+}
+
+pub fn enum_expr(parser: &mut Parser, position: Position) -> Result<Node, Error> {
+    parser.advance();
+    let name = match parser.current_token() {
+        Some((Token::Ident(name), pos)) => {
+            parser.advance();
+            Variable::new(name, pos)
+        }
+        token => return Err(parser.expected("expected a name", token.map(|(_, pos)| pos))),
+    };
+    match parser.current_token() {
+        Some((Token::LeftCurlyBrackets, _)) => parser.advance(),
+        token => return Err(parser.expected("expected {", token.map(|(_, pos)| pos))),
+    }
+    let mut variants: Vec<Variant> = Vec::new();
+    loop {
+        separators(parser);
+        match parser.current_token() {
+            Some((Token::RightCurlyBrackets, end)) => {
+                parser.advance();
+                let node = EnumDefNode::new(name, variants);
+                parser.record_enum(&node);
+                return Ok(Node::new(NodeType::EnumDef(node), position.combine(end)));
+            }
+            Some((Token::Ident(variant), pos)) => {
+                parser.advance();
+                if variants.iter().any(|v| *v.get_name() == variant) {
+                    return Err(Error::new(
+                        ErrorKind::SyntaxError,
+                        &format!("{} is defined more than once in {}", variant, name),
+                        Some(pos),
+                    ));
+                }
+                let fields = match parser.current_token() {
+                    Some((Token::LeftParenthesis, _)) => {
+                        parser.advance();
+                        Some(params(parser)?)
+                    }
+                    _ => None,
+                };
+                variants.push(Variant::new(variant, pos, fields));
+            }
+            token => {
+                return Err(parser.expected("expected a variant or }", token.map(|(_, pos)| pos)))
+            }
+        }
+        member_end(parser)?;
+    }
+}
+
+pub fn match_expr(parser: &mut Parser, position: Position) -> Result<Node, Error> {
+    parser.advance();
+    let value = expr(parser)?;
+    match parser.current_token() {
+        Some((Token::LeftCurlyBrackets, _)) => parser.advance(),
+        token => return Err(parser.expected("expected {", token.map(|(_, pos)| pos))),
+    }
+    let mut arms = Vec::new();
+    loop {
+        separators(parser);
+        if let Some((Token::RightCurlyBrackets, end)) = parser.current_token() {
+            parser.advance();
+            parser.record_match(&arms, position.clone());
+            return Ok(Node::new(
+                NodeType::Match(MatchNode::new(value, arms)),
+                position.combine(end),
+            ));
+        }
+        let pattern = pattern(parser)?;
+        let guard = match parser.current_token() {
+            Some((Token::Keyword(Keyword::If), _)) => {
+                parser.advance();
+                Some(expr(parser)?)
+            }
+            _ => None,
+        };
+        match parser.current_token() {
+            Some((Token::Arrow, _)) => parser.advance(),
+            token => return Err(parser.expected("expected '->'", token.map(|(_, pos)| pos))),
+        }
+        let body = expr(parser)?;
+        arms.push(Arm::new(pattern, guard, body));
+        member_end(parser)?;
+    }
+}
+
+/// parses a pattern which is nested in the current expression or pattern
+fn pattern(parser: &mut Parser) -> Result<Pattern, Error> {
+    parser.depth += 1;
+    let res = parser
+        .check_nesting()
+        .and_then(|_| limits::grow(|| pattern_inner(parser)));
+    parser.depth -= 1;
+    res
+}
+
+fn pattern_inner(parser: &mut Parser) -> Result<Pattern, Error> {
+    match parser.current_token() {
+        Some((Token::Type(ty), pos)) => {
+            parser.advance();
+            match ty {
+                Type::Integer(_) | Type::Float(_) | Type::String(_) => {
+                    Ok(Pattern::Literal(ty, pos))
+                }
+                _ => Err(parser.expected("expected a pattern", Some(pos))),
+            }
+        }
+        Some((Token::Minus, pos)) => {
+            parser.advance();
+            let literal = match parser.current_token() {
+                Some((Token::Type(Type::Integer(v)), end)) => {
+                    (Type::Integer(Integer::new(-v.get_value())), end)
+                }
+                Some((Token::Type(Type::Float(v)), end)) => {
+                    (Type::Float(Float::new(-v.get_value())), end)
+                }
+                token => {
+                    return Err(
+                        parser.expected("expected a number after -", token.map(|(_, pos)| pos))
+                    )
+                }
+            };
+            parser.advance();
+            Ok(Pattern::Literal(literal.0, pos.combine(literal.1)))
+        }
+        Some((Token::Ident(name), pos)) => {
+            parser.advance();
+            if name.as_str() == "_" {
+                return Ok(Pattern::Wildcard(pos));
+            }
+            let mut end = pos.clone();
+            let variant = match parser.current_token() {
+                Some((Token::Dot, _)) => {
+                    parser.advance();
+                    match parser.current_token() {
+                        Some((Token::Ident(variant), variant_pos)) => {
+                            parser.advance();
+                            end = variant_pos.clone();
+                            Some((variant, variant_pos))
+                        }
+                        token => {
+                            return Err(parser
+                                .expected("expected a variant after .", token.map(|(_, pos)| pos)))
+                        }
+                    }
+                }
+                _ => None,
+            };
+            let args = match parser.current_token() {
+                Some((Token::LeftParenthesis, _)) => {
+                    parser.advance();
+                    let (args, _, right) = patterns(parser)?;
+                    end = right;
+                    Some(args)
+                }
+                _ => None,
+            };
+            let var = Variable::new(name, pos.clone());
+            if variant.is_none() && args.is_none() {
+                return Ok(Pattern::Binding(var));
+            }
+            let constructor = Constructor::new(var, variant, args, pos.combine(end));
+            Ok(Pattern::Constructor(constructor))
+        }
+        Some((Token::LeftParenthesis, pos)) => {
+            parser.advance();
+            let (mut patterns, trailing_comma, end) = patterns(parser)?;
+            if patterns.len() == 1 && !trailing_comma {
+                return Ok(patterns.remove(0));
+            }
+            Ok(Pattern::Tuple(patterns, pos.combine(end)))
+        }
+        Some((Token::LeftSquareBrackets, pos)) => {
+            parser.advance();
+            list_pattern(parser, pos)
+        }
+        token => Err(parser.expected("expected a pattern", token.map(|(_, pos)| pos))),
+    }
+}
+
+/// parses the patterns of a list pattern including the closing bracket, one
+/// of them may be the rest, `...name`
+fn list_pattern(parser: &mut Parser, position: Position) -> Result<Pattern, Error> {
+    let mut items = Vec::new();
+    let mut rest = None;
+    loop {
+        match parser.current_token() {
+            Some((Token::RightSquareBrackets, end)) => {
+                parser.advance();
+                let list = ListPattern::new(items, rest, position.combine(end));
+                return Ok(Pattern::List(list));
+            }
+            Some((Token::Ellipsis, pos)) => {
+                parser.advance();
+                if rest.is_some() {
+                    return Err(Error::new(
+                        ErrorKind::SyntaxError,
+                        "a pattern can only have one rest",
+                        Some(pos),
+                    ));
+                }
+                let name = match parser.current_token() {
+                    Some((Token::Ident(name), pos)) => {
+                        parser.advance();
+                        match name.as_str() {
+                            "_" => Pattern::Wildcard(pos),
+                            _ => Pattern::Binding(Variable::new(name, pos)),
+                        }
+                    }
+                    token => {
+                        return Err(
+                            parser.expected("expected a name after ...", token.map(|(_, pos)| pos))
+                        )
+                    }
+                };
+                rest = Some((items.len(), name));
+            }
+            _ => items.push(pattern(parser)?),
+        }
+        match parser.current_token() {
+            Some((Token::Comma, _)) => parser.advance(),
+            Some((Token::RightSquareBrackets, _)) => {}
+            token => return Err(parser.expected("expected , or ]", token.map(|(_, pos)| pos))),
+        }
+    }
+}
+
+/// parses comma separated patterns including the closing parenthesis,
+/// returns whether the last one is followed by a comma and the position of
+/// the parenthesis
+fn patterns(parser: &mut Parser) -> Result<(Vec<Pattern>, bool, Position), Error> {
+    let mut patterns = Vec::new();
+    let mut trailing_comma = false;
+    loop {
+        if let Some((Token::RightParenthesis, end)) = parser.current_token() {
+            parser.advance();
+            return Ok((patterns, trailing_comma, end));
+        }
+        patterns.push(pattern(parser)?);
+        trailing_comma = false;
+        match parser.current_token() {
+            Some((Token::Comma, _)) => {
+                parser.advance();
+                trailing_comma = true;
+            }
+            Some((Token::RightParenthesis, _)) => {}
+            token => return Err(parser.expected("expected , or )", token.map(|(_, pos)| pos))),
+        }
+    }
+}
+
+/// parses the file or name of a module after `import` or `from`
+fn source(parser: &mut Parser) -> Result<(Source, Position), Error> {
+    let source = match parser.current_token() {
+        Some((Token::Type(Type::String(path)), pos)) => (Source::Path(path.to_string()), pos),
+        Some((Token::Ident(name), pos)) => (Source::Name(name), pos),
+        Some((_, pos)) => return Err(parser.expected("expected a module name or path", Some(pos))),
+        None => return Err(parser.expected("expected a module name or path", None)),
+    };
+    parser.advance();
+    Ok(source)
+}
+
+/// parses the name after `as`
+fn alias(parser: &mut Parser) -> Result<Variable, Error> {
+    parser.advance();
+    match parser.current_token() {
+        Some((Token::Ident(name), pos)) => {
+            parser.advance();
+            Ok(Variable::new(name, pos))
+        }
+        Some((_, pos)) => Err(parser.expected("expected a name after 'as'", Some(pos))),
+        None => Err(parser.expected("expected a name after 'as'", None)),
+    }
+}
+
+pub fn import_expr(parser: &mut Parser, position: Position) -> Result<Node, Error> {
+    parser.advance();
+    let (source, source_pos) = source(parser)?;
+    let var = match (parser.current_token(), &source) {
+        (Some((Token::Keyword(Keyword::As), _)), _) => alias(parser)?,
+        (_, Source::Name(name)) => Variable::new(name.clone(), source_pos.clone()),
+        (token, Source::Path(_)) => {
+            return Err(parser.expected(
+                "expected 'as' and a name for the module",
+                token.map(|(_, pos)| pos),
             ))
         }
+    };
+    let pos = position.combine(var.get_pos());
+    Ok(Node::new(
+        NodeType::Import(Import::new(source, source_pos, Imported::Module(var))),
+        pos,
+    ))
+}
+
+pub fn from_expr(parser: &mut Parser, position: Position) -> Result<Node, Error> {
+    parser.advance();
+    let (source, source_pos) = source(parser)?;
+    match parser.current_token() {
+        Some((Token::Keyword(Keyword::Import), _)) => parser.advance(),
+        token => return Err(parser.expected("expected 'import'", token.map(|(_, pos)| pos))),
+    }
+    let mut vars = Vec::new();
+    loop {
+        match parser.current_token() {
+            Some((Token::Ident(name), pos)) => {
+                parser.advance();
+                vars.push(Variable::new(name, pos));
+            }
+            token => return Err(parser.expected("expected a name", token.map(|(_, pos)| pos))),
+        }
+        match parser.current_token() {
+            Some((Token::Comma, _)) => parser.advance(),
+            _ => break,
+        }
+    }
+    let pos = position.combine(vars.last().unwrap().get_pos());
+    Ok(Node::new(
+        NodeType::Import(Import::new(source, source_pos, Imported::Members(vars))),
+        pos,
+    ))
+}
+
+/// parses a comma separated list of parameter names including the closing parenthesis
+/// parses `: TYPE` if it comes next
+fn annotation(parser: &mut Parser) -> Result<Option<Variable>, Error> {
+    if let Some((Token::Colon, pos)) = parser.current_token() {
+        parser.advance();
+        match parser.current_token() {
+            Some((Token::Ident(name), pos)) => {
+                parser.advance();
+                Ok(Some(Variable::new(name, pos)))
+            }
+            token => Err(parser.expected(
+                "expected a type",
+                token.map_or(Some(pos), |(_, pos)| Some(pos)),
+            )),
+        }
     } else {
-        Err(Error::new(
+        Ok(None)
+    }
+}
+
+fn params(parser: &mut Parser) -> Result<Vec<Variable>, Error> {
+    let (params, types) = typed_params(parser)?;
+    match types.into_iter().flatten().next() {
+        Some(ty) => Err(parser.expected("fields can't have types", Some(ty.get_pos()))),
+        None => Ok(params),
+    }
+}
+
+/// parses parameters which may have type annotations
+fn typed_params(parser: &mut Parser) -> Result<(Vec<Variable>, Vec<Option<Variable>>), Error> {
+    let mut params = Vec::new();
+    let mut types = Vec::new();
+    loop {
+        match parser.current_token() {
+            Some((Token::RightParenthesis, _)) if params.is_empty() => break,
+            Some((Token::Ident(name), pos)) => {
+                parser.advance();
+                params.push(Variable::new(name, pos));
+                types.push(annotation(parser)?);
+            }
+            Some((_, pos)) => return Err(parser.expected("expected identifier", Some(pos))),
+            None => return Err(parser.expected("expected identifier", None)),
+        }
+        match parser.current_token() {
+            Some((Token::Comma, _)) => parser.advance(),
+            _ => break,
+        }
+    }
+    match parser.current_token() {
+        Some((Token::RightParenthesis, _)) => {
+            parser.advance();
+            Ok((params, types))
+        }
+        Some((_, pos)) => Err(parser.expected("expected , or )", Some(pos))),
+        None => Err(parser.expected("expected )", None)),
+    }
+}
+
+/// parses statements separated by semicolons or newlines until `end` is reached
+pub fn statements(parser: &mut Parser, end: Token) -> Result<Node, Error> {
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut pos = parser.current_token().map(|(_, pos)| pos);
+    loop {
+        while let Some((Token::Semicolon, _)) | Some((Token::Newline, _)) = parser.current_token() {
+            parser.advance();
+        }
+        match parser.current_token() {
+            Some((token, _)) if token == end || token == Token::EOF => break,
+            None => break,
+            _ => {}
+        }
+        let node = expr(parser)?;
+        pos = match pos {
+            Some(pos) => Some(pos.combine(node.get_pos())),
+            None => Some(node.get_pos()),
+        };
+        nodes.push(node);
+        if !matches!(
+            parser.current_token(),
+            Some((Token::Semicolon, _)) | Some((Token::Newline, _))
+        ) {
+            break;
+        }
+    }
+    match pos {
+        Some(pos) => Ok(Node::new(NodeType::Statements(Statements::new(nodes)), pos)),
+        None => Err(Error::new(
             ErrorKind::SyntaxError,
-            "expected ident",
-            Some(position),
-        ))
+            "can't parse empty token",
+            None,
+        )),
     }
 }
 
 fn comp_expr(parser: &mut Parser) -> Result<Node, Error> {
     if let Some((Token::Bang, pos)) = parser.current_token() {
         parser.advance();
-        let node = comp_expr(parser)?;
+        let node = parser.nested(comp_expr)?;
         Ok(Node::new(
             NodeType::Operation(OperationType::UnaryOperationNode(Box::new(
                 UnaryOperationNode::from_token(Token::Bang, node).unwrap(),
@@ -1,46 +1,213 @@
-use crate::interpreter::{Error, Position, Token};
+use crate::interpreter::{limits, Error, Position, Token};
 
 mod exprs;
 pub mod nodes;
 
 use exprs::*;
 
-use crate::interpreter::ast::nodes::{NodeType, OperationType, UnaryOperationNode};
+use crate::interpreter::ast::nodes::{
+    Arm, AttributeNode, CallNode, EnumDefNode, IndexNode, NodeType, OperationType, Pattern,
+    TupleNode, UnaryOperationNode, Variable,
+};
+use crate::interpreter::token::ident::Ident;
 use crate::interpreter::token::keyword::Keyword;
-use crate::interpreter::ErrorKind::{EndOfFile, SyntaxError};
+use crate::interpreter::ErrorKind::{
+    EndOfFile, MatchError, NameError, RecursionLimit, SyntaxError, TypeError,
+};
 use nodes::{BinaryOperationNode, Node};
+use std::collections::HashMap;
 
 #[derive(Clone)]
 pub struct Parser {
     tokens: Vec<(Token, Position)>,
     index: isize,
+    // the number of nested expressions around the current token
+    depth: usize,
+    // how large `depth` may get
+    nesting: usize,
+    // the variants of the enums parsed so far with their number of fields
+    enums: HashMap<Ident, Vec<(Ident, Option<usize>)>>,
+    // the variants used in patterns with the number of patterns for their fields
+    variants: Vec<(Ident, Ident, Position, Option<usize>)>,
+    // the matches on enums, they are checked once all enums are known, as an
+    // enum may be defined after a function matching on it
+    matches: Vec<Coverage>,
+}
+
+/// The variants of an enum which the arms of a match cover
+#[derive(Clone)]
+struct Coverage {
+    ty: Ident,
+    covered: Vec<Ident>,
+    // an arm matches every value
+    catch_all: bool,
+    pos: Position,
+}
+
+/// error for a pattern with a different number of fields than `name`
+pub fn arity_error(name: &str, fields: usize, patterns: usize, pos: Position) -> Error {
+    let fields = match fields {
+        0 => "no fields".to_string(),
+        1 => "1 field".to_string(),
+        n => format!("{} fields", n),
+    };
+    Error::new(
+        TypeError,
+        &format!("{} has {} but the pattern has {}", name, fields, patterns),
+        Some(pos),
+    )
 }
 
 impl Parser {
     pub fn new(tokens: Vec<(Token, Position)>) -> Self {
-        let mut parser = Self { tokens, index: -1 };
+        let mut parser = Self {
+            tokens,
+            index: -1,
+            depth: 0,
+            nesting: limits::MAX_NESTING,
+            enums: HashMap::new(),
+            variants: Vec::new(),
+            matches: Vec::new(),
+        };
         parser.advance();
         parser
     }
 
+    /// allows expressions to be nested `nesting` times
+    pub fn with_nesting(self, nesting: usize) -> Self {
+        Self { nesting, ..self }
+    }
+
     pub fn parse(&mut self) -> Result<Node, Error> {
-        match expr(self) {
-            Ok(res) => {
-                if let Some(current_token) = self.current_token() {
-                    if current_token.0 != Token::EOF {
-                        return Err(Error::new(
-                            SyntaxError,
-                            "Expected one of the following: '+' '-' '*' or '/'",
-                            Some(current_token.1),
-                        ));
-                    }
+        let res = statements(self, Token::EOF)?;
+        if let Some(current_token) = self.current_token() {
+            if current_token.0 != Token::EOF {
+                return Err(Error::new(
+                    SyntaxError,
+                    "Expected one of the following: '+' '-' '*' or '/'",
+                    Some(current_token.1),
+                ));
+            }
+        }
+        self.check_matches()?;
+        Ok(res)
+    }
+
+    fn record_enum(&mut self, node: &EnumDefNode) {
+        let variants = (node.get_variants().iter())
+            .map(|v| (v.get_name().clone(), v.get_fields().map(<[Variable]>::len)))
+            .collect();
+        self.enums
+            .insert(node.get_name().get_name().clone(), variants);
+    }
+
+    /// remembers the variants the patterns of a match use and which ones
+    /// the arms cover
+    fn record_match(&mut self, arms: &[Arm], pos: Position) {
+        let mut coverage: Option<Coverage> = None;
+        let mut catch_all = false;
+        for arm in arms {
+            let pattern = arm.get_pattern();
+            self.record_variants(pattern);
+            let unguarded = arm.get_guard().is_none();
+            catch_all |= unguarded && pattern.is_irrefutable();
+            let constructor = match pattern {
+                Pattern::Constructor(constructor) => constructor,
+                _ => continue,
+            };
+            if let Some((variant, _)) = constructor.get_variant() {
+                let ty = constructor.get_type().get_name().clone();
+                let coverage = coverage.get_or_insert_with(|| Coverage {
+                    ty: ty.clone(),
+                    covered: Vec::new(),
+                    catch_all: false,
+                    pos: pos.clone(),
+                });
+                let all_values = (constructor.get_args().unwrap_or_default().iter())
+                    .all(Pattern::is_irrefutable);
+                if coverage.ty == ty && unguarded && all_values {
+                    coverage.covered.push(variant.clone());
                 }
-                Ok(res)
             }
-            Err(e) => Err(e),
+        }
+        if let Some(coverage) = coverage {
+            self.matches.push(Coverage {
+                catch_all,
+                ..coverage
+            });
         }
     }
 
+    fn record_variants(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Tuple(patterns, _) => patterns.iter().for_each(|p| self.record_variants(p)),
+            Pattern::List(list) => list
+                .get_items()
+                .iter()
+                .for_each(|p| self.record_variants(p)),
+            Pattern::Constructor(constructor) => {
+                if let Some((variant, pos)) = constructor.get_variant() {
+                    self.variants.push((
+                        constructor.get_type().get_name().clone(),
+                        variant.clone(),
+                        pos.clone(),
+                        constructor.get_args().map(<[Pattern]>::len),
+                    ));
+                }
+                for arg in constructor.get_args().unwrap_or_default() {
+                    self.record_variants(arg);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// fails for variants which don't exist and for matches which miss
+    /// variants of the enum they match on
+    fn check_matches(&self) -> Result<(), Error> {
+        for (ty, variant, pos, patterns) in &self.variants {
+            let variants = match self.enums.get(ty) {
+                Some(variants) => variants,
+                None => continue,
+            };
+            let name = format!("{}.{}", ty, variant);
+            match (variants.iter().find(|(v, _)| v == variant), patterns) {
+                (None, _) => {
+                    return Err(Error::new(
+                        NameError,
+                        &format!("{} has no variant {}", ty, variant),
+                        Some(pos.clone()),
+                    ))
+                }
+                (Some((_, None)), Some(patterns)) => {
+                    return Err(arity_error(&name, 0, *patterns, pos.clone()))
+                }
+                (Some((_, Some(fields))), Some(patterns)) if fields != patterns => {
+                    return Err(arity_error(&name, *fields, *patterns, pos.clone()))
+                }
+                _ => {}
+            }
+        }
+        for coverage in self.matches.iter().filter(|c| !c.catch_all) {
+            let variants = match self.enums.get(&coverage.ty) {
+                Some(variants) => variants,
+                None => continue,
+            };
+            let missing: Vec<String> = (variants.iter())
+                .filter(|(variant, _)| !coverage.covered.contains(variant))
+                .map(|(variant, _)| format!("{}.{}", coverage.ty, variant))
+                .collect();
+            if !missing.is_empty() {
+                return Err(Error::new(
+                    MatchError,
+                    &format!("the match doesn't cover {}", missing.join(", ")),
+                    Some(coverage.pos.clone()),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     fn current_token(&self) -> Option<(Token, Position)> {
         if self.index >= 0 && self.index < self.tokens.len() as isize {
             Some(self.tokens[self.index as usize].clone())
@@ -53,30 +220,67 @@ impl Parser {
         self.index += 1;
     }
 
+    /// the token after the current one
+    fn peek_token(&self) -> Option<Token> {
+        self.tokens
+            .get((self.index + 1) as usize)
+            .map(|(token, _)| token.clone())
+    }
+
+    /// error for a missing token; running out of tokens means the input is
+    /// incomplete rather than wrong, e.g. for an unclosed `{` in the REPL
+    fn expected(&self, message: &str, position: Option<Position>) -> Error {
+        let kind = match self.current_token() {
+            Some((Token::EOF, _)) | None => EndOfFile,
+            _ => SyntaxError,
+        };
+        Error::new(kind, message, position)
+    }
+
+    /// fails if the current expression is nested too deeply
+    fn check_nesting(&self) -> Result<(), Error> {
+        if self.depth > self.nesting {
+            return Err(Error::new(
+                RecursionLimit,
+                &format!("expressions can only be nested {} times", self.nesting),
+                self.current_token().map(|(_, pos)| pos),
+            ));
+        }
+        Ok(())
+    }
+
+    /// parses an expression which is nested in the current one
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<Node, Error>,
+    ) -> Result<Node, Error> {
+        self.depth += 1;
+        let res = self
+            .check_nesting()
+            .and_then(|_| limits::grow(|| parse(self)));
+        self.depth -= 1;
+        res
+    }
+
     fn binary_operation(
         &mut self,
         func: &mut dyn FnMut(&mut Self) -> Result<Node, Error>,
         operations: Vec<Token>,
     ) -> Result<Node, Error> {
-        let mut left = match func(self) {
-            Ok(res) => res,
-            Err(e) => return Err(e),
-        };
+        let mut left = func(self)?;
         while let Some(current_token) = self.current_token() {
             if !operations.contains(&current_token.0) {
                 break;
             }
             let operation = current_token;
             self.advance();
-            let right = match func(self) {
-                Ok(res) => res,
-                Err(e) => return Err(e),
-            };
+            let right = func(self)?;
+            let pos = left.get_pos().combine(right.get_pos());
             left = Node::new(
                 NodeType::Operation(OperationType::BinaryOperationNode(Box::new(
-                    BinaryOperationNode::new(left.clone(), operation.clone().0, right.clone()),
+                    BinaryOperationNode::new(left, operation.0, right),
                 ))),
-                left.get_pos().combine(right.get_pos()),
+                pos,
             );
         }
         Ok(left)
@@ -91,11 +295,18 @@ fn atom(parser: &mut Parser) -> Result<Node, Error> {
         }
         Some((Token::Ident(ident), pos)) => {
             parser.advance();
-            Ok(Node::new(NodeType::Var(ident), pos))
+            Ok(Node::new(
+                NodeType::Var(Variable::new(ident, pos.clone())),
+                pos,
+            ))
+        }
+        Some((Token::Interpolation(segments), pos)) => {
+            parser.advance();
+            interpolation(parser, &segments, pos)
         }
         Some((token, pos)) if token == Token::Minus || token == Token::Plus => {
             parser.advance();
-            match atom(parser) {
+            match parser.nested(call) {
                 Ok(ty) => Ok(Node::new(
                     NodeType::Operation(OperationType::UnaryOperationNode(
                         // unwrap is safe because of the check above (Minus or Plus)
@@ -113,54 +324,229 @@ fn atom(parser: &mut Parser) -> Result<Node, Error> {
         )),
         Some((Token::LeftParenthesis, position)) => {
             parser.advance();
-            match expr(parser) {
-                Ok(expr) => {
-                    if parser
-                        .current_token()
-                        .unwrap_or((Token::EOF, position.clone()))
-                        .0
-                        == Token::RightParenthesis
-                    {
-                        parser.advance();
-                        Ok(expr)
-                    } else {
-                        Err(Error::new(SyntaxError, "expected )", Some(position)))
-                    }
+            if let Some((Token::RightParenthesis, end)) = parser.current_token() {
+                parser.advance();
+                let pos = position.combine(end);
+                return Ok(Node::new(NodeType::Tuple(TupleNode::new(Vec::new())), pos));
+            }
+            let expr = expr(parser)?;
+            match parser.current_token() {
+                Some((Token::RightParenthesis, _)) => {
+                    parser.advance();
+                    Ok(expr)
                 }
-                Err(e) => Err(e),
+                // a comma makes it a tuple, `(a,)` has a single item
+                Some((Token::Comma, _)) => tuple(parser, expr, position),
+                _ => Err(parser.expected("expected )", Some(position))),
             }
         }
         Some((Token::LeftCurlyBrackets, position)) => {
             parser.advance();
-            match expr(parser) {
-                Ok(expr) => {
-                    if parser
-                        .current_token()
-                        .unwrap_or((Token::EOF, position.clone()))
-                        .0
-                        == Token::RightCurlyBrackets
-                    {
-                        parser.advance();
-                        Ok(expr)
-                    } else {
-                        Err(Error::new(SyntaxError, "expected }", Some(position)))
-                    }
+            let statements = statements(parser, Token::RightCurlyBrackets)?;
+            match parser.current_token() {
+                Some((Token::RightCurlyBrackets, end)) => {
+                    parser.advance();
+                    // the block includes its brackets
+                    let mut statements = statements;
+                    statements.set_pos(position.combine(end));
+                    Ok(statements)
                 }
-                Err(e) => Err(e),
+                _ => Err(parser.expected("expected }", Some(position))),
             }
         }
         Some((Token::Keyword(Keyword::If), position)) => if_expr(parser, position),
         Some((Token::Keyword(Keyword::For), position)) => for_expr(parser, position),
         Some((Token::Keyword(Keyword::While), position)) => while_expr(parser, position),
+        Some((Token::Keyword(Keyword::Fn), position)) => fn_expr(parser, position),
+        Some((Token::Keyword(Keyword::Import), position)) => import_expr(parser, position),
+        Some((Token::Keyword(Keyword::From), position)) => from_expr(parser, position),
+        Some((Token::Keyword(Keyword::Struct), position)) => struct_expr(parser, position),
+        Some((Token::Keyword(Keyword::Enum), position)) => enum_expr(parser, position),
+        Some((Token::Keyword(Keyword::Match), position)) => match_expr(parser, position),
         Some((token, position)) => Err(Error::new(
             SyntaxError,
-            &*format!("'{}' is not valid in this context", token),
+            &format!("'{}' is not valid in this context", token),
             Some(position),
         )),
         None => Err(Error::new(SyntaxError, "can't parse empty token", None)),
     }
 }
 
+fn call(parser: &mut Parser) -> Result<Node, Error> {
+    let mut node = atom(parser)?;
+    loop {
+        let position = match parser.current_token() {
+            Some((Token::LeftParenthesis, position)) => position,
+            Some((Token::Dot, _)) => {
+                node = attribute(parser, node)?;
+                continue;
+            }
+            Some((Token::LeftSquareBrackets, position)) => {
+                node = index(parser, node, position)?;
+                continue;
+            }
+            _ => break,
+        };
+        parser.advance();
+        let mut args = Vec::new();
+        let mut named = Vec::new();
+        if !matches!(parser.current_token(), Some((Token::RightParenthesis, _))) {
+            argument(parser, &mut args, &mut named)?;
+            while let Some((Token::Comma, _)) = parser.current_token() {
+                parser.advance();
+                argument(parser, &mut args, &mut named)?;
+            }
+        }
+        match parser.current_token() {
+            Some((Token::RightParenthesis, end)) => {
+                parser.advance();
+                let pos = node.get_pos().combine(end);
+                node = Node::new(NodeType::CallNode(CallNode::new(node, args, named)), pos);
+            }
+            _ => return Err(parser.expected("expected , or )", Some(position))),
+        }
+    }
+    Ok(node)
+}
+
+/// parses the items of a tuple after the first one, including the closing
+/// parenthesis
+fn tuple(parser: &mut Parser, first: Node, position: Position) -> Result<Node, Error> {
+    let mut items = vec![first];
+    while let Some((Token::Comma, _)) = parser.current_token() {
+        parser.advance();
+        if let Some((Token::RightParenthesis, _)) = parser.current_token() {
+            break;
+        }
+        items.push(expr(parser)?);
+    }
+    match parser.current_token() {
+        Some((Token::RightParenthesis, end)) => {
+            parser.advance();
+            let pos = position.combine(end);
+            Ok(Node::new(NodeType::Tuple(TupleNode::new(items)), pos))
+        }
+        _ => Err(parser.expected("expected , or )", Some(position))),
+    }
+}
+
+/// parses a positional argument or one given as `name = value`
+fn argument(
+    parser: &mut Parser,
+    args: &mut Vec<Node>,
+    named: &mut Vec<(Ident, Node)>,
+) -> Result<(), Error> {
+    match parser.current_token() {
+        Some((Token::Ident(name), pos)) if parser.peek_token() == Some(Token::Equal) => {
+            if named.iter().any(|(other, _)| *other == name) {
+                return Err(Error::new(
+                    SyntaxError,
+                    &format!("{} is given more than once", name),
+                    Some(pos),
+                ));
+            }
+            parser.advance();
+            parser.advance();
+            named.push((name, expr(parser)?));
+        }
+        Some((_, pos)) if !named.is_empty() => {
+            return Err(Error::new(
+                SyntaxError,
+                "positional arguments must come before named ones",
+                Some(pos),
+            ))
+        }
+        _ => args.push(expr(parser)?),
+    }
+    Ok(())
+}
+
+/// parses `.name` after `value`
+fn attribute(parser: &mut Parser, value: Node) -> Result<Node, Error> {
+    parser.advance();
+    match parser.current_token() {
+        Some((Token::Ident(name), pos)) => {
+            parser.advance();
+            let node_pos = value.get_pos().combine(pos.clone());
+            Ok(Node::new(
+                NodeType::AttributeNode(AttributeNode::new(value, name, pos)),
+                node_pos,
+            ))
+        }
+        Some((_, pos)) => Err(parser.expected("expected a name after .", Some(pos))),
+        None => Err(parser.expected("expected a name after .", None)),
+    }
+}
+
+/// parses `[index]` after `value`
+fn index(parser: &mut Parser, value: Node, position: Position) -> Result<Node, Error> {
+    parser.advance();
+    let index = expr(parser)?;
+    match parser.current_token() {
+        Some((Token::RightSquareBrackets, end)) => {
+            parser.advance();
+            let pos = value.get_pos().combine(end);
+            Ok(Node::new(
+                NodeType::IndexNode(IndexNode::new(value, index)),
+                pos,
+            ))
+        }
+        _ => Err(parser.expected("expected ]", Some(position))),
+    }
+}
+
 fn term(parser: &mut Parser) -> Result<Node, Error> {
-    parser.binary_operation(&mut atom, vec![Token::Star, Token::Slash])
+    parser.binary_operation(&mut call, vec![Token::Star, Token::Slash])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::ast::nodes::NodeType;
+    use crate::interpreter::{parse, ErrorKind};
+
+    const EXPRESSIONS: &[&str] = &[
+        "fn add(a, b) -> a + b",
+        "let f = fn (x) -> x * 2",
+        "(fn (a) -> a + 1)(2)",
+        "f(1)(2, g(3))",
+        "-(1 + 2) * 3",
+        "if a < b then a else b",
+        "{ let x = 1; x }",
+        "let (a, [b, _]) = (1, list(2, 3))",
+        "\"a{1 + 2}b\"",
+        "math.sqrt(2.5)",
+    ];
+
+    /// the syntax tree of the only statement in `text` printed as code
+    fn display(text: &str) -> String {
+        let ast = parse(text, "test".to_string()).unwrap();
+        match ast.get_type() {
+            NodeType::Statements(statements) => statements.get_nodes()[0].to_string(),
+            _ => panic!("{} isn't a block", ast),
+        }
+    }
+
+    #[test]
+    fn printed_trees_parse_to_the_same_tree() {
+        for text in EXPRESSIONS {
+            let printed = display(text);
+            assert_eq!(display(&printed), printed, "{}", text);
+        }
+        assert_eq!(display("if a then b else c"), "if a then b else c");
+        // the parentheses keep the order of the operations
+        assert_ne!(display("(1 + 2) * 3"), display("1 + 2 * 3"));
+        assert_eq!(display("(1 + 2) * 3"), display("((1 + (2))) * 3"));
+    }
+
+    #[test]
+    fn unfinished_input_is_told_apart_from_wrong_input() {
+        for text in ["{ 1", "fn f(a", "(1 + ", "if a then", "fn (x) ->"] {
+            let e = parse(text, "test".to_string()).err().unwrap();
+            assert_eq!(e.kind(), &ErrorKind::EndOfFile, "{}", text);
+        }
+        for text in ["{ 1 )", "fn f(1) -> 1", "1 +* 2", ") 1"] {
+            let e = parse(text, "test".to_string()).err().unwrap();
+            assert_eq!(e.kind(), &ErrorKind::SyntaxError, "{}", text);
+        }
+    }
 }
@@ -1,8 +1,11 @@
+use std::cell::{Cell, OnceCell};
 use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
 
+use crate::interpreter::stack::Slot;
 use crate::interpreter::token::ident::Ident;
 use crate::interpreter::token::types::Type;
-use crate::interpreter::{Position, Token};
+use crate::interpreter::{limits, Position, Token};
 
 #[derive(Clone)]
 pub struct Node {
@@ -14,57 +17,282 @@ impl Node {
     pub fn new(ty: NodeType, pos: Position) -> Self {
         Self { ty, pos }
     }
-    pub fn get_type(&self) -> NodeType {
-        self.ty.clone()
+    pub fn get_type(&self) -> &NodeType {
+        &self.ty
     }
 
     pub fn get_pos(&self) -> Position {
         self.pos.clone()
     }
+
+    pub fn set_pos(&mut self, pos: Position) {
+        self.pos = pos
+    }
+
+    /// byte offset after the end of the node; the position of nodes like
+    /// `if` doesn't cover all of their parts
+    pub fn end_index(&self) -> usize {
+        match &self.ty {
+            NodeType::Operation(OperationType::BinaryOperationNode(op)) => op.right.end_index(),
+            NodeType::Operation(OperationType::UnaryOperationNode(op)) => op.node.end_index(),
+            NodeType::Assign(assign) => assign.expr.end_index(),
+            NodeType::Destructure(node) => node.expr.end_index(),
+            NodeType::SetAttribute(node) => node.expr.end_index(),
+            NodeType::IfNode(node) => match node.get_else_case() {
+                Some(else_case) => else_case.end_index(),
+                None => node.cases.last().unwrap().1.end_index(),
+            },
+            NodeType::ForNode(node) => node.body.end_index(),
+            NodeType::WhileNode(node) => node.body.end_index(),
+            NodeType::FuncDefNode(node) => node.body.end_index(),
+            _ => (self.pos.index as u64 + self.pos.len()) as usize,
+        }
+    }
+}
+
+impl Drop for Node {
+    // the children are dropped one after another, which is as deep as the
+    // tree, e.g. for a long chain of operators
+    fn drop(&mut self) {
+        let ty = std::mem::replace(&mut self.ty, NodeType::Node(Type::none()));
+        limits::grow(|| drop(ty))
+    }
 }
 
 impl Display for Node {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.ty)
+        limits::grow(|| write!(f, "{}", self.ty))
     }
 }
 
-#[derive(Clone, derive_more::Display)]
+impl Node {
+    /// displays the node so it can be used as part of a bigger expression;
+    /// nodes which would swallow everything after them get parentheses
+    fn operand(&self) -> String {
+        match self.ty {
+            NodeType::Assign(_)
+            | NodeType::Destructure(_)
+            | NodeType::SetAttribute(_)
+            | NodeType::IfNode(_)
+            | NodeType::ForNode(_)
+            | NodeType::WhileNode(_)
+            | NodeType::FuncDefNode(_) => format!("({})", self),
+            _ => self.to_string(),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub enum NodeType {
     Node(Type),
     Operation(OperationType),
     Assign(Assignment),
-    Var(Ident),
+    Destructure(Destructure),
+    Var(Variable),
+    Tuple(TupleNode),
     IfNode(IfNode),
     ForNode(ForNode),
     WhileNode(WhileNode),
+    FuncDefNode(FuncDefNode),
+    CallNode(CallNode),
+    AttributeNode(AttributeNode),
+    IndexNode(IndexNode),
+    SetAttribute(SetAttributeNode),
+    StructDef(StructDefNode),
+    EnumDef(EnumDefNode),
+    Match(MatchNode),
+    Interpolation(Interpolation),
+    Import(Import),
+    Statements(Statements),
 }
 
+impl Display for NodeType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            // `{:?}` keeps the decimal point, so the literal stays a float
+            Self::Node(Type::Float(v)) => write!(f, "{:?}", v.get_value()),
+            Self::Node(Type::String(v)) => write!(f, "\"{}\"", escape(v.get_value())),
+            Self::Node(v) => write!(f, "{}", v),
+            Self::Operation(v) => write!(f, "{}", v),
+            Self::Assign(v) => write!(f, "{}", v),
+            Self::Destructure(v) => write!(f, "{}", v),
+            Self::Var(v) => write!(f, "{}", v),
+            Self::Tuple(v) => write!(f, "{}", v),
+            Self::IfNode(v) => write!(f, "{}", v),
+            Self::ForNode(v) => write!(f, "{}", v),
+            Self::WhileNode(v) => write!(f, "{}", v),
+            Self::FuncDefNode(v) => write!(f, "{}", v),
+            Self::CallNode(v) => write!(f, "{}", v),
+            Self::AttributeNode(v) => write!(f, "{}", v),
+            Self::IndexNode(v) => write!(f, "{}", v),
+            Self::SetAttribute(v) => write!(f, "{}", v),
+            Self::StructDef(v) => write!(f, "{}", v),
+            Self::EnumDef(v) => write!(f, "{}", v),
+            Self::Match(v) => write!(f, "{}", v),
+            Self::Interpolation(v) => write!(f, "{}", v),
+            Self::Import(v) => write!(f, "{}", v),
+            Self::Statements(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// escapes the text of a string, so it can be lexed again
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '{' => escaped.push_str("{{"),
+            '}' => escaped.push_str("}}"),
+            '\'' => escaped.push(c),
+            c => escaped.extend(c.escape_debug()),
+        }
+    }
+    escaped
+}
+
+/// A name in the source together with the slot the resolver found for it
 #[derive(Clone)]
-pub struct Assignment {
+pub struct Variable {
     name: Ident,
+    pos: Position,
+    slot: Cell<Option<Slot>>,
+}
+
+impl Variable {
+    pub fn new(name: Ident, pos: Position) -> Self {
+        Self {
+            name,
+            pos,
+            slot: Cell::new(None),
+        }
+    }
+
+    pub fn get_name(&self) -> &Ident {
+        &self.name
+    }
+
+    pub fn get_pos(&self) -> Position {
+        self.pos.clone()
+    }
+
+    pub fn get_slot(&self) -> Option<Slot> {
+        self.slot.get()
+    }
+
+    pub fn set_slot(&self, slot: Slot) {
+        self.slot.set(Some(slot))
+    }
+}
+
+impl Display for Variable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// writes `name` with its type annotation if it has one
+fn annotated(f: &mut Formatter<'_>, name: &dyn Display, ty: Option<&Variable>) -> fmt::Result {
+    match ty {
+        Some(ty) => write!(f, "{}: {}", name, ty),
+        None => write!(f, "{}", name),
+    }
+}
+
+#[derive(Clone)]
+pub struct Assignment {
+    name: Variable,
+    // the type annotation, only used by the checker
+    ty: Option<Box<Variable>>,
     expr: Box<Node>,
 }
 
 impl Assignment {
-    pub fn new(name: Ident, expr: Node) -> Self {
+    pub fn new(name: Variable, expr: Node) -> Self {
         Self {
             name,
+            ty: None,
             expr: Box::new(expr),
         }
     }
 
-    pub fn get_name(&self) -> Ident {
-        self.name.clone()
+    pub fn with_type(mut self, ty: Option<Variable>) -> Self {
+        self.ty = ty.map(Box::new);
+        self
+    }
+
+    pub fn get_var(&self) -> &Variable {
+        &self.name
+    }
+    pub fn get_type(&self) -> Option<&Variable> {
+        self.ty.as_deref()
     }
-    pub fn get_expr(&self) -> Box<Node> {
-        self.expr.clone()
+    pub fn get_expr(&self) -> &Node {
+        &self.expr
     }
 }
 
 impl Display for Assignment {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}={}", self.name, self.expr)
+        write!(f, "let ")?;
+        annotated(f, &self.name, self.ty.as_deref())?;
+        write!(f, " = {}", self.expr)
+    }
+}
+
+/// `let (x, y) = expr`, assigns the parts of the value to the names in the
+/// pattern
+#[derive(Clone)]
+pub struct Destructure {
+    pattern: Pattern,
+    expr: Box<Node>,
+}
+
+impl Destructure {
+    pub fn new(pattern: Pattern, expr: Node) -> Self {
+        Self {
+            pattern,
+            expr: Box::new(expr),
+        }
+    }
+
+    pub fn get_pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    pub fn get_expr(&self) -> &Node {
+        &self.expr
+    }
+}
+
+impl Display for Destructure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "let {} = {}", self.pattern, self.expr)
+    }
+}
+
+/// `(a, b)`, a tuple of the values of the expressions
+#[derive(Clone)]
+pub struct TupleNode {
+    items: Vec<Node>,
+}
+
+impl TupleNode {
+    pub fn new(items: Vec<Node>) -> Self {
+        Self { items }
+    }
+
+    pub fn get_items(&self) -> &[Node] {
+        &self.items
+    }
+}
+
+impl Display for TupleNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let items: Vec<String> = self.items.iter().map(|i| i.to_string()).collect();
+        match items.len() {
+            1 => write!(f, "({},)", items[0]),
+            _ => write!(f, "({})", items.join(", ")),
+        }
     }
 }
 
@@ -81,64 +309,64 @@ impl IfNode {
             else_case: Box::new(else_case),
         }
     }
-    pub fn get_cases(&self) -> Vec<(Node, Node)> {
-        self.cases.clone()
+    pub fn get_cases(&self) -> &[(Node, Node)] {
+        &self.cases
     }
-    pub fn get_else_case(&self) -> Box<Option<Node>> {
-        self.else_case.clone()
+    pub fn get_else_case(&self) -> Option<&Node> {
+        self.else_case.as_ref().as_ref()
     }
 }
 
 impl Display for IfNode {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let mut cases = format!("if {} then {}", self.cases[0].1, self.cases[0].1);
-        let mut it = self.cases.iter();
-        it.next();
-        for (case, expr) in it {
-            cases = format!("{}\n   elif {} then {}", cases, case, expr);
+        for (i, (condition, expr)) in self.cases.iter().enumerate() {
+            let keyword = if i == 0 { "if" } else { " elif" };
+            // only the last expression may run until the end
+            let expr = if i + 1 < self.cases.len() || self.else_case.is_some() {
+                expr.operand()
+            } else {
+                expr.to_string()
+            };
+            write!(f, "{} {} then {}", keyword, condition.operand(), expr)?;
         }
-        if let Some(else_case) = *self.else_case.clone() {
-            cases = format!("{}\n   else {}", cases, else_case);
+        if let Some(else_case) = self.get_else_case() {
+            write!(f, " else {}", else_case)?;
         }
-        write!(f, "{}", cases)
+        Ok(())
     }
 }
 
 #[derive(Clone)]
 pub struct ForNode {
-    var_name: Ident,
+    var_name: Variable,
     start: Box<Node>,
-    end: Box<Node>,
+    /// without an end the loop iterates over the items of `start`
+    end: Option<Box<Node>>,
     body: Box<Node>,
 }
 
 impl ForNode {
-    pub fn new(var_name: Ident, start: Node, end: Node, body: Node) -> Self {
+    pub fn new(var_name: Variable, start: Node, end: Option<Node>, body: Node) -> Self {
         Self {
             var_name,
             start: Box::new(start),
-            end: Box::new(end),
+            end: end.map(Box::new),
             body: Box::new(body),
         }
     }
 
-    pub fn get_all(self) -> (Ident, Node, Node, Node) {
-        (
-            self.var_name.clone(),
-            *self.start.clone(),
-            *self.end.clone(),
-            *self.body,
-        )
+    pub fn get_all(&self) -> (&Variable, &Node, Option<&Node>, &Node) {
+        (&self.var_name, &self.start, self.end.as_deref(), &self.body)
     }
 }
 
 impl Display for ForNode {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "for {} in {} to {}\n\t do {}",
-            self.var_name, self.start, self.end, self.body
-        )
+        write!(f, "for {} in {}", self.var_name, self.start.operand())?;
+        if let Some(end) = &self.end {
+            write!(f, " to {}", end.operand())?;
+        }
+        write!(f, " then {}", self.body)
     }
 }
 
@@ -156,14 +384,769 @@ impl WhileNode {
         }
     }
 
-    pub fn get_all(self) -> (Node, Node) {
-        (*self.cond.clone(), *self.body)
+    pub fn get_all(&self) -> (&Node, &Node) {
+        (&self.cond, &self.body)
     }
 }
 
 impl Display for WhileNode {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "while {} \n\tdo {}", self.cond, self.body)
+        write!(f, "while {} then {}", self.cond.operand(), self.body)
+    }
+}
+
+#[derive(Clone)]
+pub struct FuncDefNode {
+    name: Option<Variable>,
+    params: Vec<Variable>,
+    // the type annotations of the parameters and of the result
+    types: Vec<Option<Variable>>,
+    returns: Option<Box<Variable>>,
+    body: Rc<Node>,
+    locals: OnceCell<Rc<[Ident]>>,
+}
+
+impl FuncDefNode {
+    pub fn new(name: Option<Variable>, params: Vec<Variable>, body: Node) -> Self {
+        Self {
+            name,
+            types: vec![None; params.len()],
+            params,
+            returns: None,
+            body: Rc::new(body),
+            locals: OnceCell::new(),
+        }
+    }
+
+    /// `types` has one entry per parameter
+    pub fn with_types(mut self, types: Vec<Option<Variable>>, returns: Option<Variable>) -> Self {
+        self.types = types;
+        self.returns = returns.map(Box::new);
+        self
+    }
+
+    pub fn get_all(&self) -> (Option<&Variable>, &[Variable], &Rc<Node>) {
+        (self.name.as_ref(), &self.params, &self.body)
+    }
+
+    pub fn get_types(&self) -> (&[Option<Variable>], Option<&Variable>) {
+        (&self.types, self.returns.as_deref())
+    }
+
+    pub fn signature(&self) -> Signature<'_> {
+        Signature(self)
+    }
+
+    /// the names of all slots a call of this function needs, starting with the parameters
+    pub fn get_locals(&self) -> Option<Rc<[Ident]>> {
+        self.locals.get().cloned()
+    }
+
+    pub fn set_locals(&self, locals: Vec<Ident>) {
+        let _ = self.locals.set(locals.into());
+    }
+}
+
+/// `fn name(params): returns`, everything before the arrow
+pub struct Signature<'a>(&'a FuncDefNode);
+
+impl Display for Signature<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let func = self.0;
+        match &func.name {
+            Some(name) => write!(f, "fn {}(", name)?,
+            None => write!(f, "fn (")?,
+        }
+        for (i, (param, ty)) in func.params.iter().zip(&func.types).enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            annotated(f, param, ty.as_ref())?;
+        }
+        write!(f, ")")?;
+        match &func.returns {
+            Some(returns) => write!(f, ": {}", returns),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for FuncDefNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -> {}", self.signature(), self.body)
+    }
+}
+
+#[derive(Clone)]
+pub struct CallNode {
+    callee: Box<Node>,
+    args: Vec<Node>,
+    // arguments given as `name = value`, they follow the positional ones
+    named: Vec<(Ident, Node)>,
+}
+
+impl CallNode {
+    pub fn new(callee: Node, args: Vec<Node>, named: Vec<(Ident, Node)>) -> Self {
+        Self {
+            callee: Box::new(callee),
+            args,
+            named,
+        }
+    }
+
+    pub fn get_all(&self) -> (&Node, &[Node]) {
+        (&self.callee, &self.args)
+    }
+
+    pub fn get_named(&self) -> &[(Ident, Node)] {
+        &self.named
+    }
+}
+
+impl Display for CallNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let args = self.args.iter().map(|a| a.to_string());
+        let named = (self.named.iter()).map(|(name, value)| format!("{} = {}", name, value));
+        let args: Vec<String> = args.chain(named).collect();
+        write!(f, "{}({})", self.callee.operand(), args.join(", "))
+    }
+}
+
+/// `value.name`, e.g. a member of a module
+#[derive(Clone)]
+pub struct AttributeNode {
+    value: Box<Node>,
+    name: Ident,
+    // the position of the name
+    pos: Position,
+}
+
+impl AttributeNode {
+    pub fn new(value: Node, name: Ident, pos: Position) -> Self {
+        Self {
+            value: Box::new(value),
+            name,
+            pos,
+        }
+    }
+
+    pub fn get_value(&self) -> &Node {
+        &self.value
+    }
+
+    pub fn get_name(&self) -> &Ident {
+        &self.name
+    }
+
+    pub fn get_pos(&self) -> Position {
+        self.pos.clone()
+    }
+}
+
+impl Display for AttributeNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.value.operand(), self.name)
+    }
+}
+
+/// `value[index]`, an item of a list, a char of a string or what the
+/// `__getitem__` method of an object returns
+#[derive(Clone)]
+pub struct IndexNode {
+    value: Box<Node>,
+    index: Box<Node>,
+}
+
+impl IndexNode {
+    pub fn new(value: Node, index: Node) -> Self {
+        Self {
+            value: Box::new(value),
+            index: Box::new(index),
+        }
+    }
+
+    pub fn get_all(&self) -> (&Node, &Node) {
+        (&self.value, &self.index)
+    }
+}
+
+impl Display for IndexNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]", self.value.operand(), self.index)
+    }
+}
+
+/// `value.name = expr`, changes a field of an object
+#[derive(Clone)]
+pub struct SetAttributeNode {
+    attribute: AttributeNode,
+    expr: Box<Node>,
+}
+
+impl SetAttributeNode {
+    pub fn new(attribute: AttributeNode, expr: Node) -> Self {
+        Self {
+            attribute,
+            expr: Box::new(expr),
+        }
+    }
+
+    pub fn get_attribute(&self) -> &AttributeNode {
+        &self.attribute
+    }
+
+    pub fn get_expr(&self) -> &Node {
+        &self.expr
+    }
+}
+
+impl Display for SetAttributeNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.attribute, self.expr)
+    }
+}
+
+/// `struct Point { x, y; fn norm(self) -> ... }`, the methods are named
+/// function definitions
+#[derive(Clone)]
+pub struct StructDefNode {
+    name: Variable,
+    fields: Vec<Variable>,
+    methods: Vec<Node>,
+}
+
+impl StructDefNode {
+    pub fn new(name: Variable, fields: Vec<Variable>, methods: Vec<Node>) -> Self {
+        Self {
+            name,
+            fields,
+            methods,
+        }
+    }
+
+    pub fn get_name(&self) -> &Variable {
+        &self.name
+    }
+
+    pub fn get_fields(&self) -> &[Variable] {
+        &self.fields
+    }
+
+    pub fn get_methods(&self) -> &[Node] {
+        &self.methods
+    }
+
+    /// the definitions of the methods
+    pub fn get_funcs(&self) -> impl Iterator<Item = &FuncDefNode> {
+        self.methods
+            .iter()
+            .filter_map(|method| match method.get_type() {
+                NodeType::FuncDefNode(func) => Some(func),
+                _ => None,
+            })
+    }
+}
+
+impl Display for StructDefNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let fields: Vec<String> = self.fields.iter().map(|f| f.to_string()).collect();
+        let members = (!fields.is_empty()).then(|| fields.join(", "));
+        let members: Vec<String> = members
+            .into_iter()
+            .chain(self.methods.iter().map(|m| m.to_string()))
+            .collect();
+        if members.is_empty() {
+            return write!(f, "struct {} {{}}", self.name);
+        }
+        write!(f, "struct {} {{ {} }}", self.name, members.join("; "))
+    }
+}
+
+/// A variant of an enum, `Circle(r)`. Variants without parentheses have no
+/// fields and are values themselves
+#[derive(Clone)]
+pub struct Variant {
+    name: Ident,
+    pos: Position,
+    fields: Option<Vec<Variable>>,
+}
+
+impl Variant {
+    pub fn new(name: Ident, pos: Position, fields: Option<Vec<Variable>>) -> Self {
+        Self { name, pos, fields }
+    }
+
+    pub fn get_name(&self) -> &Ident {
+        &self.name
+    }
+
+    pub fn get_pos(&self) -> Position {
+        self.pos.clone()
+    }
+
+    pub fn get_fields(&self) -> Option<&[Variable]> {
+        self.fields.as_deref()
+    }
+}
+
+impl Display for Variant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(fields) = &self.fields {
+            let fields: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+            write!(f, "({})", fields.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// `enum Shape { Circle(r), Rect(w, h), Empty }`
+#[derive(Clone)]
+pub struct EnumDefNode {
+    name: Variable,
+    variants: Vec<Variant>,
+}
+
+impl EnumDefNode {
+    pub fn new(name: Variable, variants: Vec<Variant>) -> Self {
+        Self { name, variants }
+    }
+
+    pub fn get_name(&self) -> &Variable {
+        &self.name
+    }
+
+    pub fn get_variants(&self) -> &[Variant] {
+        &self.variants
+    }
+}
+
+impl Display for EnumDefNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.variants.is_empty() {
+            return write!(f, "enum {} {{}}", self.name);
+        }
+        let variants: Vec<String> = self.variants.iter().map(|v| v.to_string()).collect();
+        write!(f, "enum {} {{ {} }}", self.name, variants.join(", "))
+    }
+}
+
+/// What a value is compared with in an arm of a match
+#[derive(Clone)]
+pub enum Pattern {
+    /// `_`, matches everything
+    Wildcard(Position),
+    /// a name, matches everything and assigns the value to it
+    Binding(Variable),
+    /// a number or a string, matches values of the same type which are equal
+    Literal(Type, Position),
+    /// `(a, b)`, matches tuples and lists with as many items
+    Tuple(Vec<Pattern>, Position),
+    /// `[first, ...rest]`
+    List(ListPattern),
+    /// `Point(x, y)`, `Shape.Circle(r)` or `Color.Red`
+    Constructor(Constructor),
+}
+
+impl Pattern {
+    pub fn get_pos(&self) -> Position {
+        match self {
+            Self::Wildcard(pos) | Self::Literal(_, pos) | Self::Tuple(_, pos) => pos.clone(),
+            Self::Binding(var) => var.get_pos(),
+            Self::Constructor(constructor) => constructor.pos.clone(),
+            Self::List(list) => list.pos.clone(),
+        }
+    }
+
+    /// the pattern matches every value
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Self::Wildcard(_) | Self::Binding(_))
+    }
+
+    /// the names the pattern assigns
+    pub fn get_bindings(&self) -> Vec<&Variable> {
+        match self {
+            Self::Binding(var) => vec![var],
+            Self::Tuple(patterns, _) => patterns.iter().flat_map(Pattern::get_bindings).collect(),
+            Self::List(list) => (list.items.iter().chain(list.get_rest()))
+                .flat_map(Pattern::get_bindings)
+                .collect(),
+            Self::Constructor(constructor) => (constructor.args.iter().flatten())
+                .flat_map(Pattern::get_bindings)
+                .collect(),
+            Self::Wildcard(_) | Self::Literal(..) => Vec::new(),
+        }
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Wildcard(_) => write!(f, "_"),
+            Self::Binding(var) => write!(f, "{}", var),
+            Self::Literal(ty, _) => write!(f, "{}", NodeType::Node(ty.clone())),
+            Self::Tuple(patterns, _) => {
+                let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+                match patterns.len() {
+                    1 => write!(f, "({},)", patterns[0]),
+                    _ => write!(f, "({})", patterns.join(", ")),
+                }
+            }
+            Self::Constructor(constructor) => write!(f, "{}", constructor),
+            Self::List(list) => write!(f, "{}", list),
+        }
+    }
+}
+
+/// A pattern which matches tuples and lists. With a rest it matches
+/// sequences with at least as many items as the other patterns, the rest
+/// gets a list of the items between them
+#[derive(Clone)]
+pub struct ListPattern {
+    items: Vec<Pattern>,
+    // the number of patterns in front of the rest and the rest itself, a
+    // name or `_`
+    rest: Option<(usize, Box<Pattern>)>,
+    pos: Position,
+}
+
+impl ListPattern {
+    pub fn new(items: Vec<Pattern>, rest: Option<(usize, Pattern)>, pos: Position) -> Self {
+        Self {
+            items,
+            rest: rest.map(|(index, rest)| (index, Box::new(rest))),
+            pos,
+        }
+    }
+
+    /// the patterns besides the rest
+    pub fn get_items(&self) -> &[Pattern] {
+        &self.items
+    }
+
+    pub fn get_rest(&self) -> Option<&Pattern> {
+        self.rest.as_ref().map(|(_, rest)| rest.as_ref())
+    }
+
+    /// the items for the patterns in front of the rest, the rest and the
+    /// items for the patterns after it, None if the number of items is wrong
+    pub fn split<'a>(&self, values: &'a [Type]) -> Option<(&'a [Type], &'a [Type], &'a [Type])> {
+        match &self.rest {
+            None if values.len() == self.items.len() => Some((values, &[], &[])),
+            Some((index, _)) if values.len() >= self.items.len() => {
+                let after = values.len() - (self.items.len() - index);
+                Some((&values[..*index], &values[*index..after], &values[after..]))
+            }
+            _ => None,
+        }
+    }
+
+    /// how many values the pattern needs, e.g. `at least 2 values`
+    pub fn describe_arity(&self) -> String {
+        let count = match self.items.len() {
+            1 => "1 value".to_string(),
+            n => format!("{} values", n),
+        };
+        match self.rest {
+            Some(_) => format!("at least {}", count),
+            None => count,
+        }
+    }
+}
+
+impl Display for ListPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut items: Vec<String> = self.items.iter().map(|p| p.to_string()).collect();
+        if let Some((index, rest)) = &self.rest {
+            items.insert(*index, format!("...{}", rest));
+        }
+        write!(f, "[{}]", items.join(", "))
+    }
+}
+
+/// A pattern which matches objects of a struct or a variant of an enum by
+/// the values of their fields
+#[derive(Clone)]
+pub struct Constructor {
+    ty: Variable,
+    // the variant for enums, with its position
+    variant: Option<(Ident, Position)>,
+    args: Option<Vec<Pattern>>,
+    pos: Position,
+}
+
+impl Constructor {
+    pub fn new(
+        ty: Variable,
+        variant: Option<(Ident, Position)>,
+        args: Option<Vec<Pattern>>,
+        pos: Position,
+    ) -> Self {
+        Self {
+            ty,
+            variant,
+            args,
+            pos,
+        }
+    }
+
+    /// the struct or enum
+    pub fn get_type(&self) -> &Variable {
+        &self.ty
+    }
+
+    pub fn get_variant(&self) -> Option<&(Ident, Position)> {
+        self.variant.as_ref()
+    }
+
+    /// the patterns for the fields, None without parentheses
+    pub fn get_args(&self) -> Option<&[Pattern]> {
+        self.args.as_deref()
+    }
+
+    pub fn get_pos(&self) -> Position {
+        self.pos.clone()
+    }
+}
+
+impl Display for Constructor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.ty)?;
+        if let Some((variant, _)) = &self.variant {
+            write!(f, ".{}", variant)?;
+        }
+        if let Some(args) = &self.args {
+            let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            write!(f, "({})", args.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// `pattern if guard -> body`
+#[derive(Clone)]
+pub struct Arm {
+    pattern: Pattern,
+    guard: Option<Node>,
+    body: Node,
+}
+
+impl Arm {
+    pub fn new(pattern: Pattern, guard: Option<Node>, body: Node) -> Self {
+        Self {
+            pattern,
+            guard,
+            body,
+        }
+    }
+
+    pub fn get_pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    pub fn get_guard(&self) -> Option<&Node> {
+        self.guard.as_ref()
+    }
+
+    pub fn get_body(&self) -> &Node {
+        &self.body
+    }
+}
+
+impl Display for Arm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pattern)?;
+        if let Some(guard) = &self.guard {
+            write!(f, " if {}", guard.operand())?;
+        }
+        write!(f, " -> {}", self.body)
+    }
+}
+
+/// `match value { pattern -> expr, ... }`, the first arm whose pattern
+/// matches the value is run
+#[derive(Clone)]
+pub struct MatchNode {
+    value: Box<Node>,
+    arms: Vec<Arm>,
+}
+
+impl MatchNode {
+    pub fn new(value: Node, arms: Vec<Arm>) -> Self {
+        Self {
+            value: Box::new(value),
+            arms,
+        }
+    }
+
+    pub fn get_value(&self) -> &Node {
+        &self.value
+    }
+
+    pub fn get_arms(&self) -> &[Arm] {
+        &self.arms
+    }
+}
+
+impl Display for MatchNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.arms.is_empty() {
+            return write!(f, "match {} {{}}", self.value.operand());
+        }
+        let arms: Vec<String> = self.arms.iter().map(|a| a.to_string()).collect();
+        write!(
+            f,
+            "match {} {{ {} }}",
+            self.value.operand(),
+            arms.join("; ")
+        )
+    }
+}
+
+#[derive(Clone)]
+pub enum Part {
+    Text(String, Position),
+    Expr(Box<Node>),
+}
+
+/// A string with expressions in braces, `"a {b} c"`
+#[derive(Clone)]
+pub struct Interpolation {
+    parts: Vec<Part>,
+}
+
+impl Interpolation {
+    pub fn new(parts: Vec<Part>) -> Self {
+        Self { parts }
+    }
+
+    pub fn get_parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    /// the embedded expressions
+    pub fn get_exprs(&self) -> impl Iterator<Item = &Node> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Expr(node) => Some(node.as_ref()),
+            Part::Text(..) => None,
+        })
+    }
+}
+
+impl Display for Interpolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "\"")?;
+        for part in &self.parts {
+            match part {
+                Part::Text(text, _) => write!(f, "{}", escape(text))?,
+                Part::Expr(node) => write!(f, "{{{}}}", node)?,
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+/// Where a module comes from: a file or a name which is looked up in the
+/// builtin modules and the search paths
+#[derive(Clone)]
+pub enum Source {
+    Path(String),
+    Name(Ident),
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Path(path) => write!(f, "\"{}\"", escape(path)),
+            Self::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// What an import binds
+#[derive(Clone)]
+pub enum Imported {
+    /// the module itself, `import "a.phx" as a`
+    Module(Variable),
+    /// some of its members, `from a import b, c`
+    Members(Vec<Variable>),
+}
+
+#[derive(Clone)]
+pub struct Import {
+    source: Source,
+    // the position of the source
+    pos: Position,
+    imported: Imported,
+}
+
+impl Import {
+    pub fn new(source: Source, pos: Position, imported: Imported) -> Self {
+        Self {
+            source,
+            pos,
+            imported,
+        }
+    }
+
+    pub fn get_source(&self) -> &Source {
+        &self.source
+    }
+
+    pub fn get_pos(&self) -> Position {
+        self.pos.clone()
+    }
+
+    pub fn get_imported(&self) -> &Imported {
+        &self.imported
+    }
+
+    /// the variables the import assigns
+    pub fn get_vars(&self) -> &[Variable] {
+        match &self.imported {
+            Imported::Module(var) => std::slice::from_ref(var),
+            Imported::Members(vars) => vars,
+        }
+    }
+}
+
+impl Display for Import {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.imported {
+            Imported::Module(var) => match &self.source {
+                Source::Name(name) if name == var.get_name() => write!(f, "import {}", name),
+                source => write!(f, "import {} as {}", source, var),
+            },
+            Imported::Members(vars) => {
+                let vars: Vec<String> = vars.iter().map(|var| var.to_string()).collect();
+                write!(f, "from {} import {}", self.source, vars.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Statements {
+    nodes: Vec<Node>,
+}
+
+impl Statements {
+    pub fn new(nodes: Vec<Node>) -> Self {
+        Self { nodes }
+    }
+
+    pub fn get_nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+}
+
+impl Display for Statements {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.nodes.is_empty() {
+            return write!(f, "{{}}");
+        }
+        let nodes: Vec<String> = self.nodes.iter().map(|n| n.to_string()).collect();
+        write!(f, "{{ {} }}", nodes.join("; "))
     }
 }
 
@@ -198,22 +1181,28 @@ impl BinaryOperationNode {
         }
     }
 
-    pub fn get_left(&self) -> Node {
-        self.left.clone()
+    pub fn get_left(&self) -> &Node {
+        &self.left
     }
 
-    pub fn get_right(&self) -> Node {
-        self.right.clone()
+    pub fn get_right(&self) -> &Node {
+        &self.right
     }
 
-    pub fn get_operation(&self) -> Token {
-        self.operation.clone()
+    pub fn get_operation(&self) -> &Token {
+        &self.operation
     }
 }
 
 impl Display for BinaryOperationNode {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}{}{}", self.left, self.operation, self.right)
+        write!(
+            f,
+            "({} {} {})",
+            self.left.operand(),
+            self.operation,
+            self.right.operand()
+        )
     }
 }
 
@@ -256,12 +1245,12 @@ pub struct UnaryOperationNode {
 }
 
 impl UnaryOperationNode {
-    pub fn get_node(&self) -> Node {
-        self.node.clone()
+    pub fn get_node(&self) -> &Node {
+        &self.node
     }
 
-    pub fn get_operation(&self) -> UnaryOperation {
-        self.operation.clone()
+    pub fn get_operation(&self) -> &UnaryOperation {
+        &self.operation
     }
 
     pub fn from_token(t: Token, node: Node) -> Option<Self> {
@@ -274,6 +1263,6 @@ impl UnaryOperationNode {
 
 impl Display for UnaryOperationNode {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}{}", self.operation, self.node)
+        write!(f, "{}{}", self.operation, self.node.operand())
     }
 }
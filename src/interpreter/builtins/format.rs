@@ -0,0 +1,153 @@
+use crate::interpreter::{Error, ErrorKind, Position, Type};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// How a value is formatted: `[[fill]align][width][.precision]`
+struct Spec {
+    fill: char,
+    align: Option<Align>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+fn invalid(message: &str, pos: &Position) -> Error {
+    Error::new(ErrorKind::ValueError, message, Some(pos.clone()))
+}
+
+fn align(c: char) -> Option<Align> {
+    match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    }
+}
+
+impl Spec {
+    fn parse(spec: &str, pos: &Position) -> Result<Self, Error> {
+        let mut chars: Vec<char> = spec.chars().collect();
+        let (fill, align) = match chars.as_slice() {
+            [fill, c, ..] if align(*c).is_some() => {
+                let res = (*fill, align(*c));
+                chars.drain(..2);
+                res
+            }
+            [c, ..] if align(*c).is_some() => {
+                let res = (' ', align(*c));
+                chars.remove(0);
+                res
+            }
+            _ => (' ', None),
+        };
+        let rest: String = chars.into_iter().collect();
+        let (width, precision) = match rest.split_once('.') {
+            Some((width, precision)) => (width, Some(precision)),
+            None => (rest.as_str(), None),
+        };
+        let number = |text: &str| {
+            if text.chars().all(|c| c.is_ascii_digit()) {
+                text.parse().ok()
+            } else {
+                None
+            }
+        };
+        let invalid_spec = || invalid(&format!("invalid format spec {}", spec), pos);
+        let width = match width {
+            "" => 0,
+            width => number(width).ok_or_else(invalid_spec)?,
+        };
+        let precision = match precision {
+            Some(precision) => Some(number(precision).ok_or_else(invalid_spec)?),
+            None => None,
+        };
+        Ok(Self {
+            fill,
+            align,
+            width,
+            precision,
+        })
+    }
+
+    fn apply(&self, value: &Type) -> String {
+        let text = match (value, self.precision) {
+            (Type::Float(v), Some(precision)) => format!("{:.*}", precision, v.get_value()),
+            (Type::Integer(v), Some(precision)) => {
+                format!("{:.*}", precision, v.get_value() as f64)
+            }
+            (value, Some(precision)) => value.to_string().chars().take(precision).collect(),
+            (value, None) => value.to_string(),
+        };
+        let len = text.chars().count();
+        if len >= self.width {
+            return text;
+        }
+        let is_number = matches!(value, Type::Integer(_) | Type::Float(_));
+        let default = if is_number { Align::Right } else { Align::Left };
+        let padding = self.width - len;
+        let (left, right) = match self.align.unwrap_or(default) {
+            Align::Left => (0, padding),
+            Align::Right => (padding, 0),
+            Align::Center => (padding / 2, padding - padding / 2),
+        };
+        let fill = |n| self.fill.to_string().repeat(n);
+        format!("{}{}{}", fill(left), text, fill(right))
+    }
+}
+
+/// the inside of the field at the start of `text`, which starts with `{`:
+/// `{}`, `{1}`, `{:.2}` or `{1:>4}`. The lexer keeps these in strings for
+/// `format`, every other brace is text for both of them
+pub fn field(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix('{')?;
+    let end = rest.find(['}', '\n', '"'])?;
+    let field = &rest[..end];
+    let index = field.split(':').next().unwrap();
+    let is_field = rest[end..].starts_with('}') && index.chars().all(|c| c.is_ascii_digit());
+    is_field.then_some(field)
+}
+
+/// replaces the fields in `template` with the arguments: `{}` takes the next
+/// one, `{1}` the second one and `{:.2}` formats the next one with a spec.
+/// Other braces are kept, `{{` and `}}` were already read by the lexer
+pub fn format(template: &str, args: &[Type], pos: &Position) -> Result<String, Error> {
+    let mut out = String::new();
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let field = match field(rest) {
+            Some(field) => field,
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        rest = &rest[field.len() + 2..];
+        let (index, spec) = field.split_once(':').unwrap_or((field, ""));
+        let index = match index {
+            "" => {
+                next += 1;
+                next - 1
+            }
+            index => index
+                .parse()
+                .map_err(|_| invalid(&format!("invalid field {{{}}}", field), pos))?,
+        };
+        let value = args.get(index).ok_or_else(|| {
+            invalid(
+                &format!("there is no argument {} for the format string", index),
+                pos,
+            )
+        })?;
+        out.push_str(&Spec::parse(spec, pos)?.apply(value));
+    }
+    out.push_str(rest);
+    Ok(out)
+}
@@ -0,0 +1,40 @@
+use crate::interpreter::builtins::{int_arg, Methods};
+use crate::interpreter::token::types::{Args, Integer};
+use crate::interpreter::{Context, Error, ErrorKind, Position, Type};
+use std::convert::TryFrom;
+
+/// the methods of lists
+pub const METHODS: Methods = &[("len", 0..=0, Some("int"), len), ("get", 1..=1, None, get)];
+
+/// the list the method was called on
+fn this(args: &Args) -> &[Type] {
+    match &args[0] {
+        Type::List(v) => v.get_items(),
+        _ => unreachable!("list methods are only bound to lists"),
+    }
+}
+
+fn len(args: &Args, _context: &Context, _pos: &Position) -> Result<Type, Error> {
+    Ok(Type::Integer(Integer::new(this(args).len() as i64)))
+}
+
+/// the item at the index, starting at 0
+fn get(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let items = this(args);
+    let index = int_arg(args, 1, pos)?;
+    match usize::try_from(index)
+        .ok()
+        .and_then(|index| items.get(index))
+    {
+        Some(item) => Ok(item.clone()),
+        None => Err(Error::new(
+            ErrorKind::ValueError,
+            &format!(
+                "index {} is out of range for a list of length {}",
+                index,
+                items.len()
+            ),
+            Some(pos.clone()),
+        )),
+    }
+}
@@ -0,0 +1,302 @@
+use crate::interpreter::builtins::Methods;
+use crate::interpreter::token::ident::Ident;
+use crate::interpreter::token::types::{Args, Builtin, Float, Integer, Module};
+use crate::interpreter::{Context, Error, ErrorKind, Position, Type};
+use std::convert::TryFrom;
+use std::f64::consts;
+
+const FUNCTIONS: Methods = &[
+    ("sqrt", 1..=1, Some("float"), sqrt),
+    ("pow", 2..=2, None, pow),
+    ("abs", 1..=1, None, abs),
+    ("floor", 1..=1, Some("int"), floor),
+    ("ceil", 1..=1, Some("int"), ceil),
+    ("round", 1..=1, Some("int"), round),
+    ("min", 1..=usize::MAX, None, min),
+    ("max", 1..=usize::MAX, None, max),
+    ("sin", 1..=1, Some("float"), sin),
+    ("cos", 1..=1, Some("float"), cos),
+    ("tan", 1..=1, Some("float"), tan),
+    ("asin", 1..=1, Some("float"), asin),
+    ("acos", 1..=1, Some("float"), acos),
+    ("atan", 1..=1, Some("float"), atan),
+    ("atan2", 2..=2, Some("float"), atan2),
+    ("exp", 1..=1, Some("float"), exp),
+    ("log", 1..=2, Some("float"), log),
+    ("log2", 1..=1, Some("float"), log2),
+    ("log10", 1..=1, Some("float"), log10),
+];
+
+/// the `math` module
+pub fn module() -> Module {
+    let mut members = vec![
+        ("pi", float(consts::PI)),
+        ("e", float(consts::E)),
+        ("inf", float(f64::INFINITY)),
+        ("nan", float(f64::NAN)),
+    ];
+    for (name, arity, returns, function) in FUNCTIONS {
+        let builtin = Builtin::new(name, arity.clone(), *function).with_returns(*returns);
+        members.push((name, Type::Builtin(builtin)));
+    }
+    let members = members
+        .into_iter()
+        .map(|(name, value)| (Ident::new(name), value))
+        .collect();
+    Module::new("math", members)
+}
+
+fn float(value: f64) -> Type {
+    Type::Float(Float::new(value))
+}
+
+/// the argument at `index` as a float, ints are converted
+fn number(args: &[Type], index: usize, pos: &Position) -> Result<f64, Error> {
+    match &args[index] {
+        Type::Integer(v) => Ok(v.get_value() as f64),
+        Type::Float(v) => Ok(v.get_value()),
+        ty => Err(Error::new(
+            ErrorKind::TypeError,
+            &format!("expected a number but got {}", ty.type_name()),
+            Some(pos.clone()),
+        )),
+    }
+}
+
+/// fails if `x` is outside of the domain of the function `name`
+fn domain(outside: bool, name: &str, domain: &str, x: f64, pos: &Position) -> Result<(), Error> {
+    if outside {
+        return Err(Error::new(
+            ErrorKind::ValueError,
+            &format!("{} is only defined for {} but got {}", name, domain, x),
+            Some(pos.clone()),
+        ));
+    }
+    Ok(())
+}
+
+fn too_large(pos: &Position) -> Error {
+    Error::new(
+        ErrorKind::ValueError,
+        "the result is too large for an int",
+        Some(pos.clone()),
+    )
+}
+
+/// rounds the argument with `f`, ints are returned as they are
+fn to_int(args: &[Type], pos: &Position, f: fn(f64) -> f64) -> Result<Type, Error> {
+    if let Type::Integer(_) = &args[0] {
+        return Ok(args[0].clone());
+    }
+    let x = f(number(args, 0, pos)?);
+    // i64::MAX as f64 is rounded up to 2^63, which doesn't fit anymore
+    if !x.is_finite() || x < i64::MIN as f64 || x >= i64::MAX as f64 {
+        return Err(Error::new(
+            ErrorKind::ValueError,
+            &format!("can't convert {} to an int", x),
+            Some(pos.clone()),
+        ));
+    }
+    Ok(Type::Integer(Integer::new(x as i64)))
+}
+
+fn sqrt(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let x = number(args, 0, pos)?;
+    domain(x < 0.0, "sqrt", "numbers >= 0", x, pos)?;
+    Ok(float(x.sqrt()))
+}
+
+/// ints stay ints as long as the exponent isn't negative
+fn pow(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    if let (Type::Integer(base), Type::Integer(exp)) = (&args[0], &args[1]) {
+        if exp.get_value() >= 0 {
+            let exp = u32::try_from(exp.get_value()).map_err(|_| too_large(pos))?;
+            let value = base
+                .get_value()
+                .checked_pow(exp)
+                .ok_or_else(|| too_large(pos))?;
+            return Ok(Type::Integer(Integer::new(value)));
+        }
+    }
+    let (base, exp) = (number(args, 0, pos)?, number(args, 1, pos)?);
+    domain(
+        base < 0.0 && exp.fract() != 0.0,
+        "pow",
+        "whole exponents if the base is negative",
+        exp,
+        pos,
+    )?;
+    domain(
+        base == 0.0 && exp < 0.0,
+        "pow",
+        "exponents >= 0 if the base is 0",
+        exp,
+        pos,
+    )?;
+    Ok(float(base.powf(exp)))
+}
+
+fn abs(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    match &args[0] {
+        Type::Integer(v) => match v.get_value().checked_abs() {
+            Some(value) => Ok(Type::Integer(Integer::new(value))),
+            None => Err(too_large(pos)),
+        },
+        _ => Ok(float(number(args, 0, pos)?.abs())),
+    }
+}
+
+fn floor(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    to_int(args, pos, f64::floor)
+}
+
+fn ceil(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    to_int(args, pos, f64::ceil)
+}
+
+/// rounds half away from zero
+fn round(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    to_int(args, pos, f64::round)
+}
+
+/// the argument which is the best according to `better`, ints and floats can be mixed
+fn select(args: &[Type], pos: &Position, better: fn(f64, f64) -> bool) -> Result<Type, Error> {
+    let mut best = 0;
+    let mut best_value = number(args, 0, pos)?;
+    for index in 1..args.len() {
+        let value = number(args, index, pos)?;
+        if better(value, best_value) {
+            best = index;
+            best_value = value;
+        }
+    }
+    Ok(args[best].clone())
+}
+
+fn min(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    select(args, pos, |a, b| a < b)
+}
+
+fn max(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    select(args, pos, |a, b| a > b)
+}
+
+fn sin(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    Ok(float(number(args, 0, pos)?.sin()))
+}
+
+fn cos(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    Ok(float(number(args, 0, pos)?.cos()))
+}
+
+fn tan(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    Ok(float(number(args, 0, pos)?.tan()))
+}
+
+fn asin(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let x = number(args, 0, pos)?;
+    domain(x.abs() > 1.0, "asin", "numbers from -1 to 1", x, pos)?;
+    Ok(float(x.asin()))
+}
+
+fn acos(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let x = number(args, 0, pos)?;
+    domain(x.abs() > 1.0, "acos", "numbers from -1 to 1", x, pos)?;
+    Ok(float(x.acos()))
+}
+
+fn atan(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    Ok(float(number(args, 0, pos)?.atan()))
+}
+
+/// the angle of the point (x, y), called as `atan2(y, x)`
+fn atan2(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let (y, x) = (number(args, 0, pos)?, number(args, 1, pos)?);
+    Ok(float(y.atan2(x)))
+}
+
+fn exp(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    Ok(float(number(args, 0, pos)?.exp()))
+}
+
+/// the natural logarithm or the logarithm to the base given as second argument
+fn log(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let x = number(args, 0, pos)?;
+    domain(x <= 0.0, "log", "numbers > 0", x, pos)?;
+    match args.get(1) {
+        Some(_) => {
+            let base = number(args, 1, pos)?;
+            domain(
+                base <= 0.0 || base == 1.0,
+                "log",
+                "bases > 0 other than 1",
+                base,
+                pos,
+            )?;
+            Ok(float(x.log(base)))
+        }
+        None => Ok(float(x.ln())),
+    }
+}
+
+fn log2(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let x = number(args, 0, pos)?;
+    domain(x <= 0.0, "log2", "numbers > 0", x, pos)?;
+    Ok(float(x.log2()))
+}
+
+fn log10(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let x = number(args, 0, pos)?;
+    domain(x <= 0.0, "log10", "numbers > 0", x, pos)?;
+    Ok(float(x.log10()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::console::Buffer;
+    use crate::interpreter::{run_with, Options};
+    use std::rc::Rc;
+
+    /// runs `text`, returns what it printed or the error
+    fn output(text: &str) -> String {
+        let console = Rc::new(Buffer::new());
+        let options = Options::new().with_console(console.clone());
+        match run_with(text.to_string(), "test".to_string(), None, options).res {
+            Ok(_) => console.get_output(),
+            Err(e) => e.message().to_string(),
+        }
+    }
+
+    #[test]
+    fn functions_take_ints_and_floats() {
+        assert_eq!(output("print(math.sqrt(16), math.sqrt(2.25))"), "4 1.5\n");
+        assert_eq!(
+            output("print(math.pow(2, 10), math.pow(2.0, -1))"),
+            "1024 0.5\n"
+        );
+        assert_eq!(output("print(math.abs(-3), math.abs(-1.5))"), "3 1.5\n");
+        assert_eq!(
+            output("print(math.floor(1.5), math.ceil(1.5), math.round(-1.5))"),
+            "1 2 -2\n"
+        );
+        assert_eq!(
+            output("print(math.min(3, 1, 2), math.max(1.5, 0.5))"),
+            "1 1.5\n"
+        );
+        assert_eq!(output("print(math.log(8, 2), math.log10(1000))"), "3 3\n");
+    }
+
+    #[test]
+    fn wrong_arguments_are_reported() {
+        assert_eq!(
+            output("math.sqrt(-1)"),
+            "sqrt is only defined for numbers >= 0 but got -1"
+        );
+        assert_eq!(
+            output("math.floor(math.inf)"),
+            "can't convert inf to an int"
+        );
+        assert_eq!(output("math.sqrt(\"4\")"), "expected a number but got str");
+        assert_eq!(output("math.tau"), "module math has no member tau");
+    }
+}
@@ -0,0 +1,235 @@
+pub(super) mod format;
+mod list;
+mod math;
+pub mod prelude;
+mod string;
+
+use crate::interpreter::capabilities::Capability;
+use crate::interpreter::stack::Stack;
+use crate::interpreter::token::ident::Ident;
+use crate::interpreter::token::types::{Args, Builtin, Float, Integer, List, NativeFn, Str};
+use crate::interpreter::{visit, Context, Error, ErrorKind, Position, Type};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::ops::RangeInclusive;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// the name, the number of positional arguments, the names of the named
+/// ones, the type of the result if it is always the same and the
+/// implementation of every function
+type Functions = &'static [(
+    &'static str,
+    RangeInclusive<usize>,
+    &'static [&'static str],
+    Option<&'static str>,
+    NativeFn,
+)];
+
+/// the name, the number of arguments, the type of the result if it is always
+/// the same and the implementation of every method or module function
+pub(super) type Methods = &'static [(
+    &'static str,
+    RangeInclusive<usize>,
+    Option<&'static str>,
+    NativeFn,
+)];
+
+const FUNCTIONS: Functions = &[
+    (
+        "print",
+        0..=usize::MAX,
+        &["sep", "end"],
+        Some("None"),
+        print,
+    ),
+    ("input", 0..=1, &[], None, input),
+    ("format", 1..=usize::MAX, &[], Some("str"), format),
+    ("str", 1..=1, &[], Some("str"), str),
+    ("len", 1..=1, &[], Some("int"), len),
+    ("list", 0..=usize::MAX, &[], Some("list"), list),
+    ("exit", 0..=1, &[], None, exit),
+    ("time", 0..=0, &[], Some("float"), time),
+    ("random", 0..=0, &[], Some("float"), random),
+];
+
+/// the constants and functions every script can use
+pub fn values() -> Vec<(&'static str, Type)> {
+    let mut values = vec![
+        ("null", Type::Integer(Integer::new(0))),
+        ("true", Type::Integer(Integer::new(1))),
+        ("false", Type::Integer(Integer::new(0))),
+        ("math", Type::Module(math::module())),
+    ];
+    for (name, arity, keywords, returns, function) in FUNCTIONS {
+        let builtin = Builtin::new(name, arity.clone(), *function)
+            .with_keywords(keywords)
+            .with_returns(*returns);
+        values.push((name, Type::Builtin(builtin)));
+    }
+    values
+}
+
+pub fn names() -> Vec<&'static str> {
+    values().into_iter().map(|(name, _)| name).collect()
+}
+
+pub fn define(stack: &mut Stack) {
+    for (name, value) in values() {
+        stack.set(Ident::new(name), value);
+    }
+}
+
+/// the method `name` of `value` bound to it
+pub fn method(value: &Type, name: &Ident) -> Option<Type> {
+    let methods = match value {
+        Type::String(_) => string::METHODS,
+        Type::List(_) => list::METHODS,
+        _ => return None,
+    };
+    let (name, arity, returns, function) = methods
+        .iter()
+        .find(|(method, _, _, _)| *method == name.as_str())?;
+    let method = Builtin::new(name, arity.clone(), *function)
+        .with_returns(*returns)
+        .bind(value.clone());
+    Some(Type::Builtin(method))
+}
+
+pub(super) fn int_arg(args: &[Type], index: usize, pos: &Position) -> Result<i64, Error> {
+    match &args[index] {
+        Type::Integer(v) => Ok(v.get_value()),
+        ty => Err(Error::new(
+            ErrorKind::TypeError,
+            &format!("expected an int but got {}", ty.type_name()),
+            Some(pos.clone()),
+        )),
+    }
+}
+
+pub(super) fn str_arg<'a>(value: &'a Type, name: &str, pos: &Position) -> Result<&'a str, Error> {
+    match value {
+        Type::String(v) => Ok(v.get_value()),
+        ty => Err(Error::new(
+            ErrorKind::TypeError,
+            &format!("{} must be a str but got {}", name, ty.type_name()),
+            Some(pos.clone()),
+        )),
+    }
+}
+
+fn io_error(error: std::io::Error, pos: &Position) -> Error {
+    Error::new(ErrorKind::IOError, &error.to_string(), Some(pos.clone()))
+}
+
+/// converts the value to a string
+fn str(args: &Args, context: &Context, pos: &Position) -> Result<Type, Error> {
+    Ok(Type::String(visit::to_str(&args[0], pos, context)?))
+}
+
+/// the number of items of a list, the chars of a string or what `__len__`
+/// returns for an object
+fn len(args: &Args, context: &Context, pos: &Position) -> Result<Type, Error> {
+    Ok(Type::Integer(Integer::new(visit::length(
+        &args[0], pos, context,
+    )?)))
+}
+
+/// a list of the arguments
+fn list(args: &Args, _context: &Context, _pos: &Position) -> Result<Type, Error> {
+    Ok(Type::List(List::new(args.to_vec())))
+}
+
+/// writes the arguments separated by `sep` and followed by `end` to the console
+fn print(args: &Args, context: &Context, pos: &Position) -> Result<Type, Error> {
+    let sep = match args.named("sep") {
+        Some(sep) => str_arg(sep, "sep", pos)?,
+        None => " ",
+    };
+    let end = match args.named("end") {
+        Some(end) => str_arg(end, "end", pos)?,
+        None => "\n",
+    };
+    let values = args
+        .iter()
+        .map(|value| Ok(visit::to_str(value, pos, context)?.get_value().to_string()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let text = values.join(sep) + end;
+    context
+        .run
+        .console
+        .write(&text)
+        .map_err(|e| io_error(e, pos))?;
+    Ok(Type::none())
+}
+
+/// reads a line from the console after writing the prompt, None at the end
+/// of the input
+fn input(args: &Args, context: &Context, pos: &Position) -> Result<Type, Error> {
+    let console = &context.run.console;
+    if let Some(prompt) = args.first() {
+        console
+            .write(&prompt.to_string())
+            .map_err(|e| io_error(e, pos))?;
+    }
+    match console.read_line().map_err(|e| io_error(e, pos))? {
+        Some(line) => Ok(Type::String(Str::new(&line))),
+        None => Ok(Type::none()),
+    }
+}
+
+/// fills the fields of the format string in the first argument with the
+/// other arguments
+fn format(args: &Args, context: &Context, pos: &Position) -> Result<Type, Error> {
+    let template = str_arg(&args[0], "the format string", pos)?;
+    // objects are filled in as their `__str__`
+    let values = args[1..]
+        .iter()
+        .map(|value| match value {
+            Type::Object(_) => Ok(Type::String(visit::to_str(value, pos, context)?)),
+            value => Ok(value.clone()),
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let text = format::format(template, &values, pos)?;
+    Ok(Type::String(Str::new(&text)))
+}
+
+/// stops the script with the given exit code
+fn exit(args: &Args, context: &Context, pos: &Position) -> Result<Type, Error> {
+    context.run.capabilities.check(Capability::Exit, pos)?;
+    let code = match args.first() {
+        Some(_) => int_arg(args, 0, pos)?,
+        None => 0,
+    };
+    // the status of a process is a byte, larger codes would wrap around
+    if !(0..=255).contains(&code) {
+        return Err(Error::new(
+            ErrorKind::ValueError,
+            &format!("the exit code must be between 0 and 255 but got {}", code),
+            Some(pos.clone()),
+        ));
+    }
+    Err(Error::new(
+        ErrorKind::Exit(code),
+        &format!("the script exited with code {}", code),
+        Some(pos.clone()),
+    ))
+}
+
+/// the seconds since the unix epoch
+fn time(_args: &Args, context: &Context, pos: &Position) -> Result<Type, Error> {
+    context.run.capabilities.check(Capability::Clock, pos)?;
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |time| time.as_secs_f64());
+    Ok(Type::Float(Float::new(time)))
+}
+
+/// a random float between 0 and 1
+fn random(_args: &Args, context: &Context, pos: &Position) -> Result<Type, Error> {
+    context.run.capabilities.check(Capability::Random, pos)?;
+    // every RandomState gets new random keys, so hashing nothing is random
+    let bits = RandomState::new().build_hasher().finish();
+    Ok(Type::Float(Float::new(
+        (bits >> 11) as f64 / (1u64 << 53) as f64,
+    )))
+}
@@ -0,0 +1,50 @@
+use crate::interpreter::builtins;
+use crate::interpreter::token::ident::Ident;
+use crate::interpreter::{
+    builtin_stack, parse, resolver, visit, Context, Node, Position, StackRef, Type,
+};
+
+const SOURCE: &str = include_str!("prelude.phx");
+const FILE_NAME: &str = "<prelude>";
+
+thread_local! {
+    // the functions of the prelude, they live in a stack of their own with
+    // the builtins, so scripts can't change what they use
+    static FUNCTIONS: Vec<(Ident, Type)> = run();
+}
+
+/// the parsed source of the prelude
+pub fn ast() -> Node {
+    parse(SOURCE, FILE_NAME.to_string()).expect("the prelude can be parsed")
+}
+
+fn run() -> Vec<(Ident, Type)> {
+    let stack = builtin_stack();
+    let ast = ast();
+    resolver::Resolver::new(&mut stack.borrow_mut()).resolve(&ast);
+    let pos = Position::new(0, FILE_NAME.into(), 0, 0, 0);
+    let context = Context::new(pos, stack.clone(), None);
+    visit(&ast, &context).expect("the prelude runs");
+    let builtins = builtins::names();
+    let variables = stack.borrow().variables();
+    variables
+        .into_iter()
+        .filter(|(name, _)| !builtins.contains(&name.as_str()))
+        .map(|(name, value)| (Ident::new(name), value))
+        .collect()
+}
+
+/// defines the functions of the prelude in `stack`
+pub fn load(stack: &StackRef) {
+    FUNCTIONS.with(|functions| {
+        let mut stack = stack.borrow_mut();
+        for (name, value) in functions {
+            stack.set(name.clone(), value.clone());
+        }
+    })
+}
+
+/// the names of the functions the prelude defines
+pub fn names() -> Vec<String> {
+    FUNCTIONS.with(|functions| functions.iter().map(|(name, _)| name.get()).collect())
+}
@@ -0,0 +1,168 @@
+use crate::interpreter::builtins::{int_arg, str_arg, Methods};
+use crate::interpreter::token::types::{Args, Integer, List, Str};
+use crate::interpreter::{visit, Context, Error, ErrorKind, Position, Type};
+
+/// the methods of strings, indices count chars like the columns of positions
+pub const METHODS: Methods = &[
+    ("len", 0..=0, Some("int"), len),
+    ("upper", 0..=0, Some("str"), upper),
+    ("lower", 0..=0, Some("str"), lower),
+    ("trim", 0..=0, Some("str"), trim),
+    ("split", 0..=1, Some("list"), split),
+    ("join", 1..=1, Some("str"), join),
+    ("replace", 2..=2, Some("str"), replace),
+    ("find", 1..=1, Some("int"), find),
+    ("starts_with", 1..=1, Some("int"), starts_with),
+    ("ends_with", 1..=1, Some("int"), ends_with),
+    ("contains", 1..=1, Some("int"), contains),
+    ("chars", 0..=0, Some("list"), chars),
+    ("substring", 1..=2, Some("str"), substring),
+];
+
+/// the string the method was called on
+fn this(args: &Args) -> &str {
+    match &args[0] {
+        Type::String(v) => v.get_value(),
+        _ => unreachable!("string methods are only bound to strings"),
+    }
+}
+
+fn string(value: &str) -> Type {
+    Type::String(Str::new(value))
+}
+
+/// the char index `index` as a byte index into `value`
+fn char_index(value: &str, index: i64, pos: &Position) -> Result<usize, Error> {
+    let len = value.chars().count();
+    if index < 0 || index as usize > len {
+        return Err(Error::new(
+            ErrorKind::ValueError,
+            &format!(
+                "index {} is out of range for a str of length {}",
+                index, len
+            ),
+            Some(pos.clone()),
+        ));
+    }
+    Ok(value
+        .char_indices()
+        .nth(index as usize)
+        .map_or(value.len(), |(index, _)| index))
+}
+
+/// the number of chars
+fn len(args: &Args, _context: &Context, _pos: &Position) -> Result<Type, Error> {
+    Ok(Type::Integer(Integer::new(
+        this(args).chars().count() as i64
+    )))
+}
+
+fn upper(args: &Args, _context: &Context, _pos: &Position) -> Result<Type, Error> {
+    Ok(string(&this(args).to_uppercase()))
+}
+
+fn lower(args: &Args, _context: &Context, _pos: &Position) -> Result<Type, Error> {
+    Ok(string(&this(args).to_lowercase()))
+}
+
+/// removes whitespace at the start and the end
+fn trim(args: &Args, _context: &Context, _pos: &Position) -> Result<Type, Error> {
+    Ok(string(this(args).trim()))
+}
+
+/// splits at every occurrence of the separator or at whitespace if none is given
+fn split(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let value = this(args);
+    let parts: Vec<Type> = match args.get(1) {
+        Some(separator) => match str_arg(separator, "the separator", pos)? {
+            "" => {
+                return Err(Error::new(
+                    ErrorKind::ValueError,
+                    "the separator can't be empty",
+                    Some(pos.clone()),
+                ))
+            }
+            separator => value.split(separator).map(string).collect(),
+        },
+        None => value.split_whitespace().map(string).collect(),
+    };
+    Ok(Type::List(List::new(parts)))
+}
+
+/// the items of the list converted to strings with this string between them
+fn join(args: &Args, context: &Context, pos: &Position) -> Result<Type, Error> {
+    let items = match &args[1] {
+        Type::List(list) => list.get_items(),
+        ty => {
+            return Err(Error::new(
+                ErrorKind::TypeError,
+                &format!("expected a list but got {}", ty.type_name()),
+                Some(pos.clone()),
+            ))
+        }
+    };
+    let items = items
+        .iter()
+        .map(|item| Ok(visit::to_str(item, pos, context)?.get_value().to_string()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(string(&items.join(this(args))))
+}
+
+fn replace(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let from = str_arg(&args[1], "the replaced string", pos)?;
+    let to = str_arg(&args[2], "the replacement", pos)?;
+    Ok(string(&this(args).replace(from, to)))
+}
+
+/// the index of the first occurrence or -1
+fn find(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let value = this(args);
+    let index = match value.find(str_arg(&args[1], "the searched string", pos)?) {
+        Some(index) => value[..index].chars().count() as i64,
+        None => -1,
+    };
+    Ok(Type::Integer(Integer::new(index)))
+}
+
+fn starts_with(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let prefix = str_arg(&args[1], "the prefix", pos)?;
+    Ok(this(args).starts_with(prefix).into())
+}
+
+fn ends_with(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let suffix = str_arg(&args[1], "the suffix", pos)?;
+    Ok(this(args).ends_with(suffix).into())
+}
+
+fn contains(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let part = str_arg(&args[1], "the searched string", pos)?;
+    Ok(this(args).contains(part).into())
+}
+
+/// a list of the chars as strings
+fn chars(args: &Args, _context: &Context, _pos: &Position) -> Result<Type, Error> {
+    let chars = this(args)
+        .chars()
+        .map(|c| string(c.encode_utf8(&mut [0; 4])))
+        .collect();
+    Ok(Type::List(List::new(chars)))
+}
+
+/// the chars from `start` up to `end` or the end of the string
+fn substring(args: &Args, _context: &Context, pos: &Position) -> Result<Type, Error> {
+    let value = this(args);
+    let start = int_arg(args, 1, pos)?;
+    let end = match args.get(2) {
+        Some(_) => int_arg(args, 2, pos)?,
+        None => value.chars().count() as i64,
+    };
+    let (start_index, end_index) = (char_index(value, start, pos)?, char_index(value, end, pos)?);
+    if start > end {
+        return Err(Error::new(
+            ErrorKind::ValueError,
+            &format!("the start {} is after the end {}", start, end),
+            Some(pos.clone()),
+        ));
+    }
+    Ok(string(&value[start_index..end_index]))
+}
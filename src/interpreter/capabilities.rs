@@ -0,0 +1,247 @@
+use crate::interpreter::{Error, ErrorKind, Position};
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// Something a script can only do if it was allowed to
+#[derive(Clone, Debug, PartialEq)]
+pub enum Capability {
+    Read(PathBuf),
+    Write(PathBuf),
+    Env,
+    Clock,
+    Random,
+    Exit,
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(path) => write!(f, "read access to {}", path.display()),
+            Self::Write(path) => write!(f, "write access to {}", path.display()),
+            Self::Env => write!(f, "access to environment variables"),
+            Self::Clock => write!(f, "access to the clock"),
+            Self::Random => write!(f, "access to randomness"),
+            Self::Exit => write!(f, "permission to exit the process"),
+        }
+    }
+}
+
+/// The files or directories which may be accessed
+#[derive(Clone, Debug)]
+enum Paths {
+    All,
+    Only(Vec<PathBuf>),
+}
+
+impl Paths {
+    fn add(self, path: PathBuf) -> Self {
+        match self {
+            Self::All => Self::All,
+            Self::Only(mut paths) => {
+                paths.push(path);
+                Self::Only(paths)
+            }
+        }
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        match self {
+            Self::All => true,
+            Self::Only(paths) => {
+                // resolve `..` and links, so the script can't leave the directories
+                let path = match resolve(path) {
+                    Some(path) => path,
+                    None => return false,
+                };
+                paths.iter().any(|allowed| match resolve(allowed) {
+                    Some(allowed) => path.starts_with(allowed),
+                    None => false,
+                })
+            }
+        }
+    }
+}
+
+/// the absolute path without links, for files which don't exist yet only the
+/// directory has to exist
+fn resolve(path: &Path) -> Option<PathBuf> {
+    path.canonicalize().ok().or_else(|| {
+        let dir = match path.parent()? {
+            dir if dir.as_os_str().is_empty() => Path::new("."),
+            dir => dir,
+        };
+        Some(dir.canonicalize().ok()?.join(path.file_name()?))
+    })
+}
+
+/// What a script may do besides computing. `Capabilities::none()` is meant
+/// for untrusted code, `Capabilities::all()` for scripts of the user
+#[derive(Clone, Debug)]
+pub struct Capabilities {
+    read: Paths,
+    write: Paths,
+    env: bool,
+    clock: bool,
+    random: bool,
+    exit: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl Capabilities {
+    pub fn none() -> Self {
+        Self {
+            read: Paths::Only(Vec::new()),
+            write: Paths::Only(Vec::new()),
+            env: false,
+            clock: false,
+            random: false,
+            exit: false,
+        }
+    }
+
+    pub fn all() -> Self {
+        Self {
+            read: Paths::All,
+            write: Paths::All,
+            env: true,
+            clock: true,
+            random: true,
+            exit: true,
+        }
+    }
+
+    /// allows reading `path` and everything in it if it is a directory
+    pub fn with_read(self, path: impl Into<PathBuf>) -> Self {
+        Self {
+            read: self.read.add(path.into()),
+            ..self
+        }
+    }
+
+    /// allows writing `path` and everything in it if it is a directory
+    pub fn with_write(self, path: impl Into<PathBuf>) -> Self {
+        Self {
+            write: self.write.add(path.into()),
+            ..self
+        }
+    }
+
+    pub fn with_env(self) -> Self {
+        Self { env: true, ..self }
+    }
+
+    pub fn with_clock(self) -> Self {
+        Self {
+            clock: true,
+            ..self
+        }
+    }
+
+    pub fn with_random(self) -> Self {
+        Self {
+            random: true,
+            ..self
+        }
+    }
+
+    pub fn with_exit(self) -> Self {
+        Self { exit: true, ..self }
+    }
+
+    pub fn allows(&self, capability: &Capability) -> bool {
+        match capability {
+            Capability::Read(path) => self.read.contains(path),
+            Capability::Write(path) => self.write.contains(path),
+            Capability::Env => self.env,
+            Capability::Clock => self.clock,
+            Capability::Random => self.random,
+            Capability::Exit => self.exit,
+        }
+    }
+
+    /// returns a PermissionError naming the capability if it is missing
+    pub fn check(&self, capability: Capability, pos: &Position) -> Result<(), Error> {
+        if self.allows(&capability) {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorKind::PermissionError,
+                &format!("the script has no {}", capability),
+                Some(pos.clone()),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn capabilities_are_only_given_when_asked_for() {
+        let none = Capabilities::none();
+        let all = Capabilities::all();
+        let some = Capabilities::none().with_env().with_clock().with_exit();
+        let capabilities = [
+            Capability::Env,
+            Capability::Clock,
+            Capability::Random,
+            Capability::Exit,
+        ];
+        for capability in capabilities {
+            assert!(!none.allows(&capability));
+            assert!(all.allows(&capability));
+            assert_eq!(some.allows(&capability), capability != Capability::Random);
+        }
+        let pos = Position::new(0, "test".into(), 0, 0, 0);
+        let e = none.check(Capability::Random, &pos).unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::PermissionError);
+        assert_eq!(e.message(), "the script has no access to randomness");
+    }
+
+    #[test]
+    fn reading_is_limited_to_the_given_paths() {
+        let dir = std::env::temp_dir().join(format!("phoenix-read-{}", std::process::id()));
+        fs::create_dir_all(dir.join("allowed")).unwrap();
+        fs::write(dir.join("secret"), "").unwrap();
+        let capabilities = Capabilities::none().with_read(dir.join("allowed"));
+        let read = |path: PathBuf| capabilities.allows(&Capability::Read(path));
+        assert!(read(dir.join("allowed")));
+        // files which don't exist yet are allowed as long as their directory is
+        assert!(read(dir.join("allowed/new.phx")));
+        assert!(!read(dir.join("secret")));
+        assert!(!read(dir.join("allowed/../secret")));
+        assert!(!read(dir.join("missing/file.phx")));
+        assert!(Capabilities::all().allows(&Capability::Read(dir.join("secret"))));
+        assert!(!Capabilities::none().allows(&Capability::Read(dir.join("allowed"))));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn writing_is_limited_to_its_own_paths() {
+        let dir = std::env::temp_dir().join(format!("phoenix-write-{}", std::process::id()));
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::create_dir_all(dir.join("in")).unwrap();
+        let capabilities = Capabilities::none()
+            .with_read(dir.join("in"))
+            .with_write(dir.join("out"));
+        let write = |path: PathBuf| capabilities.allows(&Capability::Write(path));
+        assert!(write(dir.join("out/new.txt")));
+        assert!(!write(dir.join("out/../in/new.txt")));
+        // reading a path doesn't allow writing it and the other way around
+        assert!(!write(dir.join("in/new.txt")));
+        assert!(!capabilities.allows(&Capability::Read(dir.join("out/new.txt"))));
+        assert!(Capabilities::all().allows(&Capability::Write(dir.join("in/new.txt"))));
+        let pos = Position::new(0, "test".into(), 0, 0, 0);
+        let e = (capabilities.check(Capability::Write(dir.join("in")), &pos)).unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::PermissionError);
+        assert!(e.message().starts_with("the script has no write access to"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,939 @@
+use crate::interpreter::ast::nodes::{
+    BinaryOperationNode, CallNode, FuncDefNode, Node, NodeType, OperationType, UnaryOperation,
+    Variable, Variant,
+};
+use crate::interpreter::builtins::{self, prelude};
+use crate::interpreter::token::ident::Ident;
+use crate::interpreter::token::types::builtin::{self, Builtin};
+use crate::interpreter::token::types::{List, Str, Type};
+use crate::interpreter::token::Token;
+use crate::interpreter::{limits, Error, ErrorKind, Position};
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+/// What is known about something callable: the types of its parameters and
+/// of its result
+#[derive(Clone, PartialEq)]
+struct Signature {
+    // the way the runtime shows it, e.g. `<function f>`
+    name: String,
+    // None if the parameters aren't known
+    params: Option<Vec<(Ident, Ty)>>,
+    // how many positional arguments a builtin takes, its parameters have no names
+    arity: Option<RangeInclusive<usize>>,
+    returns: Ty,
+}
+
+impl Signature {
+    fn unknown(name: &str, returns: Ty) -> Rc<Self> {
+        Rc::new(Self {
+            name: name.to_string(),
+            params: None,
+            arity: None,
+            returns,
+        })
+    }
+
+    /// the signature a builtin declares next to its implementation
+    fn builtin(builtin: &Builtin) -> Rc<Self> {
+        Rc::new(Self {
+            name: builtin.to_string(),
+            params: None,
+            arity: Some(builtin.get_arity().clone()),
+            returns: builtin
+                .get_returns()
+                .and_then(Ty::named)
+                .unwrap_or(Ty::Unknown),
+        })
+    }
+}
+
+/// The members of a module or the variants of an enum
+#[derive(PartialEq)]
+struct Members {
+    name: String,
+    members: HashMap<Ident, Ty>,
+}
+
+/// The type of a value as far as the checker can tell
+#[derive(Clone, PartialEq)]
+enum Ty {
+    Int,
+    Float,
+    Str,
+    List,
+    Tuple,
+    Function(Rc<Signature>),
+    // calling it gives the `returns` of the signature
+    Struct(Rc<Signature>),
+    // the members are only known for builtin modules
+    Module(Option<Rc<Members>>),
+    // an object of the struct or enum with the name
+    Object(Ident),
+    Enum(Rc<Members>),
+    None,
+    // annotated as `any`, fits everywhere
+    Any,
+    // couldn't be inferred
+    Unknown,
+}
+
+impl Ty {
+    fn of(value: &Type) -> Self {
+        match value {
+            Type::Integer(_) => Self::Int,
+            Type::Float(_) => Self::Float,
+            Type::String(_) => Self::Str,
+            Type::List(_) => Self::List,
+            Type::Tuple(_) => Self::Tuple,
+            Type::Builtin(builtin) => Self::Function(Signature::builtin(builtin)),
+            Type::Module(module) => {
+                let members = (module.members().iter())
+                    .map(|(name, value)| (name.clone(), Self::of(value)))
+                    .collect();
+                Self::Module(Some(Rc::new(Members {
+                    name: module.get_name().to_string(),
+                    members,
+                })))
+            }
+            Type::Function(_) => {
+                Self::Function(Signature::unknown(&value.to_string(), Self::Unknown))
+            }
+            Type::Object(object) => Self::Object(Ident::new(object.get_struct().type_name())),
+            Type::None(_) => Self::None,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// the type of the builtin name `name` in annotations, structs and enums
+    /// aren't known here
+    fn named(name: &str) -> Option<Self> {
+        Some(match name {
+            "int" => Self::Int,
+            "float" => Self::Float,
+            "str" => Self::Str,
+            "list" => Self::List,
+            "tuple" => Self::Tuple,
+            "function" => Self::Function(Signature::unknown("function", Self::Unknown)),
+            "module" => Self::Module(None),
+            "None" => Self::None,
+            "any" => Self::Any,
+            _ => return None,
+        })
+    }
+
+    /// whether a value of this type may be used where `expected` is needed
+    fn fits(&self, expected: &Ty) -> bool {
+        match (self, expected) {
+            (Self::Any, _) | (_, Self::Any) | (Self::Unknown, _) | (_, Self::Unknown) => true,
+            (Self::Function(_), Self::Function(_))
+            | (Self::Struct(_), Self::Struct(_))
+            | (Self::Module(_), Self::Module(_))
+            | (Self::Enum(_), Self::Enum(_)) => true,
+            (actual, expected) => actual == expected,
+        }
+    }
+
+    /// the type of values which are either of the two types
+    fn join(self, other: Ty) -> Ty {
+        if self == other {
+            self
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// whether the value certainly isn't a list, a tuple or an object
+    fn is_scalar(&self) -> bool {
+        !matches!(
+            self,
+            Self::List | Self::Tuple | Self::Object(_) | Self::Any | Self::Unknown
+        )
+    }
+}
+
+impl Display for Ty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int => write!(f, "int"),
+            Self::Float => write!(f, "float"),
+            Self::Str => write!(f, "str"),
+            Self::List => write!(f, "list"),
+            Self::Tuple => write!(f, "tuple"),
+            Self::Function(_) => write!(f, "function"),
+            Self::Struct(_) => write!(f, "struct"),
+            Self::Module(_) => write!(f, "module"),
+            Self::Object(name) => write!(f, "{}", name),
+            Self::Enum(_) => write!(f, "enum"),
+            Self::None => write!(f, "None"),
+            Self::Any => write!(f, "any"),
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// A name in a scope
+#[derive(Clone)]
+struct Binding {
+    ty: Ty,
+    // the type comes from an annotation, later values have to fit it
+    declared: bool,
+}
+
+/// The fields and the methods of a struct
+type Layout = (Vec<Ident>, HashMap<Ident, Rc<Signature>>);
+
+struct Checker {
+    strict: bool,
+    // functions get their own scope, blocks share the one they are in
+    scopes: Vec<HashMap<Ident, Binding>>,
+    // the signatures of the functions by the index of their node
+    signatures: HashMap<i64, Rc<Signature>>,
+    // the fields and methods of the structs by their name
+    structs: HashMap<Ident, Layout>,
+    errors: Vec<Error>,
+    // the type every name got, by the index of the name where it is defined
+    types: HashMap<i64, Ty>,
+}
+
+/// What the checker found out about a script: its type errors and the types
+/// of the names it defines
+pub struct Checked {
+    errors: Vec<Error>,
+    types: HashMap<i64, Ty>,
+}
+
+impl Checked {
+    pub fn get_errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    /// the type of the name defined at `pos`, e.g. `int` or the name of a
+    /// struct, None if it couldn't be inferred
+    pub fn type_of(&self, pos: &Position) -> Option<String> {
+        match self.types.get(&pos.index)? {
+            Ty::Unknown => None,
+            ty => Some(ty.to_string()),
+        }
+    }
+
+    /// the type of the result of the function whose name is defined at `pos`
+    pub fn result_of(&self, pos: &Position) -> Option<String> {
+        match self.types.get(&pos.index)? {
+            Ty::Function(signature) if signature.returns != Ty::Unknown => {
+                Some(signature.returns.to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+thread_local! {
+    // the types of the functions of the prelude, inferred from its source
+    static PRELUDE: HashMap<Ident, Binding> = {
+        let mut checker = Checker::new(false, builtin_globals());
+        checker.infer(&prelude::ast());
+        checker.scopes.pop().unwrap_or_default()
+    };
+}
+
+/// the types of the builtins as they declare them
+fn builtin_globals() -> HashMap<Ident, Binding> {
+    (builtins::values().into_iter())
+        .map(|(name, value)| {
+            let ty = Ty::of(&value);
+            let binding = Binding {
+                ty,
+                declared: false,
+            };
+            (Ident::new(name), binding)
+        })
+        .collect()
+}
+
+/// checks the types of a parsed script without running it
+///
+/// Types are inferred from literals, operations and calls and the values of
+/// annotated names, parameters and results have to fit their annotations.
+/// What can't be inferred is let through, unless `strict` is set: then values
+/// of unknown type can't be operands or called. After an error the value is
+/// taken to be `any`, so every mistake is only reported once.
+pub fn check(ast: &Node, strict: bool) -> Vec<Error> {
+    infer(ast, strict).errors
+}
+
+/// checks the script like `check` and keeps the inferred types
+pub fn infer(ast: &Node, strict: bool) -> Checked {
+    let mut globals = builtin_globals();
+    PRELUDE.with(|prelude| globals.extend(prelude.clone()));
+    let mut checker = Checker::new(strict, globals);
+    checker.infer(ast);
+    Checked {
+        errors: checker.errors,
+        types: checker.types,
+    }
+}
+
+/// the type of the value of `ast` with the names in `values` defined, told
+/// without running it; None if it can't be inferred
+pub fn type_with(ast: &Node, values: &[(String, Type)]) -> Result<Option<String>, Error> {
+    let mut globals = builtin_globals();
+    PRELUDE.with(|prelude| globals.extend(prelude.clone()));
+    for (name, value) in values {
+        let name = Ident::new(name);
+        // the signatures of the builtins and the prelude say more than their values
+        let is_function = matches!(value, Type::Function(_) | Type::Builtin(_));
+        if is_function && globals.contains_key(&name) {
+            continue;
+        }
+        let binding = Binding {
+            ty: Ty::of(value),
+            declared: false,
+        };
+        globals.insert(name, binding);
+    }
+    let mut checker = Checker::new(false, globals);
+    let ty = checker.infer(ast);
+    if let Some(e) = checker.errors.into_iter().next() {
+        return Err(e);
+    }
+    Ok(match ty {
+        Ty::Unknown => None,
+        ty => Some(ty.to_string()),
+    })
+}
+
+impl Checker {
+    fn new(strict: bool, globals: HashMap<Ident, Binding>) -> Self {
+        Self {
+            strict,
+            scopes: vec![globals, HashMap::new()],
+            signatures: HashMap::new(),
+            structs: HashMap::new(),
+            errors: Vec::new(),
+            types: HashMap::new(),
+        }
+    }
+
+    fn error(&mut self, kind: ErrorKind, message: String, pos: Position) {
+        self.errors.push(Error::new(kind, &message, Some(pos)));
+    }
+
+    fn lookup(&self, name: &Ident) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn bind(&mut self, name: &Variable, ty: Ty, declared: bool) {
+        self.types.insert(name.get_pos().index, ty.clone());
+        let scope = self.scopes.last_mut().expect("there is always a scope");
+        scope.insert(name.get_name().clone(), Binding { ty, declared });
+    }
+
+    /// fails in strict mode if the type of `node` isn't known
+    fn known(&mut self, ty: Ty, node: &Node) -> Ty {
+        if self.strict && ty == Ty::Unknown {
+            let message = format!("can't tell the type of {}", node);
+            self.error(ErrorKind::TypeError, message, node.get_pos());
+            // a value is only reported once
+            return Ty::Any;
+        }
+        ty
+    }
+
+    /// fails if the value of `node` doesn't fit the type `expected` of `what`
+    fn expect(&mut self, actual: &Ty, expected: &Ty, node: &Node, what: &dyn Display) {
+        if !actual.fits(expected) {
+            let message = format!("{} should be {} but is {}", what, expected, actual);
+            self.error(ErrorKind::TypeError, message, node.get_pos());
+        }
+    }
+
+    /// the type a `: TYPE` annotation stands for
+    fn annotation(&mut self, ty: &Variable) -> Ty {
+        if let Some(named) = Ty::named(ty.get_name().as_str()) {
+            return named;
+        }
+        match self.lookup(ty.get_name()).map(|binding| &binding.ty) {
+            Some(Ty::Struct(signature)) => signature.returns.clone(),
+            Some(Ty::Enum(_)) => Ty::Object(ty.get_name().clone()),
+            _ => {
+                let message = format!("unknown type {}", ty);
+                self.error(ErrorKind::NameError, message, ty.get_pos());
+                Ty::Any
+            }
+        }
+    }
+
+    /// the signature given by the annotations of a function
+    fn signature(&mut self, node: &Node, func: &FuncDefNode) -> Rc<Signature> {
+        let index = node.get_pos().index;
+        if let Some(signature) = self.signatures.get(&index) {
+            return signature.clone();
+        }
+        let (name, params, _) = func.get_all();
+        let (types, returns) = func.get_types();
+        let params = (params.iter().zip(types))
+            .map(|(param, ty)| {
+                let ty = ty.as_ref().map_or(Ty::Unknown, |ty| self.annotation(ty));
+                (param.get_name().clone(), ty)
+            })
+            .collect();
+        let signature = Rc::new(Signature {
+            name: match name {
+                Some(name) => format!("<function {}>", name),
+                None => "<function>".to_string(),
+            },
+            params: Some(params),
+            arity: None,
+            returns: returns.map_or(Ty::Unknown, |ty| self.annotation(ty)),
+        });
+        self.signatures.insert(index, signature.clone());
+        signature
+    }
+
+    /// the signature of `<struct name>(fields)` which makes an object of `ty`
+    fn constructor(name: String, fields: &[Variable], ty: Ident) -> Rc<Signature> {
+        let params = fields
+            .iter()
+            .map(|field| (field.get_name().clone(), Ty::Unknown));
+        Rc::new(Signature {
+            name,
+            params: Some(params.collect()),
+            arity: None,
+            returns: Ty::Object(ty),
+        })
+    }
+
+    /// declares the structs, enums and functions of a block before it runs,
+    /// types first so functions can be annotated with them
+    fn hoist(&mut self, statements: &[Node]) {
+        for statement in statements {
+            match statement.get_type() {
+                NodeType::StructDef(def) => {
+                    let name = def.get_name();
+                    let signature = format!("<struct {}>", name);
+                    let ty =
+                        Self::constructor(signature, def.get_fields(), name.get_name().clone());
+                    self.bind(name, Ty::Struct(ty), false);
+                }
+                NodeType::EnumDef(def) => {
+                    let ty = self.enum_type(def.get_name(), def.get_variants());
+                    self.bind(def.get_name(), ty, false);
+                }
+                _ => {}
+            }
+        }
+        for statement in statements {
+            match statement.get_type() {
+                NodeType::FuncDefNode(func) => {
+                    if let (Some(name), _, _) = func.get_all() {
+                        let signature = self.signature(statement, func);
+                        self.bind(name, Ty::Function(signature), false);
+                    }
+                }
+                NodeType::StructDef(def) => {
+                    let name = def.get_name().get_name().clone();
+                    let fields = def
+                        .get_fields()
+                        .iter()
+                        .map(|field| field.get_name().clone());
+                    let mut methods = HashMap::new();
+                    for method in def.get_methods() {
+                        if let NodeType::FuncDefNode(func) = method.get_type() {
+                            if let (Some(method_name), _, _) = func.get_all() {
+                                let signature = self.signature(method, func);
+                                methods.insert(method_name.get_name().clone(), signature);
+                            }
+                        }
+                    }
+                    self.structs.insert(name, (fields.collect(), methods));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// the signature of a method bound to an object of `owner`, without the
+    /// receiver
+    fn bound(method: &Signature, name: &Ident, owner: &Ident) -> Rc<Signature> {
+        let params = (method.params.as_ref()).map(|params| params.iter().skip(1).cloned());
+        Rc::new(Signature {
+            name: format!("<method {} of {}>", name, owner),
+            params: params.map(Iterator::collect),
+            arity: None,
+            returns: method.returns.clone(),
+        })
+    }
+
+    fn enum_type(&self, name: &Variable, variants: &[Variant]) -> Ty {
+        let owner = name.get_name().clone();
+        let members = variants
+            .iter()
+            .map(|variant| {
+                let ty = match variant.get_fields() {
+                    Some(fields) => {
+                        let signature = format!("<variant {}.{}>", owner, variant.get_name());
+                        Ty::Struct(Self::constructor(signature, fields, owner.clone()))
+                    }
+                    None => Ty::Object(owner.clone()),
+                };
+                (variant.get_name().clone(), ty)
+            })
+            .collect();
+        Ty::Enum(Rc::new(Members {
+            name: owner.to_string(),
+            members,
+        }))
+    }
+
+    /// checks the body of a function in a new scope, returns its signature
+    /// with the inferred result if it has no annotated one
+    fn function(&mut self, node: &Node, func: &FuncDefNode, receiver: Option<Ty>) -> Rc<Signature> {
+        let signature = self.signature(node, func);
+        let (_, params, body) = func.get_all();
+        let (types, returns) = func.get_types();
+        self.scopes.push(HashMap::new());
+        for (i, (param, (_, ty))) in params
+            .iter()
+            .zip(signature.params.iter().flatten())
+            .enumerate()
+        {
+            match (&receiver, &types[i]) {
+                (Some(receiver), None) if i == 0 => self.bind(param, receiver.clone(), false),
+                (_, annotation) => self.bind(param, ty.clone(), annotation.is_some()),
+            }
+        }
+        let result = self.infer(body);
+        self.scopes.pop();
+        if returns.is_some() {
+            let what = format!("the result of {}", signature.name);
+            self.expect(&result, &signature.returns, body, &what);
+            signature
+        } else {
+            Rc::new(Signature {
+                returns: result,
+                ..signature.as_ref().clone()
+            })
+        }
+    }
+
+    fn infer(&mut self, node: &Node) -> Ty {
+        limits::grow(|| self.infer_node(node))
+    }
+
+    fn infer_node(&mut self, node: &Node) -> Ty {
+        match node.get_type() {
+            NodeType::Node(value) => Ty::of(value),
+            NodeType::Var(var) => self
+                .lookup(var.get_name())
+                .map_or(Ty::Unknown, |binding| binding.ty.clone()),
+            NodeType::Operation(OperationType::BinaryOperationNode(op)) => self.binary(node, op),
+            NodeType::Operation(OperationType::UnaryOperationNode(op)) => {
+                let ty = self.infer(op.get_node());
+                let ty = self.known(ty, op.get_node());
+                match (op.get_operation(), ty) {
+                    (UnaryOperation::Plus, ty) => ty,
+                    (UnaryOperation::Not, Ty::Int) | (UnaryOperation::Not, Ty::Object(_)) => {
+                        Ty::Int
+                    }
+                    (UnaryOperation::Minus, ty @ Ty::Int)
+                    | (UnaryOperation::Minus, ty @ Ty::Float)
+                    | (_, ty @ Ty::Any)
+                    | (_, ty @ Ty::Unknown) => ty,
+                    (UnaryOperation::Minus, Ty::Object(_)) => Ty::Unknown,
+                    (operation, ty) => {
+                        let message = format!("bad operand type for {}: {}", operation, ty);
+                        self.error(ErrorKind::TypeError, message, node.get_pos());
+                        Ty::Any
+                    }
+                }
+            }
+            NodeType::Assign(assign) => {
+                let (var, expr) = (assign.get_var(), assign.get_expr());
+                let value = self.infer(expr);
+                let declared = match assign.get_type() {
+                    Some(ty) => Some(self.annotation(ty)),
+                    // an annotated name keeps its type in the scope it was declared in
+                    None => (self.scopes.last())
+                        .and_then(|scope| scope.get(var.get_name()))
+                        .filter(|binding| binding.declared)
+                        .map(|binding| binding.ty.clone()),
+                };
+                match declared {
+                    Some(ty) => {
+                        self.expect(&value, &ty, expr, var);
+                        self.bind(var, ty.clone(), true);
+                        ty
+                    }
+                    None => {
+                        self.bind(var, value.clone(), false);
+                        value
+                    }
+                }
+            }
+            NodeType::Destructure(destructure) => {
+                let (pattern, expr) = (destructure.get_pattern(), destructure.get_expr());
+                let value = self.infer(expr);
+                if value.is_scalar() {
+                    let message = format!("can't destructure {} into {}", value, pattern);
+                    self.error(ErrorKind::TypeError, message, pattern.get_pos());
+                }
+                for var in pattern.get_bindings() {
+                    self.bind(var, Ty::Unknown, false);
+                }
+                value
+            }
+            NodeType::Tuple(tuple) => {
+                for item in tuple.get_items() {
+                    self.infer(item);
+                }
+                Ty::Tuple
+            }
+            NodeType::IfNode(if_node) => {
+                let mut ty = None;
+                for (condition, expr) in if_node.get_cases() {
+                    self.infer(condition);
+                    let case = self.infer(expr);
+                    ty = Some(ty.map_or(case.clone(), |ty: Ty| ty.join(case)));
+                }
+                let else_case = match if_node.get_else_case() {
+                    Some(else_case) => self.infer(else_case),
+                    None => Ty::None,
+                };
+                ty.map_or(else_case.clone(), |ty| ty.join(else_case))
+            }
+            NodeType::ForNode(for_node) => {
+                let (var, start, end, body) = for_node.get_all();
+                let start_ty = self.infer(start);
+                let item = match end {
+                    Some(end) => {
+                        let end = self.infer(end);
+                        start_ty.join(end)
+                    }
+                    None => match start_ty {
+                        Ty::Str => Ty::Str,
+                        ty if ty.is_scalar() => {
+                            let message = format!("can't iterate over {}", ty);
+                            self.error(ErrorKind::TypeError, message, start.get_pos());
+                            Ty::Any
+                        }
+                        _ => Ty::Unknown,
+                    },
+                };
+                self.bind(var, item, false);
+                self.infer(body);
+                Ty::Unknown
+            }
+            NodeType::WhileNode(while_node) => {
+                let (cond, body) = while_node.get_all();
+                self.infer(cond);
+                self.infer(body);
+                Ty::Unknown
+            }
+            NodeType::FuncDefNode(func) => {
+                let signature = self.function(node, func, None);
+                if let (Some(name), _, _) = func.get_all() {
+                    self.bind(name, Ty::Function(signature.clone()), false);
+                }
+                Ty::Function(signature)
+            }
+            NodeType::CallNode(call) => self.call(node, call),
+            NodeType::AttributeNode(attribute) => {
+                let name = attribute.get_name();
+                let value = self.infer(attribute.get_value());
+                let missing = |kind, message| Some((kind, message));
+                let (ty, error) = match &value {
+                    Ty::Module(Some(members)) | Ty::Enum(members) => {
+                        match members.members.get(name) {
+                            Some(ty) => (ty.clone(), None),
+                            None if matches!(value, Ty::Enum(_)) => (
+                                Ty::Any,
+                                missing(
+                                    ErrorKind::NameError,
+                                    format!("{} has no variant {}", members.name, name),
+                                ),
+                            ),
+                            None => (
+                                Ty::Any,
+                                missing(
+                                    ErrorKind::NameError,
+                                    format!("module {} has no member {}", members.name, name),
+                                ),
+                            ),
+                        }
+                    }
+                    Ty::Object(ty) => match self.structs.get(ty) {
+                        Some((_, methods)) if methods.contains_key(name) => {
+                            (Ty::Function(Self::bound(&methods[name], name, ty)), None)
+                        }
+                        Some((fields, _)) if !fields.contains(name) => (
+                            Ty::Any,
+                            missing(
+                                ErrorKind::NameError,
+                                format!("{} has no member {}", ty, name),
+                            ),
+                        ),
+                        _ => (Ty::Unknown, None),
+                    },
+                    Ty::Str | Ty::List => {
+                        let sample = match value {
+                            Ty::Str => Type::String(Str::new("")),
+                            _ => Type::List(List::new(Vec::new())),
+                        };
+                        match builtins::method(&sample, name) {
+                            Some(method) => (Ty::of(&method), None),
+                            None => (
+                                Ty::Any,
+                                missing(
+                                    ErrorKind::TypeError,
+                                    format!("{} has no member {}", value, name),
+                                ),
+                            ),
+                        }
+                    }
+                    Ty::Any => (Ty::Any, None),
+                    Ty::Struct(signature) => match &signature.returns {
+                        Ty::Object(ty) => match self.structs.get(ty) {
+                            Some((_, methods)) if methods.contains_key(name) => {
+                                (Ty::Function(methods[name].clone()), None)
+                            }
+                            Some(_) => (
+                                Ty::Any,
+                                missing(
+                                    ErrorKind::NameError,
+                                    format!("{} has no method {}", ty, name),
+                                ),
+                            ),
+                            None => (Ty::Unknown, None),
+                        },
+                        _ => (Ty::Unknown, None),
+                    },
+                    Ty::Module(None) | Ty::Unknown => (Ty::Unknown, None),
+                    ty => (
+                        Ty::Any,
+                        missing(
+                            ErrorKind::TypeError,
+                            format!("{} has no member {}", ty, name),
+                        ),
+                    ),
+                };
+                if let Some((kind, message)) = error {
+                    self.error(kind, message, node.get_pos());
+                }
+                ty
+            }
+            NodeType::IndexNode(index_node) => {
+                let (value, index) = index_node.get_all();
+                let value_ty = self.infer(value);
+                let index_ty = self.infer(index);
+                if matches!(value_ty, Ty::Str | Ty::List | Ty::Tuple)
+                    && !index_ty.fits(&Ty::Int)
+                    && !matches!(index_ty, Ty::Object(_))
+                {
+                    let message = format!("indices must be int, not {}", index_ty);
+                    self.error(ErrorKind::TypeError, message, index.get_pos());
+                }
+                match value_ty {
+                    Ty::Str => Ty::Str,
+                    Ty::Any => Ty::Any,
+                    ty if ty.is_scalar() => {
+                        let message = format!("{} can't be indexed", ty);
+                        self.error(ErrorKind::TypeError, message, node.get_pos());
+                        Ty::Any
+                    }
+                    _ => Ty::Unknown,
+                }
+            }
+            NodeType::SetAttribute(set) => {
+                self.infer(set.get_attribute().get_value());
+                self.infer(set.get_expr())
+            }
+            NodeType::StructDef(def) => {
+                let ty = def.get_name().get_name().clone();
+                for method in def.get_methods() {
+                    if let NodeType::FuncDefNode(func) = method.get_type() {
+                        let signature = self.function(method, func, Some(Ty::Object(ty.clone())));
+                        if let (Some((_, methods)), (Some(name), _, _)) =
+                            (self.structs.get_mut(&ty), func.get_all())
+                        {
+                            methods.insert(name.get_name().clone(), signature);
+                        }
+                    }
+                }
+                let signature = format!("<struct {}>", ty);
+                Ty::Struct(Self::constructor(signature, def.get_fields(), ty))
+            }
+            NodeType::EnumDef(def) => self.enum_type(def.get_name(), def.get_variants()),
+            NodeType::Match(match_node) => {
+                self.infer(match_node.get_value());
+                let mut ty = None;
+                for arm in match_node.get_arms() {
+                    for var in arm.get_pattern().get_bindings() {
+                        self.bind(var, Ty::Unknown, false);
+                    }
+                    if let Some(guard) = arm.get_guard() {
+                        self.infer(guard);
+                    }
+                    let body = self.infer(arm.get_body());
+                    ty = Some(ty.map_or(body.clone(), |ty: Ty| ty.join(body)));
+                }
+                ty.unwrap_or(Ty::Unknown)
+            }
+            NodeType::Interpolation(interpolation) => {
+                for expr in interpolation.get_exprs() {
+                    self.infer(expr);
+                }
+                Ty::Str
+            }
+            NodeType::Import(import) => {
+                for var in import.get_vars() {
+                    self.bind(var, Ty::Unknown, false);
+                }
+                Ty::Unknown
+            }
+            NodeType::Statements(statements) => {
+                let nodes = statements.get_nodes();
+                self.hoist(nodes);
+                let mut ty = Ty::None;
+                for node in nodes {
+                    ty = self.infer(node);
+                }
+                ty
+            }
+        }
+    }
+
+    fn binary(&mut self, node: &Node, op: &BinaryOperationNode) -> Ty {
+        let left = self.infer(op.get_left());
+        let left = self.known(left, op.get_left());
+        let right = self.infer(op.get_right());
+        let right = self.known(right, op.get_right());
+        let operation = op.get_operation();
+        let logical = matches!(operation, Token::DoubleAnd | Token::DoubleOr);
+        let ty = match (&left, &right) {
+            (Ty::Unknown, _) | (_, Ty::Unknown) if logical => return Ty::Int,
+            (Ty::Unknown, _) | (_, Ty::Unknown) => return Ty::Unknown,
+            (Ty::Any, _) | (_, Ty::Any) if logical => return Ty::Int,
+            (Ty::Any, _) | (_, Ty::Any) => return Ty::Any,
+            // objects may implement the operation with their methods
+            (Ty::Object(_), _) | (_, Ty::Object(_)) if logical => return Ty::Int,
+            (Ty::Object(_), _) | (_, Ty::Object(_)) => return Ty::Unknown,
+            _ => match operation {
+                Token::Plus | Token::Minus | Token::Star | Token::Slash => match (&left, &right) {
+                    (Ty::Int, Ty::Int) => Some(Ty::Int),
+                    (Ty::Float, Ty::Float) => Some(Ty::Float),
+                    (Ty::Str, Ty::Str) | (Ty::List, Ty::List) | (Ty::Tuple, Ty::Tuple)
+                        if *operation == Token::Plus =>
+                    {
+                        Some(left.clone())
+                    }
+                    _ => None,
+                },
+                Token::DoubleAnd | Token::DoubleOr => match (&left, &right) {
+                    (Ty::Int, Ty::Int) => Some(Ty::Int),
+                    _ => None,
+                },
+                // numbers can only be compared with numbers of the same type
+                Token::DoubleEqual | Token::NonEqual => {
+                    if matches!(left, Ty::Int | Ty::Float) && left != right {
+                        None
+                    } else {
+                        Some(Ty::Int)
+                    }
+                }
+                _ => match (&left, &right) {
+                    (Ty::Int, Ty::Int) | (Ty::Float, Ty::Float) | (Ty::Str, Ty::Str) => {
+                        Some(Ty::Int)
+                    }
+                    _ => None,
+                },
+            },
+        };
+        ty.unwrap_or_else(|| {
+            let message = format!(
+                "unsupported operand types for {}: {} and {}",
+                operation, left, right
+            );
+            self.error(ErrorKind::TypeError, message, node.get_pos());
+            Ty::Any
+        })
+    }
+
+    fn call(&mut self, node: &Node, call: &CallNode) -> Ty {
+        let (callee, args) = call.get_all();
+        let callee_ty = self.infer(callee);
+        let values: Vec<Ty> = args.iter().map(|arg| self.infer(arg)).collect();
+        let named: Vec<(Ident, Ty)> = (call.get_named().iter())
+            .map(|(name, arg)| (name.clone(), self.infer(arg)))
+            .collect();
+        let signature = match callee_ty {
+            Ty::Function(signature) | Ty::Struct(signature) => signature,
+            Ty::Any => return Ty::Any,
+            Ty::Unknown => return self.known(Ty::Unknown, callee),
+            ty => {
+                let message = format!("{} is not callable", ty);
+                self.error(ErrorKind::TypeError, message, node.get_pos());
+                return Ty::Any;
+            }
+        };
+        let pos = node.get_pos();
+        let params = match (&signature.params, &signature.arity) {
+            (Some(params), _) => params,
+            (None, Some(arity)) if !arity.contains(&values.len()) => {
+                let message = format!(
+                    "{} takes {} arguments but {} were given",
+                    signature.name,
+                    builtin::describe_arity(arity),
+                    values.len()
+                );
+                self.error(ErrorKind::TypeError, message, pos);
+                return signature.returns.clone();
+            }
+            (None, _) => return signature.returns.clone(),
+        };
+        if values.len() > params.len() {
+            let message = format!(
+                "{} takes {} arguments but {} were given",
+                signature.name,
+                params.len(),
+                values.len() + named.len()
+            );
+            self.error(ErrorKind::TypeError, message, pos);
+            return signature.returns.clone();
+        }
+        let mut bound = vec![false; params.len()];
+        for (i, value) in values.iter().enumerate() {
+            bound[i] = true;
+            let what = format!("argument {} of {}", params[i].0, signature.name);
+            self.expect(value, &params[i].1, &args[i], &what);
+        }
+        for (i, (name, value)) in named.iter().enumerate() {
+            let arg = &call.get_named()[i].1;
+            match params.iter().position(|(param, _)| param == name) {
+                Some(index) if bound[index] => {
+                    let message = format!("{} got {} more than once", signature.name, name);
+                    self.error(ErrorKind::TypeError, message, arg.get_pos());
+                }
+                Some(index) => {
+                    bound[index] = true;
+                    let what = format!("argument {} of {}", name, signature.name);
+                    self.expect(value, &params[index].1, arg, &what);
+                }
+                None => {
+                    let message = format!("{} has no parameter {}", signature.name, name);
+                    self.error(ErrorKind::TypeError, message, arg.get_pos());
+                }
+            }
+        }
+        if let Some(index) = bound.iter().position(|bound| !bound) {
+            let message = format!(
+                "{} is missing the argument {}",
+                signature.name, params[index].0
+            );
+            self.error(ErrorKind::TypeError, message, pos);
+        }
+        signature.returns.clone()
+    }
+}
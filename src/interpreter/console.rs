@@ -0,0 +1,74 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+
+/// Where `print` writes to and `input` reads from, embedders can pass their
+/// own console in the options to capture the output
+pub trait Console {
+    fn write(&self, text: &str) -> io::Result<()>;
+
+    /// reads a line without the line break, None at the end of the input
+    fn read_line(&self) -> io::Result<Option<String>>;
+}
+
+/// The stdin and stdout of the process, used by default
+pub struct Stdio;
+
+impl Console for Stdio {
+    fn write(&self, text: &str) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        stdout.write_all(text.as_bytes())?;
+        stdout.flush()
+    }
+
+    fn read_line(&self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if io::stdin().lock().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+}
+
+/// Collects the output in memory and answers `input` with given lines
+#[derive(Default)]
+pub struct Buffer {
+    output: RefCell<String>,
+    input: RefCell<VecDeque<String>>,
+}
+
+impl Buffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the lines `input` returns, one per call
+    pub fn with_input<S: Into<String>>(self, lines: impl IntoIterator<Item = S>) -> Self {
+        self.input
+            .borrow_mut()
+            .extend(lines.into_iter().map(Into::into));
+        self
+    }
+
+    /// everything written so far
+    pub fn get_output(&self) -> String {
+        self.output.borrow().clone()
+    }
+}
+
+impl Console for Buffer {
+    fn write(&self, text: &str) -> io::Result<()> {
+        self.output.borrow_mut().push_str(text);
+        Ok(())
+    }
+
+    fn read_line(&self) -> io::Result<Option<String>> {
+        Ok(self.input.borrow_mut().pop_front())
+    }
+}
@@ -0,0 +1,366 @@
+use crate::interpreter::ast::nodes::NodeType;
+use crate::interpreter::{Context, Error, Node, Position, StackRef, Type};
+use std::cell::{RefCell, RefMut};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// Called by the interpreter before it evaluates a node
+pub trait Hook {
+    fn before(&self, node: &Node, context: &Context) -> Result<(), Error>;
+
+    /// called when a loop starts another iteration, so the same nodes are
+    /// evaluated again
+    fn repeat(&self, _context: &Context) {}
+}
+
+/// A line of a file to pause at, lines start at 1
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Breakpoint {
+    file: String,
+    line: u64,
+}
+
+impl Breakpoint {
+    /// the file is resolved, so other paths to the same file match as well
+    pub fn new(file: &str, line: u64) -> Self {
+        Self {
+            file: resolve(file),
+            line,
+        }
+    }
+
+    pub fn get_file(&self) -> &str {
+        &self.file
+    }
+
+    pub fn get_line(&self) -> u64 {
+        self.line
+    }
+}
+
+/// the absolute path of the file without links, or the name itself if it
+/// isn't a file like `<stdin>`
+pub fn resolve(file: &str) -> String {
+    Path::new(file)
+        .canonicalize()
+        .map_or_else(|_| file.to_string(), |path| path.display().to_string())
+}
+
+/// How to go on after a pause
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    /// pause at the next line, also inside called functions
+    In,
+    /// pause at the next line of the current function
+    Over,
+    /// pause once the current function returned
+    Out,
+    /// pause only at breakpoints
+    Continue,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reason {
+    Entry,
+    Step,
+    Breakpoint,
+}
+
+/// A function call which is currently running or the script itself
+pub struct Frame {
+    pos: Position,
+    call: Option<Position>,
+    stack: StackRef,
+}
+
+impl Frame {
+    /// where the frame is: the paused node or the call of the next frame
+    pub fn get_pos(&self) -> &Position {
+        &self.pos
+    }
+
+    /// the call which created the frame, None for the script
+    pub fn get_call(&self) -> Option<&Position> {
+        self.call.as_ref()
+    }
+
+    pub fn get_stack(&self) -> StackRef {
+        self.stack.clone()
+    }
+
+    /// the variables of every stack visible in the frame, the innermost first
+    pub fn scopes(&self) -> Vec<Vec<(String, Type)>> {
+        let mut scopes = Vec::new();
+        let mut stack = Some(self.stack.clone());
+        while let Some(current) = stack {
+            scopes.push(current.borrow().variables());
+            stack = current.borrow().parent();
+        }
+        scopes
+    }
+}
+
+/// The state of the interpreter when it paused
+pub struct Pause {
+    reason: Reason,
+    frames: Vec<Frame>,
+}
+
+impl Pause {
+    fn new(reason: Reason, node: &Node, context: &Context) -> Self {
+        let mut frames = Vec::new();
+        let mut pos = node.get_pos();
+        let mut context = context;
+        loop {
+            frames.push(Frame {
+                pos,
+                call: context.context.as_ref().map(|_| context.get_position()),
+                stack: context.get_stack(),
+            });
+            match &context.context {
+                Some(parent) => {
+                    pos = context.get_position();
+                    context = parent;
+                }
+                None => break,
+            }
+        }
+        Self { reason, frames }
+    }
+
+    pub fn get_reason(&self) -> Reason {
+        self.reason
+    }
+
+    /// the current position
+    pub fn get_pos(&self) -> &Position {
+        &self.frames[0].pos
+    }
+
+    /// all running calls, the innermost first
+    pub fn get_frames(&self) -> &[Frame] {
+        &self.frames
+    }
+}
+
+/// The user interface of a debugger, e.g. a command line
+pub trait Frontend {
+    /// called whenever the interpreter pauses, returns how to go on. The
+    /// breakpoints can be changed
+    fn pause(&mut self, pause: &Pause, breakpoints: &mut BTreeSet<Breakpoint>) -> Step;
+}
+
+struct State {
+    step: Step,
+    // the depth of the call in which the last step started
+    depth: usize,
+    // the current line of every call
+    lines: Vec<Option<u64>>,
+    breakpoints: BTreeSet<Breakpoint>,
+    // the resolved paths of the files positions name
+    files: HashMap<String, String>,
+    entry: bool,
+}
+
+impl State {
+    /// whether there is a breakpoint at the line of `pos`
+    fn breakpoint(&mut self, pos: &Position, line: u64) -> bool {
+        if !self.breakpoints.iter().any(|b| b.line == line) {
+            return false;
+        }
+        let file = (self.files)
+            .entry(pos.filename().to_string())
+            .or_insert_with(|| resolve(pos.filename()));
+        self.breakpoints
+            .iter()
+            .any(|b| b.line == line && b.file == *file)
+    }
+}
+
+/// A hook which pauses at the first node of a line when a breakpoint or a
+/// step says so and lets the frontend decide how to go on
+pub struct Debugger<F> {
+    frontend: RefCell<F>,
+    state: RefCell<State>,
+}
+
+impl<F: Frontend> Debugger<F> {
+    pub fn new(frontend: F, breakpoints: BTreeSet<Breakpoint>, stop_on_entry: bool) -> Self {
+        Self {
+            frontend: RefCell::new(frontend),
+            state: RefCell::new(State {
+                step: if stop_on_entry {
+                    Step::In
+                } else {
+                    Step::Continue
+                },
+                depth: 0,
+                lines: Vec::new(),
+                breakpoints,
+                files: HashMap::new(),
+                entry: true,
+            }),
+        }
+    }
+
+    pub fn frontend(&self) -> RefMut<'_, F> {
+        self.frontend.borrow_mut()
+    }
+}
+
+impl<F: Frontend> Hook for Debugger<F> {
+    fn before(&self, node: &Node, context: &Context) -> Result<(), Error> {
+        // pause at the first statement of a block instead
+        if let NodeType::Statements(_) = node.get_type() {
+            return Ok(());
+        }
+        let depth = context.depth();
+        let line = node.get_pos().line() + 1;
+        let mut state = self.state.borrow_mut();
+        // forget the lines of calls which returned
+        state.lines.resize(depth + 1, None);
+        if state.lines[depth] == Some(line) {
+            return Ok(());
+        }
+        state.lines[depth] = Some(line);
+
+        let stepped = match state.step {
+            Step::In => true,
+            Step::Over => depth <= state.depth,
+            Step::Out => depth < state.depth,
+            Step::Continue => false,
+        };
+        let entry = std::mem::take(&mut state.entry);
+        let reason = if state.breakpoint(&node.get_pos(), line) {
+            Reason::Breakpoint
+        } else if stepped && entry {
+            Reason::Entry
+        } else if stepped {
+            Reason::Step
+        } else {
+            return Ok(());
+        };
+        let pause = Pause::new(reason, node, context);
+        state.step = self
+            .frontend
+            .borrow_mut()
+            .pause(&pause, &mut state.breakpoints);
+        state.depth = depth;
+        Ok(())
+    }
+
+    /// every iteration of a loop may pause at its lines again, even if the
+    /// whole loop is on one line
+    fn repeat(&self, context: &Context) {
+        if let Some(line) = self.state.borrow_mut().lines.get_mut(context.depth()) {
+            *line = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::capabilities::Capabilities;
+    use crate::interpreter::console::Buffer;
+    use crate::interpreter::{run_with, Options};
+    use std::fs;
+    use std::rc::Rc;
+
+    /// remembers where it paused and goes on with `steps`, then continues
+    struct Recorder {
+        pauses: Vec<(Reason, String, u64)>,
+        steps: Vec<Step>,
+    }
+
+    impl Frontend for Recorder {
+        fn pause(&mut self, pause: &Pause, _breakpoints: &mut BTreeSet<Breakpoint>) -> Step {
+            let pos = pause.get_pos();
+            let file = Path::new(pos.filename()).file_name().unwrap();
+            let file = file.to_string_lossy().to_string();
+            self.pauses.push((pause.get_reason(), file, pos.line() + 1));
+            if self.steps.is_empty() {
+                Step::Continue
+            } else {
+                self.steps.remove(0)
+            }
+        }
+    }
+
+    /// writes the files into a new directory and debugs the first one
+    fn debug(
+        name: &str,
+        files: &[(&str, &str)],
+        breakpoints: &[(&str, u64)],
+        steps: Vec<Step>,
+    ) -> Vec<(Reason, String, u64)> {
+        let dir = std::env::temp_dir().join(format!("phoenix-debug-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, text) in files {
+            fs::write(dir.join(file), text).unwrap();
+        }
+        let breakpoints = (breakpoints.iter())
+            .map(|(file, line)| Breakpoint::new(&dir.join(file).display().to_string(), *line))
+            .collect();
+        let recorder = Recorder {
+            pauses: Vec::new(),
+            steps,
+        };
+        let debugger = Rc::new(Debugger::new(recorder, breakpoints, false));
+        let options = Options::new()
+            .with_capabilities(Capabilities::all())
+            .with_console(Rc::new(Buffer::new()))
+            .with_hook(debugger.clone());
+        let main = dir.join(files[0].0).display().to_string();
+        let res = run_with(files[0].1.to_string(), main, None, options).res;
+        assert!(res.is_ok(), "{:?}", res);
+        let pauses = std::mem::take(&mut debugger.frontend().pauses);
+        pauses
+    }
+
+    #[test]
+    fn breakpoints_in_loops_on_one_line_fire_every_time() {
+        let text = "let i = 0\nwhile i < 3 then { let i = i + 1 }";
+        let pauses = debug(
+            "loop",
+            &[("main.phx", text)],
+            &[("main.phx", 2)],
+            Vec::new(),
+        );
+        // once for every check of the condition
+        assert_eq!(pauses.len(), 4);
+        assert!(pauses
+            .iter()
+            .all(|(reason, _, line)| *reason == Reason::Breakpoint && *line == 2));
+    }
+
+    #[test]
+    fn breakpoints_only_fire_in_their_file() {
+        let files = [
+            ("main.phx", "import util\nutil.f()\nutil.f()"),
+            ("util.phx", "fn f() -> {\n    1\n}"),
+        ];
+        let pauses = debug("files", &files, &[("util.phx", 2)], Vec::new());
+        let expected = (Reason::Breakpoint, "util.phx".to_string(), 2);
+        assert_eq!(pauses, vec![expected.clone(), expected]);
+        let pauses = debug("main", &files, &[("main.phx", 2)], Vec::new());
+        assert_eq!(
+            pauses,
+            vec![(Reason::Breakpoint, "main.phx".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn steps_enter_or_skip_calls() {
+        let text = "fn f() -> {\n    1\n}\nf()\n2";
+        let files = [("main.phx", text)];
+        let lines = |steps: Vec<Step>| -> Vec<u64> {
+            let pauses = debug("steps", &files, &[("main.phx", 4)], steps);
+            pauses.into_iter().map(|(_, _, line)| line).collect()
+        };
+        assert_eq!(lines(vec![Step::Over, Step::Over]), vec![4, 5]);
+        assert_eq!(lines(vec![Step::In, Step::In, Step::In]), vec![4, 2, 5]);
+        assert_eq!(lines(vec![Step::In, Step::Out]), vec![4, 2, 5]);
+    }
+}
@@ -0,0 +1,499 @@
+use crate::interpreter::ast::nodes::{Node, NodeType, OperationType, Part, Pattern, Variable};
+use crate::interpreter::stack::Stack;
+use crate::interpreter::token::types::Type;
+use crate::interpreter::token::{Segment, Token};
+use crate::interpreter::{lexer, limits, parse, resolver, Error, Position};
+use serde_json::{json, Value};
+
+/// How a dump is printed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+/// A generic tree of tokens or nodes, so both can be printed the same way
+struct Tree {
+    // the field of the parent this tree is stored in, e.g. `left`
+    role: Option<&'static str>,
+    kind: &'static str,
+    value: Option<String>,
+    attributes: Vec<(&'static str, String)>,
+    pos: Position,
+    children: Vec<Tree>,
+}
+
+impl Tree {
+    fn new(kind: &'static str, value: Option<String>, pos: Position) -> Self {
+        Self {
+            role: None,
+            kind,
+            value,
+            attributes: Vec::new(),
+            pos,
+            children: Vec::new(),
+        }
+    }
+
+    fn attribute(mut self, name: &'static str, value: String) -> Self {
+        self.attributes.push((name, value));
+        self
+    }
+
+    fn child(mut self, role: &'static str, child: Tree) -> Self {
+        self.children.push(Tree {
+            role: Some(role),
+            ..child
+        });
+        self
+    }
+
+    fn write_text(&self, indent: usize, out: &mut String) {
+        out.push_str(&"  ".repeat(indent));
+        if let Some(role) = self.role {
+            out.push_str(&format!("{}: ", role));
+        }
+        out.push_str(self.kind);
+        if let Some(value) = &self.value {
+            out.push_str(&format!(" {}", value));
+        }
+        for (name, value) in &self.attributes {
+            out.push_str(&format!(" {}={}", name, value));
+        }
+        out.push_str(&format!(
+            " ({}:{}, len {})\n",
+            self.pos.line + 1,
+            self.pos.column,
+            self.pos.len
+        ));
+        for child in &self.children {
+            limits::grow(|| child.write_text(indent + 1, out));
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut value = json!({
+            "kind": self.kind,
+            "position": {
+                "index": self.pos.index,
+                "line": self.pos.line + 1,
+                "column": self.pos.column,
+                "len": self.pos.len,
+            },
+        });
+        if let Some(role) = self.role {
+            value["role"] = json!(role);
+        }
+        if let Some(v) = &self.value {
+            value["value"] = json!(v);
+        }
+        for (name, v) in &self.attributes {
+            value[*name] = json!(v);
+        }
+        if !self.children.is_empty() {
+            value["children"] = self.children.iter().map(Tree::to_json).collect();
+        }
+        value
+    }
+}
+
+/// lexes `text` and prints every token with its position
+pub fn tokens(text: &str, file_name: String, format: Format) -> Result<String, Error> {
+    let tokens = lexer::Lexer::new(text, file_name).make_tokens()?;
+    let trees = tokens
+        .into_iter()
+        .map(|(token, pos)| token_tree(token, pos));
+    Ok(match format {
+        Format::Text => {
+            let mut out = String::new();
+            for tree in trees {
+                tree.write_text(0, &mut out);
+            }
+            out
+        }
+        Format::Json => {
+            serde_json::to_string_pretty(&trees.map(|t| t.to_json()).collect::<Value>()).unwrap()
+        }
+    })
+}
+
+/// parses and resolves `text` and prints the syntax tree with the positions
+/// of all nodes and the slots of all variables
+pub fn ast(text: &str, file_name: String, format: Format) -> Result<String, Error> {
+    let ast = parse(text, file_name)?;
+    resolver::Resolver::new(&mut Stack::new(None)).resolve(&ast);
+    let tree = node_tree(&ast);
+    Ok(match format {
+        Format::Text => {
+            let mut out = String::new();
+            tree.write_text(0, &mut out);
+            out
+        }
+        Format::Json => serde_json::to_string_pretty(&tree.to_json()).unwrap(),
+    })
+}
+
+fn token_tree(token: Token, pos: Position) -> Tree {
+    let (kind, value) = token_kind(&token);
+    let mut tree = Tree::new(kind, value, pos);
+    if let Token::Interpolation(segments) = token {
+        for segment in segments.iter() {
+            match segment {
+                Segment::Text(text, pos) => {
+                    tree = tree.child(
+                        "text",
+                        Tree::new("String", Some(format!("{:?}", text)), pos.clone()),
+                    )
+                }
+                Segment::Expr(tokens) => {
+                    for (token, pos) in tokens {
+                        let child = limits::grow(|| token_tree(token.clone(), pos.clone()));
+                        tree = tree.child("expr", child);
+                    }
+                }
+            }
+        }
+    }
+    tree
+}
+
+fn token_kind(token: &Token) -> (&'static str, Option<String>) {
+    let kind = match token {
+        Token::Plus => "Plus",
+        Token::Minus => "Minus",
+        Token::Star => "Star",
+        Token::Slash => "Slash",
+        Token::LeftParenthesis => "LeftParenthesis",
+        Token::RightParenthesis => "RightParenthesis",
+        Token::LeftCurlyBrackets => "LeftCurlyBrackets",
+        Token::LeftSquareBrackets => "LeftSquareBrackets",
+        Token::RightSquareBrackets => "RightSquareBrackets",
+        Token::RightCurlyBrackets => "RightCurlyBrackets",
+        Token::Equal => "Equal",
+        Token::DoubleEqual => "DoubleEqual",
+        Token::NonEqual => "NonEqual",
+        Token::LessThan => "LessThan",
+        Token::GreaterThan => "GreaterThan",
+        Token::LessThanEq => "LessThanEq",
+        Token::GreaterThanEq => "GreaterThanEq",
+        Token::DoubleAnd => "DoubleAnd",
+        Token::DoubleOr => "DoubleOr",
+        Token::Bang => "Bang",
+        Token::Comma => "Comma",
+        Token::Dot => "Dot",
+        Token::Ellipsis => "Ellipsis",
+        Token::Colon => "Colon",
+        Token::Semicolon => "Semicolon",
+        Token::Newline => "Newline",
+        Token::Arrow => "Arrow",
+        Token::Ident(_) => "Ident",
+        Token::Type(ty) => return (type_kind(ty), Some(literal(ty))),
+        Token::Keyword(_) => "Keyword",
+        Token::Interpolation(_) => "Interpolation",
+        Token::EOF => "EOF",
+    };
+    let value = match token {
+        Token::Ident(_) | Token::Keyword(_) => Some(token.to_string()),
+        _ => None,
+    };
+    (kind, value)
+}
+
+fn type_kind(ty: &Type) -> &'static str {
+    match ty {
+        Type::Integer(_) => "Integer",
+        Type::Float(_) => "Float",
+        Type::String(_) => "String",
+        Type::List(_) => "List",
+        Type::Tuple(_) => "Tuple",
+        Type::Function(_) => "Function",
+        Type::Builtin(_) => "Builtin",
+        Type::Module(_) => "Module",
+        Type::Struct(_) => "Struct",
+        Type::Object(_) => "Object",
+        Type::Enum(_) => "Enum",
+        Type::None(_) => "None",
+    }
+}
+
+fn literal(ty: &Type) -> String {
+    match ty {
+        Type::Float(v) => format!("{:?}", v.get_value()),
+        Type::String(v) => format!("{:?}", v.get_value()),
+        ty => ty.to_string(),
+    }
+}
+
+fn var_tree(kind: &'static str, var: &Variable, pos: Position) -> Tree {
+    let tree = Tree::new(kind, Some(var.get_name().to_string()), pos);
+    match var.get_slot() {
+        Some(slot) => tree.attribute("slot", format!("{}:{}", slot.depth(), slot.index())),
+        None => tree,
+    }
+}
+
+fn pattern_tree(pattern: &Pattern) -> Tree {
+    let pos = pattern.get_pos();
+    match pattern {
+        Pattern::Wildcard(_) => Tree::new("Wildcard", None, pos),
+        Pattern::Binding(var) => var_tree("Binding", var, pos),
+        Pattern::Literal(ty, _) => Tree::new(type_kind(ty), Some(literal(ty)), pos),
+        Pattern::Tuple(patterns, _) => {
+            let mut tree = Tree::new("TuplePattern", None, pos);
+            for pattern in patterns {
+                tree = tree.child("item", pattern_tree(pattern));
+            }
+            tree
+        }
+        Pattern::List(list) => {
+            let mut tree = Tree::new("ListPattern", None, pos);
+            for pattern in list.get_items() {
+                tree = tree.child("item", pattern_tree(pattern));
+            }
+            match list.get_rest() {
+                Some(rest) => tree.child("rest", pattern_tree(rest)),
+                None => tree,
+            }
+        }
+        Pattern::Constructor(constructor) => {
+            let mut tree = var_tree("Constructor", constructor.get_type(), pos);
+            if let Some((variant, _)) = constructor.get_variant() {
+                tree = tree.attribute("variant", variant.to_string());
+            }
+            for arg in constructor.get_args().unwrap_or_default() {
+                tree = tree.child("field", pattern_tree(arg));
+            }
+            tree
+        }
+    }
+}
+
+fn node_tree(node: &Node) -> Tree {
+    limits::grow(|| make_tree(node))
+}
+
+fn make_tree(node: &Node) -> Tree {
+    let pos = node.get_pos();
+    match node.get_type() {
+        NodeType::Node(ty) => Tree::new(type_kind(ty), Some(literal(ty)), pos),
+        NodeType::Operation(OperationType::BinaryOperationNode(op)) => {
+            Tree::new("BinaryOperation", Some(op.get_operation().to_string()), pos)
+                .child("left", node_tree(op.get_left()))
+                .child("right", node_tree(op.get_right()))
+        }
+        NodeType::Operation(OperationType::UnaryOperationNode(op)) => {
+            Tree::new("UnaryOperation", Some(op.get_operation().to_string()), pos)
+                .child("operand", node_tree(op.get_node()))
+        }
+        NodeType::Assign(assign) => {
+            let mut tree = var_tree("Assign", assign.get_var(), pos);
+            if let Some(ty) = assign.get_type() {
+                tree = tree.attribute("type", ty.to_string());
+            }
+            tree.child("value", node_tree(assign.get_expr()))
+        }
+        NodeType::Destructure(destructure) => Tree::new("Destructure", None, pos)
+            .child("pattern", pattern_tree(destructure.get_pattern()))
+            .child("value", node_tree(destructure.get_expr())),
+        NodeType::Var(var) => var_tree("Var", var, pos),
+        NodeType::Tuple(tuple) => {
+            let mut tree = Tree::new("Tuple", None, pos);
+            for item in tuple.get_items() {
+                tree = tree.child("item", node_tree(item));
+            }
+            tree
+        }
+        NodeType::IfNode(node) => {
+            let mut tree = Tree::new("If", None, pos);
+            for (condition, expr) in node.get_cases() {
+                tree = tree
+                    .child("condition", node_tree(condition))
+                    .child("then", node_tree(expr));
+            }
+            match node.get_else_case() {
+                Some(else_case) => tree.child("else", node_tree(else_case)),
+                None => tree,
+            }
+        }
+        NodeType::ForNode(node) => {
+            let (var, start, end, body) = node.get_all();
+            let tree = var_tree("For", var, pos).child("start", node_tree(start));
+            let tree = match end {
+                Some(end) => tree.child("end", node_tree(end)),
+                None => tree,
+            };
+            tree.child("body", node_tree(body))
+        }
+        NodeType::WhileNode(node) => {
+            let (cond, body) = node.get_all();
+            Tree::new("While", None, pos)
+                .child("condition", node_tree(cond))
+                .child("body", node_tree(body))
+        }
+        NodeType::FuncDefNode(node) => {
+            let (name, params, body) = node.get_all();
+            let tree = match name {
+                Some(name) => var_tree("FuncDef", name, pos),
+                None => Tree::new("FuncDef", None, pos),
+            };
+            let (types, returns) = node.get_types();
+            let params: Vec<String> = (params.iter().zip(types))
+                .map(|(param, ty)| match ty {
+                    Some(ty) => format!("{}: {}", param, ty),
+                    None => param.to_string(),
+                })
+                .collect();
+            let mut tree = tree.attribute("params", format!("({})", params.join(",")));
+            if let Some(returns) = returns {
+                tree = tree.attribute("returns", returns.to_string());
+            }
+            if let Some(locals) = node.get_locals() {
+                let locals: Vec<String> = locals.iter().map(|l| l.to_string()).collect();
+                tree = tree.attribute("locals", format!("({})", locals.join(",")));
+            }
+            tree.child("body", node_tree(body))
+        }
+        NodeType::CallNode(call) => {
+            let (callee, args) = call.get_all();
+            let mut tree = Tree::new("Call", None, pos).child("callee", node_tree(callee));
+            for arg in args {
+                tree = tree.child("arg", node_tree(arg));
+            }
+            for (name, arg) in call.get_named() {
+                let arg = node_tree(arg).attribute("name", name.to_string());
+                tree = tree.child("arg", arg);
+            }
+            tree
+        }
+        NodeType::AttributeNode(attribute) => {
+            Tree::new("Attribute", Some(attribute.get_name().to_string()), pos)
+                .child("value", node_tree(attribute.get_value()))
+        }
+        NodeType::IndexNode(node) => {
+            let (value, index) = node.get_all();
+            Tree::new("Index", None, pos)
+                .child("value", node_tree(value))
+                .child("index", node_tree(index))
+        }
+        NodeType::SetAttribute(node) => {
+            let attribute = node.get_attribute();
+            Tree::new("SetAttribute", Some(attribute.get_name().to_string()), pos)
+                .child("object", node_tree(attribute.get_value()))
+                .child("value", node_tree(node.get_expr()))
+        }
+        NodeType::StructDef(node) => {
+            let fields: Vec<String> = node.get_fields().iter().map(|f| f.to_string()).collect();
+            let mut tree = var_tree("StructDef", node.get_name(), pos)
+                .attribute("fields", format!("({})", fields.join(",")));
+            for method in node.get_methods() {
+                tree = tree.child("method", node_tree(method));
+            }
+            tree
+        }
+        NodeType::EnumDef(node) => {
+            let mut tree = var_tree("EnumDef", node.get_name(), pos);
+            for variant in node.get_variants() {
+                let mut child = Tree::new(
+                    "Variant",
+                    Some(variant.get_name().to_string()),
+                    variant.get_pos(),
+                );
+                if let Some(fields) = variant.get_fields() {
+                    let fields: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+                    child = child.attribute("fields", format!("({})", fields.join(",")));
+                }
+                tree = tree.child("variant", child);
+            }
+            tree
+        }
+        NodeType::Match(node) => {
+            let mut tree =
+                Tree::new("Match", None, pos).child("value", node_tree(node.get_value()));
+            for arm in node.get_arms() {
+                let pattern = arm.get_pattern();
+                let mut child = Tree::new("Arm", None, pattern.get_pos())
+                    .child("pattern", pattern_tree(pattern));
+                if let Some(guard) = arm.get_guard() {
+                    child = child.child("guard", node_tree(guard));
+                }
+                tree = tree.child("arm", child.child("body", node_tree(arm.get_body())));
+            }
+            tree
+        }
+        NodeType::Import(import) => {
+            let mut tree = Tree::new("Import", Some(import.get_source().to_string()), pos);
+            for var in import.get_vars() {
+                tree = tree.child("name", var_tree("Var", var, var.get_pos()));
+            }
+            tree
+        }
+        NodeType::Interpolation(interpolation) => {
+            let mut tree = Tree::new("Interpolation", None, pos);
+            for part in interpolation.get_parts() {
+                tree = match part {
+                    Part::Text(text, pos) => tree.child(
+                        "text",
+                        Tree::new("String", Some(format!("{:?}", text)), pos.clone()),
+                    ),
+                    Part::Expr(expr) => tree.child("expr", node_tree(expr)),
+                };
+            }
+            tree
+        }
+        NodeType::Statements(statements) => {
+            let mut tree = Tree::new("Statements", None, pos);
+            for statement in statements.get_nodes() {
+                tree = tree.child("statement", node_tree(statement));
+            }
+            tree
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_are_dumped_with_their_positions() {
+        let dump = tokens("let x = 0x1f\nx", "test".to_string(), Format::Text).unwrap();
+        assert_eq!(
+            dump,
+            "Keyword let (1:0, len 3)
+Ident x (1:4, len 1)
+Equal (1:6, len 1)
+Integer 31 (1:8, len 4)
+Newline (1:12, len 1)
+Ident x (2:0, len 1)
+EOF (2:1, len 0)
+"
+        );
+    }
+
+    #[test]
+    fn trees_are_dumped_with_the_slots_of_variables() {
+        let dump = ast("fn (a) -> a + 1", "test".to_string(), Format::Text).unwrap();
+        assert_eq!(
+            dump,
+            "Statements (1:0, len 15)
+  statement: FuncDef params=(a) locals=(a) (1:0, len 15)
+    body: BinaryOperation + (1:10, len 5)
+      left: Var a slot=0:0 (1:10, len 1)
+      right: Integer 1 (1:14, len 1)
+"
+        );
+        let json: Value =
+            serde_json::from_str(&ast("x", "test".into(), Format::Json).unwrap()).unwrap();
+        let var = &json["children"][0];
+        assert_eq!(var["kind"], "Var");
+        assert_eq!(var["role"], "statement");
+        assert_eq!(var["value"], "x");
+        assert_eq!(var["position"]["line"], 1);
+    }
+
+    #[test]
+    fn dumping_reports_syntax_errors() {
+        assert!(tokens("0xZZ", "test".into(), Format::Json).is_err());
+        assert!(ast("(1 +", "test".into(), Format::Text).is_err());
+    }
+}
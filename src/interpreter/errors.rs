@@ -1,6 +1,6 @@
 use crate::interpreter::{Context, Position};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ErrorKind {
     SyntaxError,
     EndOfFile,
@@ -9,6 +9,18 @@ pub enum ErrorKind {
     NameError,
     Unimplemented,
     TypeError,
+    Timeout,
+    RecursionLimit,
+    MemoryLimit,
+    Cancelled,
+    PermissionError,
+    ValueError,
+    IOError,
+    ImportError,
+    /// no arm of a match fits the value, or the arms miss a variant
+    MatchError,
+    /// the script called `exit` with this code
+    Exit(i64),
 }
 
 #[derive(Clone)]
@@ -16,7 +28,7 @@ pub struct Error {
     kind: ErrorKind,
     message: String,
     position: Option<Position>,
-    context: Option<Context>,
+    context: Option<Box<Context>>,
 }
 
 impl Error {
@@ -29,22 +41,35 @@ impl Error {
         }
     }
 
-    // TODO use this
-    /*pub fn with_position(self, position: Position) -> Self {
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn position(&self) -> Option<&Position> {
+        self.position.as_ref()
+    }
+
+    /// sets the position unless the error already has one
+    pub fn or_position(self, position: &Position) -> Self {
         Self {
-            kind: self.kind,
-            message: self.message,
-            position: Some(position),
-            context: None
+            position: self.position.or_else(|| Some(position.clone())),
+            ..self
         }
-    }*/
+    }
 
-    pub fn with_context(self, context: Context) -> Self {
-        Self {
-            kind: self.kind,
-            message: self.message,
-            position: None,
-            context: Some(context),
+    /// attaches the context unless the error already knows where it happened
+    pub fn or_context(self, context: Context) -> Self {
+        if self.context.is_some() {
+            self
+        } else {
+            Self {
+                context: Some(Box::new(context)),
+                ..self
+            }
         }
     }
 }
@@ -62,7 +87,7 @@ impl std::fmt::Display for Error {
             );
         }
         if let Some(context) = &self.context {
-            let mut context = Some(Box::new(context.clone()));
+            let mut context = Some(context.as_ref());
             while let Some(ctx) = context {
                 traceback = format!(
                     "  File {}, line {}, column {}:\n{}",
@@ -71,7 +96,7 @@ impl std::fmt::Display for Error {
                     ctx.pos.column,
                     traceback
                 );
-                context = ctx.context;
+                context = ctx.context.as_deref();
             }
         }
         if !traceback.is_empty() {
@@ -80,3 +105,9 @@ impl std::fmt::Display for Error {
         write!(f, "{}{:?}: {}", position_hint, self.kind, self.message)
     }
 }
+
+impl std::fmt::Debug for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
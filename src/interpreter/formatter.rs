@@ -0,0 +1,554 @@
+use crate::interpreter::ast::nodes::{Node, NodeType, OperationType, UnaryOperation};
+use crate::interpreter::token::Token;
+use crate::interpreter::{ast, lexer, limits, Error};
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Lines longer than this are broken up where possible
+const WIDTH: usize = 100;
+const INDENT: &str = "    ";
+
+/// formats `text` in the canonical style, keeping all comments
+pub fn format(text: &str, file_name: String) -> Result<String, Error> {
+    let mut lexer = lexer::Lexer::new(text, file_name);
+    let tokens = lexer.make_tokens()?;
+    let ast = ast::Parser::new(tokens).parse()?;
+    let mut formatter = Formatter {
+        text,
+        comments: lexer
+            .comments()
+            .iter()
+            .map(|pos| pos.index as usize..(pos.index as u64 + pos.len()) as usize)
+            .collect(),
+        next: 0,
+        broken: HashSet::new(),
+    };
+    let nodes = match ast.get_type() {
+        NodeType::Statements(statements) => statements.get_nodes(),
+        _ => std::slice::from_ref(&ast),
+    };
+    let mut lines = Vec::new();
+    formatter.body(nodes, 0, text.len(), &mut lines);
+    if lines.is_empty() {
+        return Ok(String::new());
+    }
+    Ok(lines.join("\n") + "\n")
+}
+
+/// how tightly a node binds, nodes with a lower precedence than their
+/// surrounding need parentheses
+fn precedence(node: &Node) -> u8 {
+    match node.get_type() {
+        NodeType::Assign(_) | NodeType::Destructure(_) | NodeType::SetAttribute(_) => 0,
+        NodeType::Operation(OperationType::BinaryOperationNode(op)) => match op.get_operation() {
+            Token::DoubleAnd | Token::DoubleOr => 1,
+            Token::Plus | Token::Minus => 4,
+            Token::Star | Token::Slash => 5,
+            _ => 3,
+        },
+        NodeType::Operation(OperationType::UnaryOperationNode(op)) => match op.get_operation() {
+            UnaryOperation::Not => 2,
+            _ => 6,
+        },
+        NodeType::CallNode(_) | NodeType::AttributeNode(_) | NodeType::IndexNode(_) => 7,
+        _ => 8,
+    }
+}
+
+/// the precedence the operand of a unary operation needs
+fn unary_operand(operation: &UnaryOperation) -> u8 {
+    match operation {
+        UnaryOperation::Not => 2,
+        _ => 6,
+    }
+}
+
+/// whether the printed node would swallow everything following it, like the
+/// body of a function
+fn is_open(node: &Node) -> bool {
+    match node.get_type() {
+        NodeType::Assign(_)
+        | NodeType::Destructure(_)
+        | NodeType::SetAttribute(_)
+        | NodeType::IfNode(_)
+        | NodeType::ForNode(_)
+        | NodeType::WhileNode(_)
+        | NodeType::FuncDefNode(_) => true,
+        NodeType::Operation(OperationType::BinaryOperationNode(op)) => {
+            precedence(op.get_right()) > precedence(node) && is_open(op.get_right())
+        }
+        NodeType::Operation(OperationType::UnaryOperationNode(op)) => {
+            precedence(op.get_node()) >= unary_operand(op.get_operation()) && is_open(op.get_node())
+        }
+        _ => false,
+    }
+}
+
+fn start(node: &Node) -> usize {
+    node.get_pos().index as usize
+}
+
+struct Formatter<'a> {
+    text: &'a str,
+    comments: Vec<Range<usize>>,
+    // the first comment which isn't printed yet
+    next: usize,
+    // the nodes which don't fit in a single line, so they aren't printed flat
+    // again for every node around them
+    broken: HashSet<*const Node>,
+}
+
+impl Formatter<'_> {
+    fn line_start(&self, index: usize) -> usize {
+        self.text[..index].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    /// whether nothing but whitespace is in front of `index` in its line
+    fn is_first_in_line(&self, index: usize) -> bool {
+        self.text[self.line_start(index)..index].trim().is_empty()
+    }
+
+    fn follows_blank_line(&self, index: usize) -> bool {
+        let start = self.line_start(index);
+        start > 0 && {
+            let previous = self.line_start(start - 1);
+            self.text[previous..start - 1].trim().is_empty()
+        }
+    }
+
+    fn has_comments(&self, range: Range<usize>) -> bool {
+        self.comments[self.next..]
+            .iter()
+            .any(|comment| range.contains(&comment.start))
+    }
+
+    /// prints all comments before `limit`. Comments which shared their line
+    /// with code stay at the end of the last line
+    fn comments_before(&mut self, limit: usize, indent: usize, lines: &mut Vec<String>) {
+        while let Some(comment) = self.comments.get(self.next).cloned() {
+            if comment.start >= limit {
+                break;
+            }
+            self.next += 1;
+            let text = self.text[comment.clone()].trim_end();
+            match lines.last_mut() {
+                Some(line) if !self.is_first_in_line(comment.start) => {
+                    line.push_str("  ");
+                    line.push_str(text);
+                }
+                _ => {
+                    if !lines.is_empty() && self.follows_blank_line(comment.start) {
+                        lines.push(String::new());
+                    }
+                    lines.push(INDENT.repeat(indent) + text);
+                }
+            }
+        }
+    }
+
+    /// prints statements one per line, keeping single blank lines between them
+    fn body(&mut self, nodes: &[Node], indent: usize, end: usize, lines: &mut Vec<String>) {
+        let first_line = lines.len();
+        for node in nodes {
+            self.comments_before(start(node), indent, lines);
+            if lines.len() > first_line && self.follows_blank_line(start(node)) {
+                lines.push(String::new());
+            }
+            let statement = self.fit(node, indent);
+            lines.push(INDENT.repeat(indent) + &statement);
+        }
+        self.comments_before(end, indent, lines);
+    }
+
+    /// prints the node in a single line if it fits, otherwise broken up.
+    /// Only the first line of the result isn't indented
+    fn fit(&mut self, node: &Node, indent: usize) -> String {
+        match self.node(node, indent, true) {
+            Some(flat) if indent * INDENT.len() + flat.chars().count() <= WIDTH => flat,
+            _ => self.node(node, indent, false).unwrap(),
+        }
+    }
+
+    /// prints `node` as part of another node, adding parentheses if needed
+    fn operand(
+        &mut self,
+        node: &Node,
+        precedence: u8,
+        followed: bool,
+        indent: usize,
+        flat: bool,
+    ) -> Option<String> {
+        let text = if flat {
+            self.node(node, indent, true)?
+        } else {
+            self.fit(node, indent)
+        };
+        Some(self::wrap(node, text, precedence, followed))
+    }
+
+    /// like `operand`, but breaks the node up in broken mode, used for the
+    /// last part of a node
+    fn last_operand(
+        &mut self,
+        node: &Node,
+        precedence: u8,
+        indent: usize,
+        flat: bool,
+    ) -> Option<String> {
+        let text = self.node(node, indent, flat)?;
+        Some(self::wrap(node, text, precedence, false))
+    }
+
+    /// prints the node, returns None if it can't be printed in a single line
+    /// but `flat` is set
+    fn node(&mut self, node: &Node, indent: usize, flat: bool) -> Option<String> {
+        if !flat {
+            return limits::grow(|| self.format_node(node, indent, false));
+        }
+        if self.broken.contains(&(node as *const Node)) {
+            return None;
+        }
+        // a single line longer than the width never fits, neither does a node
+        // around it
+        let text = limits::grow(|| self.format_node(node, indent, true))
+            .filter(|text| text.chars().count() <= WIDTH);
+        if text.is_none() {
+            self.broken.insert(node);
+        }
+        text
+    }
+
+    /// prints a chain of operators with the same precedence in parentheses,
+    /// one operand per line after its operator, as newlines only continue
+    /// an expression inside brackets
+    fn chain(&mut self, node: &Node, indent: usize) -> String {
+        let precedence = precedence(node);
+        let mut operands = Vec::new();
+        let mut left = node;
+        while let NodeType::Operation(OperationType::BinaryOperationNode(op)) = left.get_type() {
+            if self::precedence(left) != precedence {
+                break;
+            }
+            operands.push((Some(op.get_operation()), op.get_right()));
+            left = op.get_left();
+        }
+        operands.push((None, left));
+        operands.reverse();
+        let mut lines = vec!["(".to_string()];
+        let last = operands.len() - 1;
+        for (i, (operation, operand)) in operands.into_iter().enumerate() {
+            self.comments_before(start(operand), indent + 1, &mut lines);
+            let text = self.fit(operand, indent + 1);
+            let text = match operation {
+                Some(operation) => {
+                    let text = self::wrap(operand, text, precedence + 1, i < last);
+                    format!("{} {}", operation, text)
+                }
+                None => self::wrap(operand, text, precedence, true),
+            };
+            lines.push(INDENT.repeat(indent + 1) + &text);
+        }
+        lines.push(format!("{})", INDENT.repeat(indent)));
+        lines.join("\n")
+    }
+
+    fn format_node(&mut self, node: &Node, indent: usize, flat: bool) -> Option<String> {
+        Some(match node.get_type() {
+            NodeType::Node(_) | NodeType::Interpolation(_) => {
+                // keep the literal as it was written, e.g. in hex
+                let pos = node.get_pos();
+                self.text[pos.index as usize..(pos.index as u64 + pos.len()) as usize].to_string()
+            }
+            NodeType::Var(var) => var.to_string(),
+            NodeType::Import(import) => import.to_string(),
+            NodeType::Operation(OperationType::BinaryOperationNode(_)) if !flat => {
+                self.chain(node, indent)
+            }
+            NodeType::Operation(OperationType::BinaryOperationNode(op)) => {
+                let precedence = precedence(node);
+                format!(
+                    "{} {} {}",
+                    self.operand(op.get_left(), precedence, true, indent, true)?,
+                    op.get_operation(),
+                    self.last_operand(op.get_right(), precedence + 1, indent, true)?
+                )
+            }
+            NodeType::Operation(OperationType::UnaryOperationNode(op)) => {
+                let operation = op.get_operation();
+                format!(
+                    "{}{}",
+                    operation,
+                    self.last_operand(op.get_node(), unary_operand(operation), indent, flat)?
+                )
+            }
+            NodeType::Assign(assign) => {
+                let ty = assign.get_type().map(|ty| format!(": {}", ty));
+                format!(
+                    "let {}{} = {}",
+                    assign.get_var(),
+                    ty.unwrap_or_default(),
+                    self.last_operand(assign.get_expr(), 0, indent, flat)?
+                )
+            }
+            NodeType::Destructure(destructure) => format!(
+                "let {} = {}",
+                destructure.get_pattern(),
+                self.last_operand(destructure.get_expr(), 0, indent, flat)?
+            ),
+            NodeType::Tuple(tuple) => {
+                let items = tuple.get_items();
+                let has_comments = self.has_comments(start(node)..node.end_index());
+                if flat && has_comments {
+                    return None;
+                } else if flat || (items.is_empty() && !has_comments) {
+                    let items = (items.iter())
+                        .map(|item| self.node(item, indent, true))
+                        .collect::<Option<Vec<_>>>()?;
+                    match items.len() {
+                        1 => format!("({},)", items[0]),
+                        _ => format!("({})", items.join(", ")),
+                    }
+                } else {
+                    // one item per line, a single one keeps its comma
+                    let mut lines = vec!["(".to_string()];
+                    for (i, item) in items.iter().enumerate() {
+                        self.comments_before(start(item), indent + 1, &mut lines);
+                        let comma = if i + 1 < items.len() || items.len() == 1 {
+                            ","
+                        } else {
+                            ""
+                        };
+                        let item = self.fit(item, indent + 1);
+                        lines.push(format!("{}{}{}", INDENT.repeat(indent + 1), item, comma));
+                    }
+                    self.comments_before(node.end_index(), indent + 1, &mut lines);
+                    lines.push(format!("{})", INDENT.repeat(indent)));
+                    lines.join("\n")
+                }
+            }
+            NodeType::IndexNode(index) => {
+                let (value, index) = index.get_all();
+                format!(
+                    "{}[{}]",
+                    self.operand(value, 7, true, indent, flat)?,
+                    self.node(index, indent, flat)?
+                )
+            }
+            NodeType::SetAttribute(set) => {
+                let attribute = set.get_attribute();
+                format!(
+                    "{}.{} = {}",
+                    self.operand(attribute.get_value(), 7, true, indent, flat)?,
+                    attribute.get_name(),
+                    self.last_operand(set.get_expr(), 0, indent, flat)?
+                )
+            }
+            NodeType::StructDef(def) => {
+                let fields: Vec<String> = def.get_fields().iter().map(|f| f.to_string()).collect();
+                let methods = def.get_methods();
+                let has_comments = self.has_comments(start(node)..node.end_index());
+                if methods.is_empty() && !has_comments {
+                    if fields.is_empty() {
+                        format!("struct {} {{}}", def.get_name())
+                    } else {
+                        format!("struct {} {{ {} }}", def.get_name(), fields.join(", "))
+                    }
+                } else if flat {
+                    return None;
+                } else {
+                    // the fields in the first line, then one method per line
+                    let mut lines = vec![format!("struct {} {{", def.get_name())];
+                    if let Some(first) = def.get_fields().first() {
+                        let first = first.get_pos().index as usize;
+                        self.comments_before(first, indent + 1, &mut lines);
+                        lines.push(INDENT.repeat(indent + 1) + &fields.join(", "));
+                    }
+                    self.body(methods, indent + 1, node.end_index() - 1, &mut lines);
+                    lines.push(format!("{}}}", INDENT.repeat(indent)));
+                    lines.join("\n")
+                }
+            }
+            NodeType::EnumDef(def) => {
+                let variants: Vec<String> =
+                    def.get_variants().iter().map(|v| v.to_string()).collect();
+                let has_comments = self.has_comments(start(node)..node.end_index());
+                if flat && has_comments {
+                    return None;
+                } else if variants.is_empty() && !has_comments {
+                    format!("enum {} {{}}", def.get_name())
+                } else if flat {
+                    format!("enum {} {{ {} }}", def.get_name(), variants.join(", "))
+                } else {
+                    // one variant per line
+                    let mut lines = vec![format!("enum {} {{", def.get_name())];
+                    for (variant, text) in def.get_variants().iter().zip(variants) {
+                        let first = variant.get_pos().index as usize;
+                        self.comments_before(first, indent + 1, &mut lines);
+                        lines.push(INDENT.repeat(indent + 1) + &text);
+                    }
+                    self.comments_before(node.end_index() - 1, indent + 1, &mut lines);
+                    lines.push(format!("{}}}", INDENT.repeat(indent)));
+                    lines.join("\n")
+                }
+            }
+            NodeType::Match(match_node) => {
+                let value = self.operand(match_node.get_value(), 0, true, indent, flat)?;
+                let arms = match_node.get_arms();
+                let has_comments = self.has_comments(start(node)..node.end_index());
+                if arms.is_empty() && !has_comments {
+                    format!("match {} {{}}", value)
+                } else if flat {
+                    return None;
+                } else {
+                    // one arm per line
+                    let mut lines = vec![format!("match {} {{", value)];
+                    for arm in arms {
+                        let pattern = arm.get_pattern();
+                        self.comments_before(
+                            pattern.get_pos().index as usize,
+                            indent + 1,
+                            &mut lines,
+                        );
+                        let mut text = INDENT.repeat(indent + 1) + &pattern.to_string();
+                        if let Some(guard) = arm.get_guard() {
+                            text.push_str(" if ");
+                            text.push_str(&self.operand(guard, 0, true, indent + 1, false)?);
+                        }
+                        text.push_str(" -> ");
+                        text.push_str(&self.fit(arm.get_body(), indent + 1));
+                        lines.push(text);
+                    }
+                    self.comments_before(node.end_index() - 1, indent + 1, &mut lines);
+                    lines.push(format!("{}}}", INDENT.repeat(indent)));
+                    lines.join("\n")
+                }
+            }
+            NodeType::IfNode(if_node) => {
+                let mut text = String::new();
+                let cases = if_node.get_cases();
+                for (i, (condition, expr)) in cases.iter().enumerate() {
+                    let condition = self.operand(condition, 0, true, indent, flat)?;
+                    let expr = if i + 1 < cases.len() || if_node.get_else_case().is_some() {
+                        let expr_text = self.node(expr, indent, flat)?;
+                        self::wrap(expr, expr_text, 0, true)
+                    } else {
+                        self.last_operand(expr, 0, indent, flat)?
+                    };
+                    let keyword = if i == 0 { "if" } else { " elif" };
+                    text.push_str(&format!("{} {} then {}", keyword, condition, expr));
+                }
+                if let Some(else_case) = if_node.get_else_case() {
+                    let else_case = self.last_operand(else_case, 0, indent, flat)?;
+                    text.push_str(&format!(" else {}", else_case));
+                }
+                text
+            }
+            NodeType::ForNode(for_node) => {
+                let (var, start, end, body) = for_node.get_all();
+                let mut text = format!(
+                    "for {} in {}",
+                    var,
+                    self.operand(start, 0, true, indent, flat)?
+                );
+                if let Some(end) = end {
+                    text.push_str(&format!(
+                        " to {}",
+                        self.operand(end, 0, true, indent, flat)?
+                    ));
+                }
+                text.push_str(&format!(
+                    " then {}",
+                    self.last_operand(body, 0, indent, flat)?
+                ));
+                text
+            }
+            NodeType::WhileNode(while_node) => {
+                let (condition, body) = while_node.get_all();
+                format!(
+                    "while {} then {}",
+                    self.operand(condition, 0, true, indent, flat)?,
+                    self.last_operand(body, 0, indent, flat)?
+                )
+            }
+            NodeType::FuncDefNode(func) => {
+                format!(
+                    "{} -> {}",
+                    func.signature(),
+                    self.last_operand(func.get_all().2, 0, indent, flat)?
+                )
+            }
+            NodeType::AttributeNode(attribute) => format!(
+                "{}.{}",
+                self.operand(attribute.get_value(), 7, true, indent, flat)?,
+                attribute.get_name()
+            ),
+            NodeType::CallNode(call) => {
+                let (callee, args) = call.get_all();
+                let callee = self.operand(callee, 7, true, indent, flat)?;
+                // the prefix of named arguments together with the value
+                let args: Vec<(String, &Node)> = (args.iter().map(|arg| (String::new(), arg)))
+                    .chain(
+                        (call.get_named().iter()).map(|(name, arg)| (format!("{} = ", name), arg)),
+                    )
+                    .collect();
+                let has_comments = self.has_comments(start(node)..node.end_index());
+                if flat && has_comments {
+                    return None;
+                } else if flat || (args.is_empty() && !has_comments) {
+                    let args = args
+                        .iter()
+                        .map(|(name, arg)| {
+                            Some(format!("{}{}", name, self.node(arg, indent, true)?))
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+                    format!("{}({})", callee, args.join(", "))
+                } else {
+                    // one argument per line
+                    let mut lines = vec![format!("{}(", callee)];
+                    for (i, (name, arg)) in args.iter().enumerate() {
+                        self.comments_before(start(arg), indent + 1, &mut lines);
+                        let comma = if i + 1 < args.len() { "," } else { "" };
+                        let arg = self.fit(arg, indent + 1);
+                        let indent = INDENT.repeat(indent + 1);
+                        lines.push(format!("{}{}{}{}", indent, name, arg, comma));
+                    }
+                    self.comments_before(node.end_index(), indent + 1, &mut lines);
+                    lines.push(format!("{})", INDENT.repeat(indent)));
+                    lines.join("\n")
+                }
+            }
+            NodeType::Statements(statements) => {
+                let nodes = statements.get_nodes();
+                if flat {
+                    if nodes.len() > 1 || self.has_comments(start(node)..node.end_index()) {
+                        return None;
+                    }
+                    match nodes.first() {
+                        Some(statement) => format!("{{ {} }}", self.node(statement, indent, true)?),
+                        None => "{}".to_string(),
+                    }
+                } else {
+                    let mut lines = vec!["{".to_string()];
+                    // the closing bracket is the last char of the block
+                    self.body(nodes, indent + 1, node.end_index() - 1, &mut lines);
+                    lines.push(format!("{}}}", INDENT.repeat(indent)));
+                    lines.join("\n")
+                }
+            }
+        })
+    }
+}
+
+/// adds parentheses if the node binds too loose or would swallow what follows
+fn wrap(node: &Node, text: String, precedence: u8, followed: bool) -> String {
+    // broken up chains of operators have their parentheses already
+    let is_chain = matches!(
+        node.get_type(),
+        NodeType::Operation(OperationType::BinaryOperationNode(_))
+    ) && text.starts_with("(\n");
+    if !is_chain && (self::precedence(node) < precedence || (followed && is_open(node))) {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
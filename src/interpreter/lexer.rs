@@ -1,214 +1,823 @@
-use crate::interpreter::token::types::{Float, Integer, Type};
-use crate::interpreter::token::{ident, keyword};
+use crate::interpreter::builtins::format;
+use crate::interpreter::limits;
+use crate::interpreter::token::types::{Float, Integer, Str, Type};
+use crate::interpreter::token::{ident, keyword, Segment};
 use crate::interpreter::{Error, ErrorKind, Position, Token};
+use std::rc::Rc;
+use std::str::CharIndices;
+use unicode_xid::UnicodeXID;
 
-pub struct Lexer {
-    text: String,
-    pos: Position,
-    current_char: Option<char>,
+/// Turns source text into tokens. The text is walked exactly once, tokens
+/// are produced on demand by iterating over the lexer.
+pub struct Lexer<'a> {
+    text: &'a str,
+    chars: CharIndices<'a>,
+    current: Option<(usize, char)>,
+    file_name: Rc<str>,
+    line: u64,
+    column: i64,
+    finished: bool,
+    // the brackets which are currently open, innermost last
+    brackets: Vec<char>,
+    comments: Vec<Position>,
+    // the number of strings whose braces the current token is in
+    strings: usize,
+    // how large `strings` may get
+    nesting: usize,
 }
 
-impl Lexer {
-    pub fn new(text: String, file_name: String) -> Self {
-        let mut lexer = Self {
+impl<'a> Lexer<'a> {
+    pub fn new(text: &'a str, file_name: String) -> Self {
+        let mut chars = text.char_indices();
+        let current = chars.next();
+        Self {
             text,
-            pos: Position::new(-1, file_name, 0, -1, 1),
-            current_char: None,
-        };
-        lexer.advance();
-        lexer
+            chars,
+            current,
+            file_name: file_name.into(),
+            line: 0,
+            column: 0,
+            finished: false,
+            brackets: Vec::new(),
+            comments: Vec::new(),
+            strings: 0,
+            nesting: limits::MAX_NESTING,
+        }
+    }
+
+    /// allows strings to be interpolated inside each other `nesting` times
+    pub fn with_nesting(self, nesting: usize) -> Self {
+        Self { nesting, ..self }
+    }
+
+    /// the positions of all comments lexed so far, starting at the `#`
+    pub fn comments(&self) -> &[Position] {
+        &self.comments
     }
 
     fn advance(&mut self) {
-        self.pos.advance(self.current_char);
-        if self.pos.index >= 0 && self.pos.index < self.text.len() as i64 {
-            self.current_char =
-                Some(self.text.chars().collect::<Vec<_>>()[self.pos.index as usize]);
-        } else {
-            self.current_char = None;
+        if let Some((_, c)) = self.current {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 0;
+            } else {
+                self.column += 1;
+            }
         }
+        self.current = self.chars.next();
+    }
+
+    fn current_char(&self) -> Option<char> {
+        self.current.map(|(_, c)| c)
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.chars.clone().next().map(|(_, c)| c)
+    }
+
+    /// byte offset of the current char
+    fn index(&self) -> usize {
+        self.current.map_or(self.text.len(), |(index, _)| index)
+    }
+
+    /// position of the current char
+    fn pos(&self) -> Position {
+        Position::new(
+            self.index() as i64,
+            self.file_name.clone(),
+            self.line,
+            self.column,
+            self.current.map_or(0, |(_, c)| c.len_utf8()) as u64,
+        )
+    }
+
+    /// position from `start` up to (excluding) the current char
+    fn pos_from(&self, mut start: Position) -> Position {
+        start.set_len(self.index() as u64 - start.index as u64);
+        start
+    }
+
+    /// position of the char `c` at byte offset `index` in the same line as `start`
+    fn pos_at(&self, start: &Position, index: usize, c: char) -> Position {
+        let column = self.text[start.index as usize..index].chars().count() as i64;
+        Position::new(
+            index as i64,
+            self.file_name.clone(),
+            start.line,
+            start.column + column,
+            c.len_utf8() as u64,
+        )
     }
 
     pub fn make_tokens(&mut self) -> Result<Vec<(Token, Position)>, Error> {
-        let mut tokens: Vec<(Token, Position)> = Vec::new();
-        while let Some(c) = self.current_char {
-            match c {
-                ' ' | '\t' => self.advance(),
-                '+' => {
-                    tokens.push((Token::Plus, self.pos.clone()));
-                    self.advance();
+        self.collect()
+    }
+
+    fn next_token(&mut self) -> Result<(Token, Position), Error> {
+        loop {
+            match self.current_char() {
+                Some('#') => self.skip_comment(),
+                // newlines only separate statements outside of parentheses
+                // and square brackets
+                Some('\n') if matches!(self.brackets.last(), Some('(') | Some('[')) => {
+                    self.advance()
                 }
-                '-' => {
-                    tokens.push((Token::Minus, self.pos.clone()));
+                // lines only end at `\n`, like in the positions and the
+                // language server, other separators like U+2028 are spaces
+                Some(c) if c != '\n' && c.is_whitespace() => self.advance(),
+                _ => break,
+            }
+        }
+        let c = match self.current_char() {
+            Some(c) => c,
+            None => return Ok((Token::EOF, self.pos())),
+        };
+        Ok(match c {
+            '\n' => self.make_single(Token::Newline),
+            '+' => self.make_single(Token::Plus),
+            '-' => self.make_minus(),
+            '*' => self.make_single(Token::Star),
+            '/' => self.make_single(Token::Slash),
+            '(' => self.make_open('(', Token::LeftParenthesis),
+            ')' => self.make_close('(', Token::RightParenthesis),
+            '{' => self.make_open('{', Token::LeftCurlyBrackets),
+            '}' => self.make_close('{', Token::RightCurlyBrackets),
+            '[' => self.make_open('[', Token::LeftSquareBrackets),
+            ']' => self.make_close('[', Token::RightSquareBrackets),
+            ',' => self.make_single(Token::Comma),
+            ';' => self.make_single(Token::Semicolon),
+            ':' => self.make_single(Token::Colon),
+            '!' => self.make_not(),
+            '&' => self.make_and()?,
+            '|' => self.make_or()?,
+            '=' => self.make_eq(),
+            '<' => self.make_less_than(),
+            '>' => self.make_greater_than(),
+            c if c.is_ascii_digit() => self.make_number()?,
+            '.' if matches!(self.peek_char(), Some(c) if c.is_ascii_digit()) => {
+                self.make_number()?
+            }
+            '.' => self.make_dot(),
+            '"' => self.make_string()?,
+            c if c == '_' || c.is_xid_start() => self.make_ident()?,
+            c => {
+                return Err(Error::new(
+                    ErrorKind::SyntaxError,
+                    &format!("Illegal character: {}", c),
+                    Some(self.pos()),
+                ));
+            }
+        })
+    }
+
+    fn skip_comment(&mut self) {
+        let pos = self.pos();
+        while !matches!(self.current_char(), Some('\n') | None) {
+            self.advance();
+        }
+        self.comments.push(self.pos_from(pos));
+    }
+
+    fn make_open(&mut self, bracket: char, token: Token) -> (Token, Position) {
+        self.brackets.push(bracket);
+        self.make_single(token)
+    }
+
+    /// unbalanced brackets are left to the parser to report
+    fn make_close(&mut self, bracket: char, token: Token) -> (Token, Position) {
+        if self.brackets.last() == Some(&bracket) {
+            self.brackets.pop();
+        }
+        self.make_single(token)
+    }
+
+    fn make_single(&mut self, token: Token) -> (Token, Position) {
+        let pos = self.pos();
+        self.advance();
+        (token, pos)
+    }
+
+    fn make_ident(&mut self) -> Result<(Token, Position), Error> {
+        let pos_start = self.pos();
+        while let Some(c) = self.current_char() {
+            if !c.is_xid_continue() {
+                break;
+            }
+            self.advance();
+        }
+        let ident = &self.text[pos_start.index as usize..self.index()];
+        Ok((
+            if keyword::is_keyword(ident) {
+                Token::Keyword(keyword::from_str(ident).unwrap())
+            } else {
+                Token::Ident(ident::Ident::new(ident))
+            },
+            self.pos_from(pos_start),
+        ))
+    }
+
+    /// lexes a string in double quotes, it may span multiple lines.
+    /// Expressions in braces are lexed as well, `{{` and `}}` are braces
+    fn make_string(&mut self) -> Result<(Token, Position), Error> {
+        let pos = self.pos();
+        self.advance();
+        let mut segments = Vec::new();
+        let mut value = String::new();
+        let mut text_pos = self.pos();
+        loop {
+            match self.current_char() {
+                Some('"') => break,
+                Some(c @ ('{' | '}')) if self.peek_char() == Some(c) => {
+                    value.push(c);
                     self.advance();
-                }
-                '*' => {
-                    tokens.push((Token::Star, self.pos.clone()));
                     self.advance();
                 }
-                '/' => {
-                    tokens.push((Token::Slash, self.pos.clone()));
-                    self.advance();
+                Some('}') => {
+                    return Err(Error::new(
+                        ErrorKind::SyntaxError,
+                        "single } in a string, use }} for a brace",
+                        Some(self.pos()),
+                    ))
                 }
-                '(' => {
-                    tokens.push((Token::LeftParenthesis, self.pos.clone()));
+                Some('{') => match format::field(&self.text[self.index()..]) {
+                    // `{}`, `{1}` and `{:.2}` are kept for `format`
+                    Some(field) => {
+                        for _ in 0..field.chars().count() + 2 {
+                            value.extend(self.current_char());
+                            self.advance();
+                        }
+                    }
+                    None => {
+                        if !value.is_empty() {
+                            let text = std::mem::take(&mut value);
+                            segments.push(Segment::Text(text, self.pos_from(text_pos)));
+                        }
+                        segments.push(Segment::Expr(self.embedded()?));
+                        text_pos = self.pos();
+                    }
+                },
+                Some('\\') => {
+                    let escape = self.pos();
                     self.advance();
+                    value.push(self.escape(escape)?);
                 }
-                ')' => {
-                    tokens.push((Token::RightParenthesis, self.pos.clone()));
+                Some(c) => {
+                    value.push(c);
                     self.advance();
                 }
-                '{' => {
-                    tokens.push((Token::LeftCurlyBrackets, self.pos.clone()));
-                    self.advance();
+                // the rest of the string may still follow, e.g. in the REPL
+                None => {
+                    return Err(Error::new(
+                        ErrorKind::EndOfFile,
+                        "unterminated string",
+                        Some(pos),
+                    ))
                 }
-                '}' => {
-                    tokens.push((Token::RightCurlyBrackets, self.pos.clone()));
-                    self.advance();
+            }
+        }
+        let token = if segments.is_empty() {
+            Token::Type(Type::String(Str::new(&value)))
+        } else {
+            if !value.is_empty() {
+                segments.push(Segment::Text(value, self.pos_from(text_pos)));
+            }
+            Token::Interpolation(segments.into())
+        };
+        self.advance();
+        Ok((token, self.pos_from(pos)))
+    }
+
+    /// lexes the expression in braces inside a string up to the closing `}`
+    fn embedded(&mut self) -> Result<Vec<(Token, Position)>, Error> {
+        let open = self.pos();
+        if self.strings >= self.nesting {
+            return Err(Error::new(
+                ErrorKind::RecursionLimit,
+                &format!(
+                    "strings can only be interpolated {} times inside each other",
+                    self.nesting
+                ),
+                Some(open),
+            ));
+        }
+        self.strings += 1;
+        let res = limits::grow(|| self.embedded_tokens(open));
+        self.strings -= 1;
+        res
+    }
+
+    /// the tokens of `embedded` after the `{` at `open`
+    fn embedded_tokens(&mut self, open: Position) -> Result<Vec<(Token, Position)>, Error> {
+        self.advance();
+        // like in parentheses newlines don't end the expression
+        let depth = self.brackets.len();
+        self.brackets.push('(');
+        let mut tokens = Vec::new();
+        loop {
+            match self.next_token()? {
+                // a `}` without an open `{` ends the expression, even if a
+                // parenthesis is still open
+                (Token::RightCurlyBrackets, pos) if !self.brackets[depth..].contains(&'{') => {
+                    self.brackets.truncate(depth);
+                    tokens.push((Token::EOF, pos));
+                    break;
                 }
-                '!' => tokens.push(self.make_not()),
-                '&' => tokens.push(self.make_and()?),
-                '|' => tokens.push(self.make_or()?),
-                '=' => tokens.push(self.make_eq()),
-                '<' => tokens.push(self.make_less_than()),
-                '>' => tokens.push(self.make_greater_than()),
-                c if c.is_digit(10) => match self.make_number() {
-                    Ok(number) => tokens.push(number),
-                    Err(e) => return Err(e),
-                },
-                c if c.is_ascii_alphabetic() => match self.make_ident() {
-                    Ok(i) => tokens.push(i),
-                    Err(e) => return Err(e),
-                },
-                c => {
-                    let position = self.pos.clone();
+                (Token::EOF, _) => {
                     return Err(Error::new(
-                        ErrorKind::SyntaxError,
-                        &*format!("Illegal character: {}", c),
-                        Some(position),
-                    ));
+                        ErrorKind::EndOfFile,
+                        "unterminated string",
+                        Some(open),
+                    ))
                 }
+                token => tokens.push(token),
             }
         }
-        tokens.push((Token::EOF, self.pos.clone()));
+        if tokens.len() == 1 {
+            return Err(Error::new(
+                ErrorKind::SyntaxError,
+                "expected an expression in the braces, use {{ for a brace",
+                Some(self.pos_from(open)),
+            ));
+        }
         Ok(tokens)
     }
 
-    fn make_ident(&mut self) -> Result<(Token, Position), Error> {
-        let mut ident = String::new();
-        let pos_start = self.pos.clone();
-        while let Some(c) = self.current_char {
-            if !(c.is_ascii_alphanumeric() || c == '_') {
-                break;
+    /// lexes the char after a `\` which starts at `start`
+    fn escape(&mut self, start: Position) -> Result<char, Error> {
+        let c = match self.current_char() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some(c @ ('\\' | '"' | '\'')) => c,
+            Some('u') => return self.unicode_escape(start),
+            Some(c) => {
+                self.advance();
+                return Err(Error::new(
+                    ErrorKind::SyntaxError,
+                    &format!("unknown escape sequence \\{}", c),
+                    Some(self.pos_from(start)),
+                ));
+            }
+            None => {
+                return Err(Error::new(
+                    ErrorKind::EndOfFile,
+                    "unterminated string",
+                    Some(start),
+                ))
             }
-            ident += &c.to_string();
+        };
+        self.advance();
+        Ok(c)
+    }
+
+    /// lexes `u{...}` with at most 6 hexadecimal digits
+    fn unicode_escape(&mut self, start: Position) -> Result<char, Error> {
+        self.advance();
+        if self.current_char() != Some('{') {
+            return Err(Error::new(
+                ErrorKind::SyntaxError,
+                "expected { after \\u",
+                Some(self.pos_from(start)),
+            ));
+        }
+        self.advance();
+        let digits_start = self.index();
+        while matches!(self.current_char(), Some(c) if c.is_ascii_hexdigit()) {
             self.advance();
         }
-        Ok((
-            if keyword::is_keyword(ident.clone()) {
-                Token::Keyword(keyword::from_str(&*ident).unwrap())
-            } else {
-                Token::Ident(ident::Ident::new(ident))
-            },
-            pos_start.combine(self.pos.clone()),
-        ))
+        let digits = &self.text[digits_start..self.index()];
+        if self.current_char() != Some('}') {
+            return Err(Error::new(
+                ErrorKind::SyntaxError,
+                "expected } at the end of the unicode escape",
+                Some(self.pos_from(start)),
+            ));
+        }
+        self.advance();
+        let c = match digits.len() {
+            1..=6 => u32::from_str_radix(digits, 16)
+                .ok()
+                .and_then(char::from_u32),
+            _ => None,
+        };
+        c.ok_or_else(|| {
+            Error::new(
+                ErrorKind::SyntaxError,
+                &format!("invalid unicode escape \\u{{{}}}", digits),
+                Some(self.pos_from(start)),
+            )
+        })
     }
 
     fn check_eq(&mut self, t1: Token, t2: Token, pos: Position) -> (Token, Position) {
-        if self.current_char == Some('=') {
+        if self.current_char() == Some('=') {
             self.advance();
-            (t1, pos.combine(self.pos.clone()))
+            (t1, self.pos_from(pos))
         } else {
-            (t2, pos)
+            (t2, self.pos_from(pos))
         }
     }
 
     fn make_less_than(&mut self) -> (Token, Position) {
-        let pos = self.pos.clone();
+        let pos = self.pos();
         self.advance();
         self.check_eq(Token::LessThanEq, Token::LessThan, pos)
     }
 
     fn make_and(&mut self) -> Result<(Token, Position), Error> {
-        let pos = self.pos.clone();
+        let pos = self.pos();
         self.advance();
-        if self.current_char == Some('&') {
+        if self.current_char() == Some('&') {
             self.advance();
-            Ok((Token::DoubleAnd, pos.combine(self.pos.clone())))
+            Ok((Token::DoubleAnd, self.pos_from(pos)))
         } else {
             Err(Error::new(
                 ErrorKind::SyntaxError,
                 "expected &",
-                Some(self.pos.clone()),
+                Some(self.pos()),
             ))
         }
     }
 
     fn make_or(&mut self) -> Result<(Token, Position), Error> {
-        let pos = self.pos.clone();
+        let pos = self.pos();
         self.advance();
-        if self.current_char == Some('|') {
+        if self.current_char() == Some('|') {
             self.advance();
-            Ok((Token::DoubleOr, pos.combine(self.pos.clone())))
+            Ok((Token::DoubleOr, self.pos_from(pos)))
         } else {
             Err(Error::new(
                 ErrorKind::SyntaxError,
                 "expected |",
-                Some(self.pos.clone()),
+                Some(self.pos()),
             ))
         }
     }
 
     fn make_greater_than(&mut self) -> (Token, Position) {
-        let pos = self.pos.clone();
+        let pos = self.pos();
         self.advance();
         self.check_eq(Token::GreaterThanEq, Token::GreaterThan, pos)
     }
 
     fn make_eq(&mut self) -> (Token, Position) {
-        let pos = self.pos.clone();
+        let pos = self.pos();
         self.advance();
         self.check_eq(Token::DoubleEqual, Token::Equal, pos)
     }
 
-    fn make_not(&mut self) -> (Token, Position) {
-        let pos = self.pos.clone();
+    fn make_dot(&mut self) -> (Token, Position) {
+        let pos = self.pos();
+        self.advance();
+        if self.current_char() == Some('.') && self.peek_char() == Some('.') {
+            self.advance();
+            self.advance();
+            (Token::Ellipsis, self.pos_from(pos))
+        } else {
+            (Token::Dot, self.pos_from(pos))
+        }
+    }
+
+    fn make_minus(&mut self) -> (Token, Position) {
+        let pos = self.pos();
         self.advance();
-        if self.current_char == Some('=') {
+        if self.current_char() == Some('>') {
             self.advance();
-            (Token::NonEqual, pos.combine(self.pos.clone()))
+            (Token::Arrow, self.pos_from(pos))
         } else {
-            (Token::Bang, pos)
+            (Token::Minus, self.pos_from(pos))
         }
     }
 
+    fn make_not(&mut self) -> (Token, Position) {
+        let pos = self.pos();
+        self.advance();
+        self.check_eq(Token::NonEqual, Token::Bang, pos)
+    }
+
     fn make_number(&mut self) -> Result<(Token, Position), Error> {
-        let mut number = String::new();
-        let mut dots: u8 = 0;
-        let mut pos = self.pos.clone();
-        pos.set_len(0);
-        while let Some(c) = self.current_char {
-            if c == '.' {
-                if dots == 1 {
-                    break;
+        let pos = self.pos();
+        if self.current_char() == Some('0') {
+            match self.peek_char() {
+                Some('x') | Some('X') => return self.make_radix_number(pos, 16),
+                Some('o') | Some('O') => return self.make_radix_number(pos, 8),
+                Some('b') | Some('B') => return self.make_radix_number(pos, 2),
+                _ => {}
+            }
+        }
+        let mut is_float = false;
+        self.skip_digits();
+        if self.current_char() == Some('.') {
+            match self.peek_char() {
+                Some(c) if c.is_ascii_digit() => {
+                    self.advance();
+                    self.skip_digits();
+                    is_float = true;
                 }
-                dots += 1;
-                number += ".";
-            } else if c.is_digit(10) {
-                pos.set_len(pos.len() + 1);
-                number += &c.to_string();
-            } else {
+                // `1.` is a float as long as nothing that could be a name follows
+                Some(c) if c == '.' || c == '_' || c.is_alphabetic() => {}
+                _ => {
+                    self.advance();
+                    is_float = true;
+                }
+            }
+        }
+        if let Some('e') | Some('E') = self.current_char() {
+            is_float = true;
+            self.advance();
+            if let Some('+') | Some('-') = self.current_char() {
+                self.advance();
+            }
+            if !matches!(self.current_char(), Some(c) if c.is_ascii_digit()) {
+                return Err(self.invalid_number(&pos, "expected digits in the exponent"));
+            }
+            self.skip_digits();
+        }
+        // anything glued to the literal makes it invalid, e.g. `12ab` or `1.2.3`
+        if let Some(c) = self.current_char() {
+            let is_fraction = c == '.' && matches!(self.peek_char(), Some(c) if c.is_ascii_digit());
+            if is_fraction || c.is_alphanumeric() || c == '_' {
+                while let Some(c) = self.current_char() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '.') {
+                        break;
+                    }
+                    self.advance();
+                }
+                return Err(self.invalid_number(&pos, "unexpected characters after the number"));
+            }
+        }
+        self.check_separators(&pos, pos.index as usize, 10)?;
+        let number: String = self.text[pos.index as usize..self.index()]
+            .chars()
+            .filter(|c| *c != '_')
+            .collect();
+        let ty = if is_float {
+            match number.parse() {
+                Ok(value) => Type::Float(Float::new(value)),
+                Err(_) => return Err(self.invalid_number(&pos, "not a valid float")),
+            }
+        } else {
+            match number.parse() {
+                Ok(value) => Type::Integer(Integer::new(value)),
+                Err(_) => return Err(self.invalid_number(&pos, "integer is too large")),
+            }
+        };
+        Ok((Token::Type(ty), self.pos_from(pos)))
+    }
+
+    /// lexes integers with a `0x`, `0o` or `0b` prefix
+    fn make_radix_number(&mut self, pos: Position, radix: u32) -> Result<(Token, Position), Error> {
+        self.advance();
+        self.advance();
+        let digits_start = self.index();
+        while let Some(c) = self.current_char() {
+            if !(c.is_alphanumeric() || c == '_') {
                 break;
             }
             self.advance();
         }
-        Ok((
-            Token::Type(if dots == 0 {
-                Type::Integer(Integer::new(number.parse().unwrap()))
-            } else {
-                Type::Float(Float::new(number.parse().unwrap()))
-            }),
-            pos,
-        ))
+        let name = match radix {
+            16 => "hexadecimal",
+            8 => "octal",
+            _ => "binary",
+        };
+        let digits = &self.text[digits_start..self.index()];
+        if digits.is_empty() {
+            return Err(self.invalid_number(&pos, &format!("expected {} digits", name)));
+        }
+        if let Some((offset, c)) = digits
+            .char_indices()
+            .find(|(_, c)| *c != '_' && !c.is_digit(radix))
+        {
+            return Err(Error::new(
+                ErrorKind::SyntaxError,
+                &format!("invalid digit '{}' in {} literal", c, name),
+                Some(self.pos_at(&pos, digits_start + offset, c)),
+            ));
+        }
+        self.check_separators(&pos, digits_start, radix)?;
+        let digits: String = digits.chars().filter(|c| *c != '_').collect();
+        match i64::from_str_radix(&digits, radix) {
+            Ok(value) => Ok((
+                Token::Type(Type::Integer(Integer::new(value))),
+                self.pos_from(pos),
+            )),
+            Err(_) => Err(self.invalid_number(&pos, "integer is too large")),
+        }
+    }
+
+    fn skip_digits(&mut self) {
+        while let Some(c) = self.current_char() {
+            if !(c.is_ascii_digit() || c == '_') {
+                break;
+            }
+            self.advance();
+        }
+    }
+
+    /// checks that every `_` in the literal from `start` on sits between two digits
+    fn check_separators(&self, pos: &Position, start: usize, radix: u32) -> Result<(), Error> {
+        let literal = &self.text[start..self.index()];
+        let mut previous = None;
+        let mut chars = literal.char_indices().peekable();
+        while let Some((offset, c)) = chars.next() {
+            if c == '_' {
+                let next = chars.peek().map(|(_, c)| *c);
+                let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_digit(radix));
+                if !is_digit(previous) || !is_digit(next) {
+                    return Err(Error::new(
+                        ErrorKind::SyntaxError,
+                        "'_' in a number literal must be between two digits",
+                        Some(self.pos_at(pos, start + offset, c)),
+                    ));
+                }
+            }
+            previous = Some(c);
+        }
+        Ok(())
+    }
+
+    fn invalid_number(&self, pos: &Position, reason: &str) -> Error {
+        let literal = &self.text[pos.index as usize..self.index()];
+        Error::new(
+            ErrorKind::SyntaxError,
+            &format!("invalid number literal '{}': {}", literal, reason),
+            Some(self.pos_from(pos.clone())),
+        )
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<(Token, Position), Error>;
+
+    /// yields every token up to and including `Token::EOF`, or the first error
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let token = self.next_token();
+        self.finished = matches!(token, Ok((Token::EOF, _)) | Err(_));
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the tokens of `text` the way they are written, without the EOF
+    fn tokens(text: &str) -> Vec<String> {
+        let tokens = Lexer::new(text, "test".to_string()).make_tokens().unwrap();
+        (tokens.iter())
+            .filter(|(token, _)| *token != Token::EOF)
+            .map(|(token, _)| token.to_string())
+            .collect()
+    }
+
+    /// the message of the error lexing `text` gives and the column it points to
+    fn error(text: &str) -> (String, i64) {
+        let e = match Lexer::new(text, "test".to_string()).make_tokens() {
+            Ok(_) => panic!("{} should not lex", text),
+            Err(e) => e,
+        };
+        assert_eq!(*e.kind(), ErrorKind::SyntaxError, "{}", text);
+        (e.message().to_string(), e.position().unwrap().column())
+    }
+
+    #[test]
+    fn numbers_can_have_a_radix_an_exponent_and_separators() {
+        assert_eq!(
+            tokens("0xff 0o17 0b1010 1_000 0xdead_beef"),
+            vec!["255", "15", "10", "1000", "3735928559"]
+        );
+        assert_eq!(
+            tokens("1.5 .5 1e3 2.5E-2 1_0.2_5"),
+            vec!["1.5", "0.5", "1000", "0.025", "10.25"]
+        );
+        // `1.` is a float, but the dot of `1.x` is an access
+        assert_eq!(tokens("1. 1.x"), vec!["1", "1", ".", "x"]);
+        assert_eq!(tokens("9223372036854775807"), vec!["9223372036854775807"]);
+    }
+
+    #[test]
+    fn malformed_numbers_are_reported_where_they_go_wrong() {
+        let cases = [
+            ("0xZZ", "invalid digit 'Z' in hexadecimal literal", 2),
+            ("0b102", "invalid digit '2' in binary literal", 4),
+            ("0o8", "invalid digit '8' in octal literal", 2),
+            (
+                "0x",
+                "invalid number literal '0x': expected hexadecimal digits",
+                0,
+            ),
+            (
+                "1e",
+                "invalid number literal '1e': expected digits in the exponent",
+                0,
+            ),
+            (
+                "1e+x",
+                "invalid number literal '1e+': expected digits in the exponent",
+                0,
+            ),
+            (
+                "1__0",
+                "'_' in a number literal must be between two digits",
+                1,
+            ),
+            (
+                "1_",
+                "'_' in a number literal must be between two digits",
+                1,
+            ),
+            (
+                "0x_1",
+                "'_' in a number literal must be between two digits",
+                2,
+            ),
+            (
+                "12ab",
+                "invalid number literal '12ab': unexpected characters after the number",
+                0,
+            ),
+            (
+                "1.2.3",
+                "invalid number literal '1.2.3': unexpected characters after the number",
+                0,
+            ),
+            (
+                "9223372036854775808",
+                "invalid number literal '9223372036854775808': integer is too large",
+                0,
+            ),
+            (
+                "0x1_0000_0000_0000_0000",
+                "invalid number literal '0x1_0000_0000_0000_0000': integer is too large",
+                0,
+            ),
+        ];
+        for (text, message, column) in cases {
+            assert_eq!(error(text), (message.to_string(), column), "{}", text);
+        }
+        // the position is that of the literal, not of the line
+        assert_eq!(error("let x = 1e").1, 8);
+    }
+
+    #[test]
+    fn names_may_be_unicode_and_are_normalized() {
+        assert_eq!(tokens("café _x ä1"), vec!["café", "_x", "ä1"]);
+        let composed = Lexer::new("café", "test".to_string())
+            .make_tokens()
+            .unwrap();
+        let decomposed = Lexer::new("cafe\u{301}", "test".to_string())
+            .make_tokens()
+            .unwrap();
+        assert!(composed[0].0 == decomposed[0].0);
+        // unicode whitespace separates tokens, but only \n ends a line
+        assert_eq!(tokens("a\u{a0}b\u{2003}\tc"), vec!["a", "b", "c"]);
+        assert_eq!(error("a § b"), ("Illegal character: §".to_string(), 2));
+    }
+
+    #[test]
+    fn positions_count_chars_and_lines() {
+        let tokens = Lexer::new("é + 1\n  x", "test".to_string())
+            .make_tokens()
+            .unwrap();
+        let positions: Vec<(i64, u64, i64, u64)> = (tokens.iter())
+            .map(|(_, pos)| (pos.index(), pos.line(), pos.column(), pos.len()))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                (0, 0, 0, 2),
+                (3, 0, 2, 1),
+                (5, 0, 4, 1),
+                (6, 0, 5, 1),
+                (9, 1, 2, 1),
+                (10, 1, 3, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_line_feeds_end_lines() {
+        let text = "1\u{2028}2\u{2029}3\r\n4";
+        let tokens = Lexer::new(text, "test".to_string()).make_tokens().unwrap();
+        let lines: Vec<(String, u64)> = (tokens.iter())
+            .map(|(token, pos)| (token.to_string(), pos.line()))
+            .collect();
+        let newline = Token::Newline.to_string();
+        assert_eq!(
+            lines,
+            vec![
+                ("1".to_string(), 0),
+                ("2".to_string(), 0),
+                ("3".to_string(), 0),
+                (newline, 0),
+                ("4".to_string(), 1),
+                (Token::EOF.to_string(), 1),
+            ]
+        );
     }
 }
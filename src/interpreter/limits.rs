@@ -0,0 +1,361 @@
+use crate::interpreter::{Error, ErrorKind, Position, Type};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// The number of nested calls allowed by default
+pub const MAX_DEPTH: usize = 1000;
+
+/// How deeply expressions may be nested in the source by default
+pub const MAX_NESTING: usize = 256;
+
+/// The bytes of the native stack a run may use if the size of the stack of
+/// the current thread can't be found out
+pub const STACK_SIZE: usize = 1 << 20;
+
+/// The bytes of the native stack which are always left free, so that builtins
+/// and the error handling still have room when the limit is hit
+pub const STACK_MARGIN: usize = 256 << 10;
+
+/// The bytes of stack `grow` adds when the stack is almost used up
+const STACK_GROWTH: usize = 1 << 20;
+
+/// the address of a local variable; as the stack grows down, the difference
+/// to an earlier address is the stack used in between
+fn stack_position() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+/// the bytes of the native stack the current thread has left, if they are
+/// known
+pub fn stack_left() -> Option<usize> {
+    stacker::remaining_stack()
+}
+
+/// runs `f` on a new piece of stack if less than `STACK_MARGIN` is left, for
+/// the passes over syntax trees, which are as deep as long chains of
+/// operators and calls
+pub fn grow<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(STACK_MARGIN, STACK_GROWTH, f)
+}
+
+/// Stops a run from another thread
+#[derive(Clone, Debug, Default)]
+pub struct CancelHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// stops the runs using this handle before they evaluate the next node
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// allows runs using this handle again
+    pub fn reset(&self) {
+        self.cancelled.store(false, Ordering::Relaxed)
+    }
+}
+
+/// The resources a run may use. Only the depth of calls and the native stack
+/// are limited by default
+#[derive(Clone, Debug)]
+pub struct Limits {
+    steps: Option<u64>,
+    depth: usize,
+    nesting: usize,
+    stack: Option<usize>,
+    memory: Option<usize>,
+    cancel: Option<CancelHandle>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            steps: None,
+            depth: MAX_DEPTH,
+            nesting: MAX_NESTING,
+            stack: None,
+            memory: None,
+            cancel: None,
+        }
+    }
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the number of nodes which may be evaluated
+    pub fn with_steps(self, steps: u64) -> Self {
+        Self {
+            steps: Some(steps),
+            ..self
+        }
+    }
+
+    /// the number of nested calls
+    pub fn with_depth(self, depth: usize) -> Self {
+        Self { depth, ..self }
+    }
+
+    /// how deeply expressions may be nested in the source, e.g. in
+    /// parentheses, blocks or arguments
+    pub fn with_nesting(self, nesting: usize) -> Self {
+        Self { nesting, ..self }
+    }
+
+    pub fn get_nesting(&self) -> usize {
+        self.nesting
+    }
+
+    /// the bytes of the native stack the interpreter may use for nested
+    /// calls and expressions. By default and if the thread has less, all
+    /// but `STACK_MARGIN` of what the thread has left is used
+    pub fn with_stack(self, stack: usize) -> Self {
+        Self {
+            stack: Some(stack),
+            ..self
+        }
+    }
+
+    /// the bytes of the native stack a run starting here may use
+    fn stack_size(&self) -> usize {
+        let left = stack_left().map(|left| left.saturating_sub(STACK_MARGIN));
+        match (self.stack, left) {
+            (Some(stack), Some(left)) => stack.min(left),
+            (Some(stack), None) => stack,
+            (None, Some(left)) => left,
+            (None, None) => STACK_SIZE,
+        }
+    }
+
+    /// the number of bytes the stacks, strings, lists and tuples of the run
+    /// may use at the same time
+    pub fn with_memory(self, memory: usize) -> Self {
+        Self {
+            memory: Some(memory),
+            ..self
+        }
+    }
+
+    pub fn with_cancel(self, cancel: CancelHandle) -> Self {
+        Self {
+            cancel: Some(cancel),
+            ..self
+        }
+    }
+}
+
+/// What a run used so far, shared by all contexts of the run
+#[derive(Debug, Default)]
+pub struct Budget {
+    limits: Limits,
+    steps: Cell<u64>,
+    // the stack position of the first node
+    stack_start: Cell<usize>,
+    stack_size: Cell<usize>,
+    memory: Rc<Cell<usize>>,
+}
+
+impl Budget {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    pub fn get_limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// counts the evaluation of a node
+    pub fn step(&self, pos: &Position) -> Result<(), Error> {
+        let steps = self.steps.get() + 1;
+        self.steps.set(steps);
+        let stack = stack_position();
+        if steps == 1 {
+            self.stack_start.set(stack);
+            self.stack_size.set(self.limits.stack_size());
+        } else if self.stack_start.get().saturating_sub(stack) > self.stack_size.get() {
+            return Err(Error::new(
+                ErrorKind::RecursionLimit,
+                "the script is nested too deeply",
+                Some(pos.clone()),
+            ));
+        }
+        if matches!(self.limits.steps, Some(limit) if steps > limit) {
+            return Err(Error::new(
+                ErrorKind::Timeout,
+                &format!("the script took more than {} steps", steps - 1),
+                Some(pos.clone()),
+            ));
+        }
+        if matches!(&self.limits.cancel, Some(cancel) if cancel.is_cancelled()) {
+            return Err(Error::new(
+                ErrorKind::Cancelled,
+                "the script was cancelled",
+                Some(pos.clone()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// checks whether a call at `depth` may be made
+    pub fn call(&self, depth: usize, pos: &Position) -> Result<(), Error> {
+        if depth > self.limits.depth {
+            return Err(Error::new(
+                ErrorKind::RecursionLimit,
+                &format!("more than {} nested calls", self.limits.depth),
+                Some(pos.clone()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// fails if `bytes` more can't be used, without reserving them
+    pub fn reserve(&self, bytes: usize, pos: &Position) -> Result<(), Error> {
+        match self.limits.memory {
+            Some(limit) if self.memory.get().saturating_add(bytes) > limit => Err(Error::new(
+                ErrorKind::MemoryLimit,
+                &format!("the script used more than {} bytes", limit),
+                Some(pos.clone()),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// reserves `bytes`, they are given back when the allocation is dropped
+    pub fn allocate(&self, bytes: usize, pos: &Position) -> Result<Allocation, Error> {
+        self.reserve(bytes, pos)?;
+        self.memory.set(self.memory.get() + bytes);
+        Ok(Allocation {
+            memory: self.memory.clone(),
+            bytes,
+        })
+    }
+
+    /// counts the memory of a string, list or tuple the first time a node
+    /// evaluates to it, until the value and all its copies are dropped
+    pub fn charge(&self, value: Type, pos: &Position) -> Result<Type, Error> {
+        if self.limits.memory.is_none() {
+            return Ok(value);
+        }
+        match value.uncounted_size() {
+            Some(bytes) => {
+                let allocation = self.allocate(bytes, pos)?;
+                Ok(value.with_charge(Charge(Some(Rc::new(allocation)))))
+            }
+            None => Ok(value),
+        }
+    }
+}
+
+/// Memory counted by a `Budget` for as long as this lives
+#[derive(Debug)]
+pub struct Allocation {
+    memory: Rc<Cell<usize>>,
+    bytes: usize,
+}
+
+impl Drop for Allocation {
+    fn drop(&mut self) {
+        self.memory.set(self.memory.get() - self.bytes)
+    }
+}
+
+/// The memory of a value counted by a `Budget`, shared by the copies of the
+/// value. It isn't part of the value, so it doesn't affect comparisons
+#[derive(Clone, Debug, Default)]
+pub struct Charge(Option<Rc<Allocation>>);
+
+impl Charge {
+    pub fn is_counted(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+impl PartialEq for Charge {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::token::types::Str;
+
+    fn pos() -> Position {
+        Position::new(0, "test".into(), 0, 0, 0)
+    }
+
+    #[test]
+    fn steps_and_calls_are_counted_against_the_limits() {
+        let budget = Budget::new(Limits::new().with_steps(2).with_depth(3));
+        assert!(budget.step(&pos()).is_ok());
+        assert!(budget.step(&pos()).is_ok());
+        let e = budget.step(&pos()).unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::Timeout);
+        assert_eq!(e.message(), "the script took more than 2 steps");
+        assert!(budget.call(3, &pos()).is_ok());
+        assert_eq!(
+            budget.call(4, &pos()).unwrap_err().kind(),
+            &ErrorKind::RecursionLimit
+        );
+    }
+
+    #[test]
+    fn cancelling_stops_the_next_step() {
+        let cancel = CancelHandle::new();
+        let budget = Budget::new(Limits::new().with_cancel(cancel.clone()));
+        assert!(budget.step(&pos()).is_ok());
+        cancel.cancel();
+        assert!(cancel.is_cancelled());
+        assert_eq!(
+            budget.step(&pos()).unwrap_err().kind(),
+            &ErrorKind::Cancelled
+        );
+        cancel.reset();
+        assert!(budget.step(&pos()).is_ok());
+    }
+
+    #[test]
+    fn memory_is_given_back_when_it_is_dropped() {
+        let budget = Budget::new(Limits::new().with_memory(100));
+        let first = budget.allocate(60, &pos()).unwrap();
+        assert!(budget.reserve(40, &pos()).is_ok());
+        let e = budget.allocate(41, &pos()).unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::MemoryLimit);
+        assert_eq!(e.message(), "the script used more than 100 bytes");
+        drop(first);
+        let second = budget.allocate(100, &pos()).unwrap();
+        drop(second);
+
+        // a value is counted once, no matter how often it is copied
+        let value = budget
+            .charge(Type::String(Str::new("abc")), &pos())
+            .unwrap();
+        let used = budget.memory.get();
+        assert!(used > 0);
+        assert_eq!(value.uncounted_size(), None);
+        let copy = budget.charge(value.clone(), &pos()).unwrap();
+        assert_eq!(budget.memory.get(), used);
+        drop(value);
+        assert_eq!(budget.memory.get(), used);
+        drop(copy);
+        assert_eq!(budget.memory.get(), 0);
+    }
+}
@@ -1,40 +1,106 @@
+pub mod analysis;
 mod ast;
+pub mod builtins;
+pub mod capabilities;
+pub mod check;
+pub mod console;
+pub mod debug;
+pub mod dump;
 mod errors;
+pub mod formatter;
 mod lexer;
+pub mod limits;
+mod modules;
+mod resolver;
 mod stack;
 mod token;
 mod visit;
 
+pub use ast::nodes::Node;
+use capabilities::Capabilities;
+use console::{Console, Stdio};
+use debug::Hook;
+use limits::{Budget, Limits};
+use modules::Modules;
 use stack::Stack;
+pub use stack::StackRef;
+pub use token::keyword::keywords;
 pub use token::types::Type;
 
-use crate::interpreter::token::ident::Ident;
-use crate::interpreter::token::types::Integer;
 pub use errors::*;
+use std::path::PathBuf;
+use std::rc::Rc;
 use token::Token;
 use visit::*;
 
-#[derive(Clone, Debug)]
+/// Where the interpreter currently is: the stack it evaluates in and the
+/// chain of calls that led there, used for tracebacks
+#[derive(Clone)]
 pub struct Context {
     pos: Position,
-    stack: Stack,
-    context: Option<Box<Context>>,
+    stack: StackRef,
+    context: Option<Rc<Context>>,
+    // the number of calls that led here
+    depth: usize,
+    run: Rc<Run>,
+}
+
+/// What all contexts of a run share
+struct Run {
+    // called before every node
+    hook: Option<Rc<dyn Hook>>,
+    budget: Budget,
+    capabilities: Capabilities,
+    console: Rc<dyn Console>,
+    modules: Modules,
+    // whether imported files get the prelude
+    prelude: bool,
+}
+
+impl Default for Run {
+    fn default() -> Self {
+        Self {
+            hook: None,
+            budget: Budget::default(),
+            capabilities: Capabilities::default(),
+            console: Rc::new(Stdio),
+            modules: Modules::default(),
+            prelude: true,
+        }
+    }
 }
 
 impl Context {
-    pub fn new(pos: Position, stack: Stack, context: Option<Context>) -> Self {
+    /// creates a context for a call inside `context`, or for a whole run if
+    /// it is None
+    pub fn new(pos: Position, stack: StackRef, context: Option<Context>) -> Self {
         Self {
             pos,
-            context: if let Some(ctx) = context {
-                Some(Box::new(ctx))
-            } else {
-                None
-            },
+            depth: context.as_ref().map_or(0, |c| c.depth + 1),
+            run: context
+                .as_ref()
+                .map_or_else(Default::default, |c| c.run.clone()),
+            context: context.map(Rc::new),
             stack,
         }
     }
 
-    pub fn context(&self) -> Option<Box<Context>> {
+    /// sets what the run may do, for a context without a parent
+    pub fn with_options(self, options: Options) -> Self {
+        Self {
+            run: Rc::new(Run {
+                hook: options.hook,
+                budget: Budget::new(options.limits),
+                capabilities: options.capabilities,
+                console: options.console.unwrap_or_else(|| Rc::new(Stdio)),
+                modules: Modules::new(options.search_paths),
+                prelude: !options.without_prelude,
+            }),
+            ..self
+        }
+    }
+
+    pub fn context(&self) -> Option<Rc<Context>> {
         self.context.clone()
     }
 
@@ -42,40 +108,37 @@ impl Context {
         self.pos.clone()
     }
 
-    pub fn combine(&self, other: Self) -> Self {
-        let mut stack = self.stack.clone();
-        stack.combine(other.stack.clone());
-        Self::new(
-            Position::new(
-                self.pos.index,
-                self.pos.filename.clone(),
-                self.pos.line,
-                self.pos.column,
-                (other.pos.index - self.pos.index) as u64 + other.pos.len,
-            ),
-            stack,
-            if self.context.is_some() {
-                Some(*self.context().unwrap())
-            } else if other.context.is_some() {
-                Some(*other.context().unwrap())
-            } else {
-                None
-            },
-        )
+    pub fn get_stack(&self) -> StackRef {
+        self.stack.clone()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+impl std::fmt::Debug for Context {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Context")
+            .field("pos", &self.pos)
+            .field("stack", &self.stack)
+            .field("context", &self.context)
+            .finish()
     }
 }
 
-#[derive(Clone, Debug)]
+/// A span in the source; `index` and `len` are in bytes, `column` counts chars
+#[derive(Clone, Debug, PartialEq)]
 pub struct Position {
     index: i64,
-    filename: String,
+    filename: Rc<str>,
     line: u64,
     column: i64,
     len: u64,
 }
 
 impl Position {
-    pub fn new(index: i64, filename: String, line: u64, column: i64, len: u64) -> Self {
+    pub fn new(index: i64, filename: Rc<str>, line: u64, column: i64, len: u64) -> Self {
         Self {
             index,
             filename,
@@ -85,19 +148,30 @@ impl Position {
         }
     }
 
-    pub fn advance(&mut self, current_char: Option<char>) {
-        self.index += 1;
-        self.column += 1;
-        if current_char.unwrap_or(' ') == '\n' {
-            self.line += 1;
-            self.column = 0;
-        }
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    pub fn index(&self) -> i64 {
+        self.index
+    }
+
+    pub fn line(&self) -> u64 {
+        self.line
+    }
+
+    pub fn column(&self) -> i64 {
+        self.column
     }
 
     pub fn len(&self) -> u64 {
         self.len
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     pub fn set_len(&mut self, len: u64) {
         self.len = len
     }
@@ -111,73 +185,143 @@ impl Position {
             (other.index - self.index) as u64 + other.len,
         )
     }
-    // TODO use this in modules
-    /*pub fn add_context(&self, context: Box<Position>) -> Self {
-        Self {
-            index: self.index,
-            filename: self.filename.clone(),
-            line: self.line,
-            column: self.column,
-            len: self.len,
-            context: Some(context),
-
-        }
-    }*/
 }
 
 pub struct InterpretionResult {
     pub res: Result<Type, Error>,
-    pub stack: stack::Stack,
+    pub stack: StackRef,
 }
 
 impl InterpretionResult {
-    fn new(res: Result<Type, Error>, stack: stack::Stack) -> Self {
+    fn new(res: Result<Type, Error>, stack: StackRef) -> Self {
         Self { res, stack }
     }
 }
 
-pub fn new_stack() -> Stack {
-    stack::Stack::new(None)
+/// a stack with the builtins and the prelude
+pub fn new_stack() -> StackRef {
+    let stack = builtin_stack();
+    builtins::prelude::load(&stack);
+    stack
+}
+
+/// a stack with only the builtins
+pub fn builtin_stack() -> StackRef {
+    let stack = Stack::new_ref(None);
+    builtins::define(&mut stack.borrow_mut());
+    stack
+}
+
+/// lexes and parses `text` without running it
+pub fn parse(text: &str, file_name: String) -> Result<Node, Error> {
+    parse_with(text, file_name, &Limits::default())
 }
 
-pub fn run(text: String, file_name: String, stack: Option<Stack>) -> InterpretionResult {
+/// parses `text` like `parse`, expressions may be nested as deeply as
+/// `limits` allow
+pub fn parse_with(text: &str, file_name: String, limits: &Limits) -> Result<Node, Error> {
+    let tokens = lexer::Lexer::new(text, file_name)
+        .with_nesting(limits.get_nesting())
+        .make_tokens()?;
+    ast::Parser::new(tokens)
+        .with_nesting(limits.get_nesting())
+        .parse()
+}
+
+/// What a run may do and who watches it, see `run_with`. Nothing but
+/// computing is allowed by default
+#[derive(Clone, Default)]
+pub struct Options {
+    limits: Limits,
+    capabilities: Capabilities,
+    console: Option<Rc<dyn Console>>,
+    search_paths: Vec<PathBuf>,
+    without_prelude: bool,
+    hook: Option<Rc<dyn Hook>>,
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limits(self, limits: Limits) -> Self {
+        Self { limits, ..self }
+    }
+
+    pub fn with_capabilities(self, capabilities: Capabilities) -> Self {
+        Self {
+            capabilities,
+            ..self
+        }
+    }
+
+    /// where the output goes to and the input comes from instead of stdio
+    pub fn with_console(self, console: Rc<dyn Console>) -> Self {
+        Self {
+            console: Some(console),
+            ..self
+        }
+    }
+
+    /// where imported files are looked up after the directory of the
+    /// importing file
+    pub fn with_search_paths(self, search_paths: Vec<PathBuf>) -> Self {
+        Self {
+            search_paths,
+            ..self
+        }
+    }
+
+    /// only defines the builtins in new stacks, not the functions of the prelude
+    pub fn without_prelude(self) -> Self {
+        Self {
+            without_prelude: true,
+            ..self
+        }
+    }
+
+    /// calls `hook` before every node, e.g. for a debugger
+    pub fn with_hook(self, hook: Rc<dyn Hook>) -> Self {
+        Self {
+            hook: Some(hook),
+            ..self
+        }
+    }
+}
+
+/// runs `text` with all capabilities
+pub fn run(text: String, file_name: String, stack: Option<StackRef>) -> InterpretionResult {
+    let options = Options::new().with_capabilities(Capabilities::all());
+    run_with(text, file_name, stack, options)
+}
+
+/// runs `text` like `run` with the given limits, capabilities and hook
+pub fn run_with(
+    text: String,
+    file_name: String,
+    stack: Option<StackRef>,
+    options: Options,
+) -> InterpretionResult {
     // create a stack if none is supplied
-    let mut stack = stack.unwrap_or_else(new_stack);
-
-    // Built-in variables
-    stack.set(
-        Ident::new("null".to_string()),
-        Type::Integer(Integer::new(0)),
-    );
-    stack.set(
-        Ident::new("true".to_string()),
-        Type::Integer(Integer::new(1)),
-    );
-    stack.set(
-        Ident::new("false".to_string()),
-        Type::Integer(Integer::new(0)),
-    );
-
-    // lexing
-    let mut lexer = lexer::Lexer::new(text, file_name.clone());
-    let tokens = match lexer.make_tokens() {
-        Ok(t) => t,
-        Err(e) => return InterpretionResult::new(Err(e), stack),
+    let stack = match stack {
+        Some(stack) => stack,
+        None if options.without_prelude => builtin_stack(),
+        None => new_stack(),
     };
 
-    // parsing to ast
-    let mut parser = ast::Parser::new(tokens);
-    let ast = match parser.parse() {
+    let ast = match parse_with(&text, file_name.clone(), &options.limits) {
         Ok(n) => n,
         Err(e) => return InterpretionResult::new(Err(e), stack),
     };
 
+    // resolve the variables
+    resolver::Resolver::new(&mut stack.borrow_mut()).resolve(&ast);
+
     // execute the ast
-    let pos = Position::new(0, file_name, 0, 0, 0);
-    let context = Context::new(pos, stack.clone(), None);
-    let (ty, context) = match visit(ast, context) {
-        Ok(t) => t,
-        Err(e) => return InterpretionResult::new(Err(e), stack),
-    };
-    InterpretionResult::new(Ok(ty), context.stack)
+    let pos = Position::new(0, file_name.as_str().into(), 0, 0, 0);
+    let context = Context::new(pos, stack.clone(), None).with_options(options);
+    context.run.modules.start(&file_name);
+    let res = visit(&ast, &context);
+    InterpretionResult::new(res, stack)
 }
@@ -0,0 +1,176 @@
+use crate::interpreter::ast::nodes::Source;
+use crate::interpreter::capabilities::Capability;
+use crate::interpreter::token::ident::Ident;
+use crate::interpreter::token::types::{Module, Type};
+use crate::interpreter::{
+    builtin_stack, builtins, new_stack, parse_with, resolver, visit, Context, Error, ErrorKind,
+    Position,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The files a run has imported. Every file is only run once, later imports
+/// get the same module
+#[derive(Default)]
+pub struct Modules {
+    search_paths: Vec<PathBuf>,
+    loaded: RefCell<HashMap<PathBuf, Module>>,
+    // the files which are currently being run together with their names,
+    // innermost last
+    loading: RefCell<Vec<(PathBuf, String)>>,
+}
+
+impl Modules {
+    pub fn new(search_paths: Vec<PathBuf>) -> Self {
+        Self {
+            search_paths,
+            ..Self::default()
+        }
+    }
+
+    /// marks the file the run started with, importing it is circular as well
+    pub fn start(&self, file_name: &str) {
+        if let Ok(key) = Path::new(file_name).canonicalize() {
+            self.loading.borrow_mut().push((key, file_name.to_string()));
+        }
+    }
+
+    /// the directories `path` is looked up in: the one of the importing
+    /// file first, then the search paths. Only files the script may read are
+    /// probed, so it can't find out whether other files exist
+    fn find(&self, path: &Path, pos: &Position, context: &Context) -> Result<PathBuf, Error> {
+        let capabilities = &context.run.capabilities;
+        if path.is_absolute() {
+            capabilities.check(Capability::Read(path.to_path_buf()), pos)?;
+            if path.is_file() {
+                return Ok(path.to_path_buf());
+            }
+            return Err(Error::new(
+                ErrorKind::ImportError,
+                &format!("can't find {}", path.display()),
+                Some(pos.clone()),
+            ));
+        }
+        let importer = Path::new(pos.filename())
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        let dirs = std::iter::once(importer).chain(self.search_paths.iter().map(PathBuf::as_path));
+        let mut searched = Vec::new();
+        let mut denied = None;
+        for dir in dirs {
+            let candidate = dir.join(path);
+            if let Err(e) = capabilities.check(Capability::Read(candidate.clone()), pos) {
+                denied = denied.or(Some(e));
+                continue;
+            }
+            if candidate.is_file() {
+                return Ok(candidate);
+            }
+            if dir.as_os_str().is_empty() {
+                searched.push(".".to_string());
+            } else {
+                searched.push(dir.display().to_string());
+            }
+        }
+        // without any readable directory the missing permission is the problem
+        match denied {
+            Some(e) if searched.is_empty() => Err(e),
+            _ => Err(Error::new(
+                ErrorKind::ImportError,
+                &format!("can't find {} in {}", path.display(), searched.join(", ")),
+                Some(pos.clone()),
+            )),
+        }
+    }
+
+    /// loads the module `source` for the import at `pos`
+    pub fn import(
+        &self,
+        source: &Source,
+        pos: &Position,
+        context: &Context,
+    ) -> Result<Module, Error> {
+        let error = |kind, message: &str| Error::new(kind, message, Some(pos.clone()));
+        let path = match source {
+            Source::Name(name) => {
+                let builtin = builtins::values()
+                    .into_iter()
+                    .find(|(builtin, _)| *builtin == name.as_str());
+                if let Some((_, Type::Module(module))) = builtin {
+                    return Ok(module);
+                }
+                PathBuf::from(format!("{}.phx", name))
+            }
+            Source::Path(path) => PathBuf::from(path),
+        };
+        let path = self.find(&path, pos, context)?;
+        let key = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if let Some(module) = self.loaded.borrow().get(&key) {
+            return Ok(module.clone());
+        }
+        let name = path.display().to_string();
+        let loading = self
+            .loading
+            .borrow()
+            .iter()
+            .position(|(file, _)| *file == key);
+        if let Some(start) = loading {
+            let loading = self.loading.borrow();
+            let cycle: Vec<&str> = (loading[start..].iter())
+                .map(|(_, name)| name.as_str())
+                .chain(std::iter::once(name.as_str()))
+                .collect();
+            return Err(error(
+                ErrorKind::ImportError,
+                &format!("circular import: {}", cycle.join(" -> ")),
+            ));
+        }
+        let text = std::fs::read_to_string(&path).map_err(|e| {
+            error(
+                ErrorKind::IOError,
+                &format!("can't read {}: {}", path.display(), e),
+            )
+        })?;
+        self.loading.borrow_mut().push((key.clone(), name));
+        let res = run(&text, &path, pos, context);
+        self.loading.borrow_mut().pop();
+        let module = res?;
+        self.loaded.borrow_mut().insert(key, module.clone());
+        Ok(module)
+    }
+}
+
+/// runs the file in its own global stack, the module gets the globals the
+/// file defined
+fn run(text: &str, path: &Path, pos: &Position, context: &Context) -> Result<Module, Error> {
+    let stack = if context.run.prelude {
+        new_stack()
+    } else {
+        builtin_stack()
+    };
+    let predefined = stack.borrow().variables();
+    let module_context = Context::new(pos.clone(), stack.clone(), Some(context.clone()));
+    let limits = context.run.budget.get_limits();
+    // the whole file is there, so nothing missing can follow anymore
+    let ast = parse_with(text, path.display().to_string(), limits).map_err(|e| {
+        let e = match e.kind() {
+            ErrorKind::EndOfFile => {
+                Error::new(ErrorKind::SyntaxError, e.message(), e.position().cloned())
+            }
+            _ => e,
+        };
+        e.or_context(module_context.clone())
+    })?;
+    resolver::Resolver::new(&mut stack.borrow_mut()).resolve(&ast);
+    visit(&ast, &module_context).map_err(|e| e.or_context(module_context))?;
+    let members = stack
+        .borrow()
+        .variables()
+        .into_iter()
+        .filter(|variable| !predefined.contains(variable))
+        .map(|(name, value)| (Ident::new(name), value))
+        .collect();
+    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+    Ok(Module::new(&name, members))
+}
@@ -0,0 +1,377 @@
+use crate::interpreter::ast::nodes::{
+    FuncDefNode, Node, NodeType, OperationType, Pattern, Variable,
+};
+use crate::interpreter::limits;
+use crate::interpreter::stack::{Slot, Stack};
+use crate::interpreter::token::ident::Ident;
+
+/// Computes the slot of every variable before the ast is executed, so the
+/// interpreter can index into the stacks instead of searching them by name
+pub struct Resolver<'a> {
+    global: &'a mut Stack,
+    // the locals of the functions we are currently in, innermost last
+    scopes: Vec<Vec<Ident>>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(global: &'a mut Stack) -> Self {
+        Self {
+            global,
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn resolve(&mut self, node: &Node) {
+        self.hoist(node);
+        self.visit(node);
+    }
+
+    /// finds the innermost declaration of a variable. Unknown names get a
+    /// slot in the global stack, as they may still be defined later on
+    fn lookup(&mut self, var: &Variable) {
+        let name = var.get_name().clone();
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some(index) = scope.iter().position(|n| *n == name) {
+                return var.set_slot(Slot::new(depth, index));
+            }
+        }
+        var.set_slot(Slot::new(self.scopes.len(), self.global.declare(name)))
+    }
+
+    fn declare(&mut self, var: &Variable) {
+        let name = var.get_name().clone();
+        let index = match self.scopes.last_mut() {
+            Some(scope) => match scope.iter().position(|n| *n == name) {
+                Some(index) => index,
+                None => {
+                    scope.push(name);
+                    scope.len() - 1
+                }
+            },
+            None => self.global.declare(name),
+        };
+        var.set_slot(Slot::new(0, index))
+    }
+
+    /// resolves the parameters and the body of a function in its own scope
+    fn function(&mut self, node: &FuncDefNode) {
+        let (_, params, body) = node.get_all();
+        self.scopes
+            .push(params.iter().map(|p| p.get_name().clone()).collect());
+        self.hoist(body);
+        self.visit(body);
+        node.set_locals(self.scopes.pop().unwrap_or_default());
+    }
+
+    /// declares the names a pattern binds, the names of structs and enums
+    /// in it are looked up
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Binding(var) => self.declare(var),
+            Pattern::Tuple(patterns, _) => patterns.iter().for_each(|p| self.pattern(p)),
+            Pattern::List(list) => {
+                for pattern in list.get_items().iter().chain(list.get_rest()) {
+                    self.pattern(pattern);
+                }
+            }
+            Pattern::Constructor(constructor) => {
+                self.lookup(constructor.get_type());
+                for arg in constructor.get_args().unwrap_or_default() {
+                    self.pattern(arg);
+                }
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(..) => {}
+        }
+    }
+
+    /// declares the names a pattern binds without resolving anything else
+    fn bindings(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Binding(var) => self.declare(var),
+            Pattern::Tuple(patterns, _) => patterns.iter().for_each(|p| self.bindings(p)),
+            Pattern::List(list) => {
+                for pattern in list.get_items().iter().chain(list.get_rest()) {
+                    self.bindings(pattern);
+                }
+            }
+            Pattern::Constructor(constructor) => {
+                for arg in constructor.get_args().unwrap_or_default() {
+                    self.bindings(arg);
+                }
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(..) => {}
+        }
+    }
+
+    /// declares every name the current scope binds up front, so functions
+    /// can use each other and closures see variables assigned after them.
+    /// The bodies of nested functions are scopes of their own
+    fn hoist(&mut self, node: &Node) {
+        limits::grow(|| self.hoist_node(node))
+    }
+
+    fn hoist_node(&mut self, node: &Node) {
+        match node.get_type() {
+            NodeType::Node(_) | NodeType::Var(_) => {}
+            NodeType::Operation(OperationType::BinaryOperationNode(node)) => {
+                self.hoist(node.get_left());
+                self.hoist(node.get_right());
+            }
+            NodeType::Operation(OperationType::UnaryOperationNode(node)) => {
+                self.hoist(node.get_node())
+            }
+            NodeType::Assign(node) => {
+                self.hoist(node.get_expr());
+                self.declare(node.get_var());
+            }
+            NodeType::Destructure(node) => {
+                self.hoist(node.get_expr());
+                self.bindings(node.get_pattern());
+            }
+            NodeType::Tuple(node) => node.get_items().iter().for_each(|item| self.hoist(item)),
+            NodeType::IfNode(node) => {
+                for (condition, expr) in node.get_cases() {
+                    self.hoist(condition);
+                    self.hoist(expr);
+                }
+                if let Some(else_case) = node.get_else_case() {
+                    self.hoist(else_case);
+                }
+            }
+            NodeType::ForNode(node) => {
+                let (var_name, start, end, body) = node.get_all();
+                self.hoist(start);
+                if let Some(end) = end {
+                    self.hoist(end);
+                }
+                self.declare(var_name);
+                self.hoist(body);
+            }
+            NodeType::WhileNode(node) => {
+                let (cond, body) = node.get_all();
+                self.hoist(cond);
+                self.hoist(body);
+            }
+            NodeType::FuncDefNode(node) => {
+                if let (Some(name), _, _) = node.get_all() {
+                    self.declare(name);
+                }
+            }
+            NodeType::StructDef(node) => self.declare(node.get_name()),
+            NodeType::EnumDef(node) => self.declare(node.get_name()),
+            NodeType::Match(node) => {
+                self.hoist(node.get_value());
+                for arm in node.get_arms() {
+                    self.bindings(arm.get_pattern());
+                    if let Some(guard) = arm.get_guard() {
+                        self.hoist(guard);
+                    }
+                    self.hoist(arm.get_body());
+                }
+            }
+            NodeType::CallNode(node) => {
+                let (callee, args) = node.get_all();
+                self.hoist(callee);
+                args.iter().for_each(|arg| self.hoist(arg));
+                node.get_named().iter().for_each(|(_, arg)| self.hoist(arg));
+            }
+            NodeType::AttributeNode(node) => self.hoist(node.get_value()),
+            NodeType::IndexNode(node) => {
+                let (value, index) = node.get_all();
+                self.hoist(value);
+                self.hoist(index);
+            }
+            NodeType::SetAttribute(node) => {
+                self.hoist(node.get_attribute().get_value());
+                self.hoist(node.get_expr());
+            }
+            NodeType::Import(node) => node.get_vars().iter().for_each(|var| self.declare(var)),
+            NodeType::Interpolation(node) => node.get_exprs().for_each(|e| self.hoist(e)),
+            NodeType::Statements(node) => node.get_nodes().iter().for_each(|n| self.hoist(n)),
+        }
+    }
+
+    fn visit(&mut self, node: &Node) {
+        limits::grow(|| self.visit_node(node))
+    }
+
+    fn visit_node(&mut self, node: &Node) {
+        match node.get_type() {
+            NodeType::Node(_) => {}
+            NodeType::Operation(OperationType::BinaryOperationNode(node)) => {
+                self.visit(node.get_left());
+                self.visit(node.get_right());
+            }
+            NodeType::Operation(OperationType::UnaryOperationNode(node)) => {
+                self.visit(node.get_node())
+            }
+            NodeType::Var(var) => self.lookup(var),
+            NodeType::Assign(node) => {
+                self.visit(node.get_expr());
+                self.declare(node.get_var());
+            }
+            NodeType::Destructure(node) => {
+                self.visit(node.get_expr());
+                self.pattern(node.get_pattern());
+            }
+            NodeType::Tuple(node) => {
+                for item in node.get_items() {
+                    self.visit(item);
+                }
+            }
+            NodeType::IfNode(node) => {
+                for (condition, expr) in node.get_cases() {
+                    self.visit(condition);
+                    self.visit(expr);
+                }
+                if let Some(else_case) = node.get_else_case() {
+                    self.visit(else_case);
+                }
+            }
+            NodeType::ForNode(node) => {
+                let (var_name, start, end, body) = node.get_all();
+                self.visit(start);
+                if let Some(end) = end {
+                    self.visit(end);
+                }
+                self.declare(var_name);
+                self.visit(body);
+            }
+            NodeType::WhileNode(node) => {
+                let (cond, body) = node.get_all();
+                self.visit(cond);
+                self.visit(body);
+            }
+            NodeType::FuncDefNode(node) => {
+                if let (Some(name), _, _) = node.get_all() {
+                    self.declare(name);
+                }
+                self.function(node);
+            }
+            NodeType::StructDef(node) => {
+                self.declare(node.get_name());
+                for method in node.get_funcs() {
+                    self.function(method);
+                }
+            }
+            NodeType::EnumDef(node) => self.declare(node.get_name()),
+            NodeType::Match(node) => {
+                self.visit(node.get_value());
+                for arm in node.get_arms() {
+                    self.pattern(arm.get_pattern());
+                    if let Some(guard) = arm.get_guard() {
+                        self.visit(guard);
+                    }
+                    self.visit(arm.get_body());
+                }
+            }
+            NodeType::CallNode(node) => {
+                let (callee, args) = node.get_all();
+                self.visit(callee);
+                for arg in args {
+                    self.visit(arg);
+                }
+                for (_, arg) in node.get_named() {
+                    self.visit(arg);
+                }
+            }
+            NodeType::AttributeNode(node) => self.visit(node.get_value()),
+            NodeType::IndexNode(node) => {
+                let (value, index) = node.get_all();
+                self.visit(value);
+                self.visit(index);
+            }
+            NodeType::SetAttribute(node) => {
+                self.visit(node.get_attribute().get_value());
+                self.visit(node.get_expr());
+            }
+            NodeType::Import(node) => {
+                for var in node.get_vars() {
+                    self.declare(var);
+                }
+            }
+            NodeType::Interpolation(node) => {
+                for expr in node.get_exprs() {
+                    self.visit(expr);
+                }
+            }
+            NodeType::Statements(node) => {
+                for statement in node.get_nodes() {
+                    self.visit(statement);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::console::Buffer;
+    use crate::interpreter::{parse, run_with, Options};
+    use std::rc::Rc;
+
+    /// runs `text`, returns what it printed or the error
+    fn output(text: &str) -> String {
+        let console = Rc::new(Buffer::new());
+        let options = Options::new().with_console(console.clone());
+        match run_with(text.to_string(), "test".to_string(), None, options).res {
+            Ok(_) => console.get_output(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn statements(node: &Node) -> &[Node] {
+        match node.get_type() {
+            NodeType::Statements(statements) => statements.get_nodes(),
+            _ => panic!("{} isn't a block", node),
+        }
+    }
+
+    fn body(node: &Node) -> &Node {
+        match node.get_type() {
+            NodeType::FuncDefNode(func) => func.get_all().2,
+            _ => panic!("{} isn't a function", node),
+        }
+    }
+
+    #[test]
+    fn closures_see_variables_assigned_after_them() {
+        let ast = parse(
+            "fn outer() -> { fn inner() -> y; let y = 5 }",
+            "test".into(),
+        )
+        .unwrap();
+        Resolver::new(&mut Stack::new(None)).resolve(&ast);
+        let outer = &statements(&ast)[0];
+        let inner = &statements(body(outer))[0];
+        match body(inner).get_type() {
+            // `y` is the only local of `outer` after `inner`
+            NodeType::Var(y) => assert_eq!(y.get_slot(), Some(Slot::new(1, 1))),
+            _ => panic!("the body of inner isn't a variable"),
+        }
+
+        let text = "fn outer() -> { fn inner() -> y; let y = 5; inner() }
+fn lambda() -> { let f = fn() -> y; let y = 6; f() }
+fn pattern() -> { let f = fn() -> a + b; let (a, [b]) = (1, list(2)); f() }
+print(outer(), lambda(), pattern())";
+        assert_eq!(output(text), "5 6 3\n");
+    }
+
+    #[test]
+    fn unassigned_locals_fall_back_to_the_surrounding_scopes() {
+        let text = "let n = 1
+fn bump() -> { let n = n + 1; n }
+fn twice() -> { let i = 0; while i < 2 then { print(n); let n = 5; let i = i + 1 } }
+print(bump(), n)
+twice()";
+        assert_eq!(output(text), "2 1\n1\n5\n");
+    }
+
+    #[test]
+    fn unknown_names_are_global() {
+        let text = "fn f() -> later\nlet later = 3\nprint(f())";
+        assert_eq!(output(text), "3\n");
+        assert!(output("fn f() -> missing\nf()").contains("NameError: missing is not defined"));
+    }
+}
@@ -1,41 +1,187 @@
+use crate::interpreter::limits::Allocation;
 use crate::interpreter::token::ident::Ident;
 use crate::interpreter::Type;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
 
-#[derive(Clone, Debug)]
+/// A stack shared between everything that can see it, e.g. the contexts
+/// of a run and the closures defined in it
+pub type StackRef = Rc<RefCell<Stack>>;
+
+/// The location of a variable computed by the resolver: how many stacks to
+/// walk up and the index in that stack
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slot {
+    depth: usize,
+    index: usize,
+}
+
+impl Slot {
+    pub fn new(depth: usize, index: usize) -> Self {
+        Self { depth, index }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+#[derive(Debug)]
 pub struct Stack {
-    symbols: HashMap<Ident, Type>,
-    parent_stack: Option<Box<Stack>>,
+    names: Vec<Ident>,
+    values: Vec<Option<Type>>,
+    parent_stack: Option<StackRef>,
+    // only kept to give the memory of a frame back to the run which called it
+    _allocation: Option<Allocation>,
 }
 
 impl Stack {
-    pub fn new(parent_stack: Option<Stack>) -> Self {
+    pub fn new(parent_stack: Option<StackRef>) -> Self {
         Self {
-            symbols: HashMap::new(),
-            parent_stack: match parent_stack {
-                Some(s) => Some(Box::new(s)),
-                None => None,
-            },
+            names: Vec::new(),
+            values: Vec::new(),
+            parent_stack,
+            _allocation: None,
         }
     }
+
+    pub fn new_ref(parent_stack: Option<StackRef>) -> StackRef {
+        Rc::new(RefCell::new(Self::new(parent_stack)))
+    }
+
+    /// creates the stack for a function call with a slot for every local
+    pub fn frame(names: &[Ident], parent_stack: StackRef, allocation: Allocation) -> StackRef {
+        Rc::new(RefCell::new(Self {
+            names: names.to_vec(),
+            values: vec![None; names.len()],
+            parent_stack: Some(parent_stack),
+            _allocation: Some(allocation),
+        }))
+    }
+
+    /// the bytes used by a frame with `locals` variables
+    pub fn frame_size(locals: usize) -> usize {
+        std::mem::size_of::<Self>()
+            + locals * (std::mem::size_of::<Ident>() + std::mem::size_of::<Option<Type>>())
+    }
+
+    /// returns the slot of `name` in this stack, adding one if it doesn't exist yet
+    pub fn declare(&mut self, name: Ident) -> usize {
+        match self.names.iter().position(|n| *n == name) {
+            Some(index) => index,
+            None => {
+                self.names.push(name);
+                self.values.push(None);
+                self.names.len() - 1
+            }
+        }
+    }
+
     pub fn get(&self, name: &Ident) -> Option<Type> {
-        match self.symbols.get(name) {
-            Some(a) => Some(a.clone()),
-            None if self.parent_stack.is_some() => self.parent_stack.as_ref().unwrap().get(name),
-            None => None,
+        match self.names.iter().position(|n| n == name) {
+            Some(index) if self.values[index].is_some() => self.values[index].clone(),
+            _ => self.parent_stack.as_ref()?.borrow().get(name),
         }
     }
+
     pub fn set(&mut self, name: Ident, value: Type) {
-        *self.symbols.entry(name).or_insert(value) = value.clone()
+        let index = self.declare(name);
+        self.values[index] = Some(value);
+    }
+
+    /// the stack of the surrounding scope, e.g. the one a function was defined in
+    pub fn parent(&self) -> Option<StackRef> {
+        self.parent_stack.clone()
+    }
+
+    /// all variables with a value in this stack, in the order they were declared
+    pub fn variables(&self) -> Vec<(String, Type)> {
+        self.names
+            .iter()
+            .zip(&self.values)
+            .filter_map(|(name, value)| Some((name.get(), value.clone()?)))
+            .collect()
+    }
+
+    pub fn get_slot(&self, slot: Slot) -> Option<Type> {
+        if slot.depth == 0 {
+            self.values.get(slot.index)?.clone()
+        } else {
+            self.parent_stack
+                .as_ref()?
+                .borrow()
+                .get_slot(Slot::new(slot.depth - 1, slot.index))
+        }
+    }
+
+    pub fn set_slot(&mut self, slot: Slot, value: Type) {
+        if slot.depth == 0 {
+            if slot.index >= self.values.len() {
+                self.values.resize(slot.index + 1, None);
+            }
+            self.values[slot.index] = Some(value);
+        } else if let Some(parent) = &self.parent_stack {
+            parent
+                .borrow_mut()
+                .set_slot(Slot::new(slot.depth - 1, slot.index), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::token::types::Integer;
+
+    fn ident(name: &str) -> Ident {
+        Ident::new(name)
     }
 
-    pub fn combine(&mut self, other: Self) {
-        self.symbols.extend(other.symbols)
+    fn int(value: i64) -> Type {
+        Type::Integer(Integer::new(value))
     }
 
-    // TODO use this
-    /*
-    pub fn remove(&mut self, name: Ident) {
-        self.symbols.remove(&name);
-    }*/
+    #[test]
+    fn names_are_looked_up_in_the_parent_stacks() {
+        let outer = Stack::new_ref(None);
+        outer.borrow_mut().set(ident("a"), int(1));
+        let mut inner = Stack::new(Some(outer.clone()));
+        assert_eq!(inner.get(&ident("a")), Some(int(1)));
+        assert_eq!(inner.get(&ident("b")), None);
+        // declaring a name without a value doesn't hide the outer one
+        inner.declare(ident("a"));
+        assert_eq!(inner.get(&ident("a")), Some(int(1)));
+        inner.set(ident("a"), int(2));
+        assert_eq!(inner.get(&ident("a")), Some(int(2)));
+        assert_eq!(outer.borrow().get(&ident("a")), Some(int(1)));
+        assert_eq!(inner.variables(), vec![("a".to_string(), int(2))]);
+    }
+
+    #[test]
+    fn slots_walk_up_the_stacks() {
+        let outer = Stack::new_ref(None);
+        let index = outer.borrow_mut().declare(ident("a"));
+        assert_eq!(outer.borrow_mut().declare(ident("a")), index);
+        let mut inner = Stack::new(Some(outer.clone()));
+        inner.set_slot(Slot::new(1, index), int(3));
+        inner.set_slot(Slot::new(0, 2), int(4));
+        assert_eq!(outer.borrow().get(&ident("a")), Some(int(3)));
+        assert_eq!(inner.get_slot(Slot::new(1, index)), Some(int(3)));
+        assert_eq!(inner.get_slot(Slot::new(0, 2)), Some(int(4)));
+        assert_eq!(inner.get_slot(Slot::new(0, 1)), None);
+        assert_eq!(inner.get_slot(Slot::new(2, 0)), None);
+    }
+
+    #[test]
+    fn stacks_are_shared_by_everything_that_refers_to_them() {
+        let outer = Stack::new_ref(None);
+        let closure = Stack::new_ref(Some(outer.clone()));
+        outer.borrow_mut().set(ident("late"), int(5));
+        assert_eq!(closure.borrow().get(&ident("late")), Some(int(5)));
+        assert!(Rc::ptr_eq(&closure.borrow().parent().unwrap(), &outer));
+    }
 }
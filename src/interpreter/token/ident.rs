@@ -1,19 +1,125 @@
-#[derive(Clone, Debug, PartialEq, Hash, PartialOrd, Eq)]
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+use unicode_normalization::UnicodeNormalization;
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new(Interner::default());
+}
+
+/// The interner keeps at least this many names before it looks for unused ones
+const MIN_SWEEP: usize = 1024;
+
+/// Keeps one copy of every identifier name in use, so names can be compared
+/// by their address
+///
+/// Names nothing refers to anymore are dropped once the table has doubled,
+/// so scripts which keep coming up with new names don't use up the memory.
+#[derive(Default)]
+struct Interner {
+    names: HashSet<Rc<str>>,
+    // the number of names after which the unused ones are dropped
+    sweep_at: usize,
+}
+
+impl Interner {
+    fn intern(&mut self, name: &str) -> Rc<str> {
+        if let Some(name) = self.names.get(name) {
+            return name.clone();
+        }
+        if self.names.len() >= self.sweep_at {
+            self.names.retain(|name| Rc::strong_count(name) > 1);
+            self.sweep_at = MIN_SWEEP.max(self.names.len() * 2);
+        }
+        let name: Rc<str> = name.into();
+        self.names.insert(name.clone());
+        name
+    }
+}
+
+#[derive(Clone, Eq)]
 pub struct Ident {
-    name: String,
+    name: Rc<str>,
 }
 
 impl Ident {
-    pub fn new(name: String) -> Self {
+    pub fn new(name: impl AsRef<str>) -> Self {
+        let name = name.as_ref();
+        // names that look the same should be the same, no matter how they are encoded
+        let name = if name.is_ascii() {
+            INTERNER.with(|interner| interner.borrow_mut().intern(name))
+        } else {
+            let name: String = name.nfc().collect();
+            INTERNER.with(|interner| interner.borrow_mut().intern(&name))
+        };
         Self { name }
     }
     pub fn get(&self) -> String {
-        self.name.clone()
+        self.as_str().to_string()
+    }
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+}
+
+// there is only one copy of every name, so it is enough to compare addresses
+impl PartialEq for Ident {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.name, &other.name)
+    }
+}
+
+impl Hash for Ident {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(Rc::as_ptr(&self.name) as *const u8, state)
     }
 }
 
 impl std::fmt::Display for Ident {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.name)
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::fmt::Debug for Ident {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_names_share_a_symbol() {
+        let a = Ident::new("name");
+        assert_eq!(a, Ident::new("name"));
+        assert_ne!(a, Ident::new("other"));
+        assert_eq!(a.as_str(), "name");
+        assert_eq!(a.to_string(), "name");
+    }
+
+    #[test]
+    fn names_are_normalized() {
+        let composed = Ident::new("caf\u{e9}");
+        let decomposed = Ident::new("cafe\u{301}");
+        assert_eq!(composed, decomposed);
+        assert_eq!(decomposed.get(), "caf\u{e9}");
+        // only equivalent names are merged, not ones that merely look alike
+        assert_ne!(Ident::new("\u{fb01}"), Ident::new("fi"));
+    }
+
+    #[test]
+    fn unused_names_are_dropped() {
+        let kept = Ident::new("kept");
+        let pointer = Rc::as_ptr(&kept.name);
+        for i in 0..4 * MIN_SWEEP {
+            Ident::new(format!("name{}", i));
+        }
+        let names = INTERNER.with(|interner| interner.borrow().names.len());
+        assert!(names <= 2 * MIN_SWEEP, "{} names are kept", names);
+        assert_eq!(Rc::as_ptr(&Ident::new("kept").name), pointer);
     }
 }
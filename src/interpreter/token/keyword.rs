@@ -9,19 +9,27 @@ pub enum Keyword {
     While,
     In,
     To,
+    Fn,
+    Import,
+    From,
+    As,
+    Struct,
+    Enum,
+    Match,
 }
 
 pub fn keywords() -> Vec<String> {
     [
-        "let", "if", "else", "then", "elif", "for", "while", "in", "to",
+        "let", "if", "else", "then", "elif", "for", "while", "in", "to", "fn", "import", "from",
+        "as", "struct", "enum", "match",
     ]
     .iter()
     .map(|s| s.to_string())
     .collect()
 }
 
-pub fn is_keyword(word: String) -> bool {
-    keywords().contains(&word)
+pub fn is_keyword(word: &str) -> bool {
+    keywords().iter().any(|keyword| keyword == word)
 }
 
 impl std::fmt::Display for Keyword {
@@ -39,6 +47,13 @@ impl std::fmt::Display for Keyword {
                 Self::While => "while",
                 Self::In => "in",
                 Self::To => "to",
+                Self::Fn => "fn",
+                Self::Import => "import",
+                Self::From => "from",
+                Self::As => "as",
+                Self::Struct => "struct",
+                Self::Enum => "enum",
+                Self::Match => "match",
             }
         )
     }
@@ -55,6 +70,13 @@ pub fn from_str(v: &str) -> Option<Keyword> {
         "while" => Some(Keyword::While),
         "in" => Some(Keyword::In),
         "to" => Some(Keyword::To),
+        "fn" => Some(Keyword::Fn),
+        "import" => Some(Keyword::Import),
+        "from" => Some(Keyword::From),
+        "as" => Some(Keyword::As),
+        "struct" => Some(Keyword::Struct),
+        "enum" => Some(Keyword::Enum),
+        "match" => Some(Keyword::Match),
         _ => None,
     }
 }
@@ -1,4 +1,6 @@
+use crate::interpreter::{limits, Position};
 use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
 
 pub mod ident;
 pub mod keyword;
@@ -7,6 +9,7 @@ use ident::Ident;
 use keyword::Keyword;
 use types::Type;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, PartialEq)]
 pub enum Token {
     Plus,
@@ -17,6 +20,8 @@ pub enum Token {
     RightParenthesis,
     LeftCurlyBrackets,
     RightCurlyBrackets,
+    LeftSquareBrackets,
+    RightSquareBrackets,
     Equal,
     DoubleEqual,
     NonEqual,
@@ -27,12 +32,43 @@ pub enum Token {
     DoubleAnd,
     DoubleOr,
     Bang,
+    Comma,
+    Dot,
+    /// `...` in front of the rest of a list pattern
+    Ellipsis,
+    /// `:` in front of a type annotation
+    Colon,
+    Semicolon,
+    Newline,
+    Arrow,
     Ident(Ident),
     Type(Type),
     Keyword(Keyword),
+    /// a string with expressions in braces, shared as the parser copies
+    /// tokens while looking at them
+    Interpolation(Rc<[Segment]>),
     EOF,
 }
 
+/// A part of an interpolated string
+#[derive(Clone, PartialEq)]
+pub enum Segment {
+    Text(String, Position),
+    /// the tokens between the braces, ending with `Token::EOF` at the `}`
+    Expr(Vec<(Token, Position)>),
+}
+
+impl Drop for Segment {
+    // the strings in the braces are dropped one after another, which is as
+    // deep as they are nested
+    fn drop(&mut self) {
+        if let Self::Expr(tokens) = self {
+            let tokens = std::mem::take(tokens);
+            limits::grow(|| drop(tokens))
+        }
+    }
+}
+
 impl Display for Token {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
@@ -47,6 +83,8 @@ impl Display for Token {
                 Self::RightParenthesis => ")".to_string(),
                 Self::LeftCurlyBrackets => "{".to_string(),
                 Self::RightCurlyBrackets => "}".to_string(),
+                Self::LeftSquareBrackets => "[".to_string(),
+                Self::RightSquareBrackets => "]".to_string(),
                 Self::Type(t) => format!("{}", t),
                 Self::Equal => "=".to_string(),
                 Self::DoubleEqual => "==".to_string(),
@@ -58,8 +96,16 @@ impl Display for Token {
                 Self::DoubleAnd => "&&".to_string(),
                 Self::DoubleOr => "||".to_string(),
                 Self::Bang => "!".to_string(),
+                Self::Comma => ",".to_string(),
+                Self::Dot => ".".to_string(),
+                Self::Ellipsis => "...".to_string(),
+                Self::Colon => ":".to_string(),
+                Self::Semicolon => ";".to_string(),
+                Self::Newline => "\\n".to_string(),
+                Self::Arrow => "->".to_string(),
                 Self::Ident(i) => format!("{}", i),
                 Self::Keyword(k) => format!("{}", k),
+                Self::Interpolation(_) => "interpolated string".to_string(),
                 Self::EOF => "EOF".to_string(),
             }
         )
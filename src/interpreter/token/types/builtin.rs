@@ -0,0 +1,186 @@
+use crate::interpreter::token::ident::Ident;
+use crate::interpreter::token::types::{Cmp, CmpResult, Conversion, Operators, Type};
+use crate::interpreter::{Context, Error, ErrorKind, Position};
+use std::fmt::{self, Display, Formatter};
+use std::ops::{Deref, RangeInclusive};
+
+/// The rust function behind a builtin. It gets the arguments, the context of
+/// the call and the position of the call
+pub type NativeFn = fn(&Args, &Context, &Position) -> Result<Type, Error>;
+
+/// The arguments of a call, they deref to the positional ones
+pub struct Args {
+    positional: Vec<Type>,
+    named: Vec<(Ident, Type)>,
+}
+
+impl Args {
+    pub fn new(positional: Vec<Type>, named: Vec<(Ident, Type)>) -> Self {
+        Self { positional, named }
+    }
+
+    /// the argument given as `name = value`
+    pub fn named(&self, name: &str) -> Option<&Type> {
+        self.named
+            .iter()
+            .find(|(ident, _)| ident.as_str() == name)
+            .map(|(_, value)| value)
+    }
+}
+
+impl Deref for Args {
+    type Target = [Type];
+
+    fn deref(&self) -> &[Type] {
+        &self.positional
+    }
+}
+
+/// A function implemented in rust, or a method if it is bound to a value
+#[derive(Clone)]
+pub struct Builtin {
+    name: &'static str,
+    // how many positional arguments may be given, without the receiver
+    arity: RangeInclusive<usize>,
+    // the names of the arguments which may be given as `name = value`
+    keywords: &'static [&'static str],
+    // the type of the result if it is always the same, named like in annotations
+    returns: Option<&'static str>,
+    function: NativeFn,
+    // passed as the first argument, e.g. the string of `"a".upper`
+    receiver: Option<Box<Type>>,
+}
+
+impl Builtin {
+    pub fn new(name: &'static str, arity: RangeInclusive<usize>, function: NativeFn) -> Self {
+        Self {
+            name,
+            arity,
+            keywords: &[],
+            returns: None,
+            function,
+            receiver: None,
+        }
+    }
+
+    /// turns the function into a method of `receiver`
+    pub fn bind(self, receiver: Type) -> Self {
+        Self {
+            receiver: Some(Box::new(receiver)),
+            ..self
+        }
+    }
+
+    pub fn with_keywords(self, keywords: &'static [&'static str]) -> Self {
+        Self { keywords, ..self }
+    }
+
+    pub fn with_returns(self, returns: Option<&'static str>) -> Self {
+        Self { returns, ..self }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn get_arity(&self) -> &RangeInclusive<usize> {
+        &self.arity
+    }
+
+    pub fn get_keywords(&self) -> &'static [&'static str] {
+        self.keywords
+    }
+
+    pub fn get_returns(&self) -> Option<&'static str> {
+        self.returns
+    }
+
+    pub fn call(&self, args: &Args, context: &Context, pos: &Position) -> Result<Type, Error> {
+        if let Some((name, _)) =
+            (args.named.iter()).find(|(name, _)| !self.keywords.contains(&name.as_str()))
+        {
+            return Err(Error::new(
+                ErrorKind::TypeError,
+                &format!("{} got an unexpected argument {}", self, name),
+                Some(pos.clone()),
+            ));
+        }
+        if !self.arity.contains(&args.len()) {
+            return Err(Error::new(
+                ErrorKind::TypeError,
+                &format!(
+                    "{} takes {} arguments but {} were given",
+                    self,
+                    describe_arity(&self.arity),
+                    args.len()
+                ),
+                Some(pos.clone()),
+            ));
+        }
+        match &self.receiver {
+            Some(receiver) => {
+                let mut positional = vec![receiver.as_ref().clone()];
+                positional.extend(args.iter().cloned());
+                let args = Args::new(positional, args.named.clone());
+                (self.function)(&args, context, pos)
+            }
+            None => (self.function)(args, context, pos),
+        }
+    }
+}
+
+/// how many arguments `arity` allows, e.g. `1 to 2` or `at least 1`
+pub fn describe_arity(arity: &RangeInclusive<usize>) -> String {
+    let (min, max) = (arity.start(), arity.end());
+    if min == max {
+        min.to_string()
+    } else if *max == usize::MAX {
+        format!("at least {}", min)
+    } else {
+        format!("{} to {}", min, max)
+    }
+}
+
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.receiver == other.receiver
+    }
+}
+
+impl Display for Builtin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.receiver {
+            Some(receiver) => write!(f, "<method {} of {}>", self.name, receiver.type_name()),
+            None => write!(f, "<builtin function {}>", self.name),
+        }
+    }
+}
+
+impl Cmp for Builtin {
+    fn cmp(&self, other: Type) -> Result<CmpResult, Error> {
+        match other {
+            Type::Builtin(v) if *self == v => Ok(CmpResult::Equal),
+            _ => Err(Error::new(
+                ErrorKind::Unimplemented,
+                "can't compare functions",
+                None,
+            )),
+        }
+    }
+}
+
+impl Operators for Builtin {
+    fn eq(&self, other: Type) -> Result<Type, Error> {
+        Ok(matches!(other, Type::Builtin(v) if *self == v).into())
+    }
+
+    fn neq(&self, other: Type) -> Result<Type, Error> {
+        Ok((!matches!(other, Type::Builtin(v) if *self == v)).into())
+    }
+}
+
+impl Conversion for Builtin {
+    fn __bool__(&self) -> Result<bool, Error> {
+        Ok(true)
+    }
+}
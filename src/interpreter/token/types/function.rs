@@ -0,0 +1,119 @@
+use crate::interpreter::ast::nodes::Node;
+use crate::interpreter::stack::StackRef;
+use crate::interpreter::token::ident::Ident;
+use crate::interpreter::token::types::{Cmp, CmpResult, Conversion, Operators, Type};
+use crate::interpreter::{Error, ErrorKind};
+use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
+
+/// A user defined function together with the stack it was defined in
+#[derive(Clone)]
+pub struct Function {
+    name: Option<Ident>,
+    params: Vec<Ident>,
+    locals: Rc<[Ident]>,
+    body: Rc<Node>,
+    stack: StackRef,
+    // passed as the first argument, e.g. the object of `point.norm`
+    receiver: Option<Box<Type>>,
+}
+
+impl Function {
+    pub fn new(
+        name: Option<Ident>,
+        params: Vec<Ident>,
+        locals: Rc<[Ident]>,
+        body: Rc<Node>,
+        stack: StackRef,
+    ) -> Self {
+        Self {
+            name,
+            params,
+            locals,
+            body,
+            stack,
+            receiver: None,
+        }
+    }
+
+    /// turns the function into a method of `receiver`
+    pub fn bind(self, receiver: Type) -> Self {
+        Self {
+            receiver: Some(Box::new(receiver)),
+            ..self
+        }
+    }
+
+    pub fn get_name(&self) -> Option<Ident> {
+        self.name.clone()
+    }
+
+    pub fn get_params(&self) -> &[Ident] {
+        &self.params
+    }
+
+    pub fn get_locals(&self) -> &[Ident] {
+        &self.locals
+    }
+
+    pub fn get_body(&self) -> Rc<Node> {
+        self.body.clone()
+    }
+
+    pub fn get_stack(&self) -> StackRef {
+        self.stack.clone()
+    }
+
+    pub fn get_receiver(&self) -> Option<&Type> {
+        self.receiver.as_deref()
+    }
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.body, &other.body)
+            && Rc::ptr_eq(&self.stack, &other.stack)
+            && self.receiver == other.receiver
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (&self.name, &self.receiver) {
+            (Some(name), Some(receiver)) => {
+                write!(f, "<method {} of {}>", name, receiver.type_name())
+            }
+            (Some(name), None) => write!(f, "<function {}>", name),
+            (None, _) => write!(f, "<function>"),
+        }
+    }
+}
+
+impl Cmp for Function {
+    fn cmp(&self, other: Type) -> Result<CmpResult, Error> {
+        match other {
+            Type::Function(v) if *self == v => Ok(CmpResult::Equal),
+            _ => Err(Error::new(
+                ErrorKind::Unimplemented,
+                "can't compare functions",
+                None,
+            )),
+        }
+    }
+}
+
+impl Operators for Function {
+    fn eq(&self, other: Type) -> Result<Type, Error> {
+        Ok(matches!(other, Type::Function(v) if *self == v).into())
+    }
+
+    fn neq(&self, other: Type) -> Result<Type, Error> {
+        Ok((!matches!(other, Type::Function(v) if *self == v)).into())
+    }
+}
+
+impl Conversion for Function {
+    fn __bool__(&self) -> Result<bool, Error> {
+        Ok(true)
+    }
+}
@@ -0,0 +1,93 @@
+use crate::interpreter::limits::Charge;
+use crate::interpreter::token::types::{unsupported, Cmp, CmpResult, Conversion, Operators, Type};
+use crate::interpreter::{Error, ErrorKind};
+use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
+
+/// An immutable sequence of values, copies share the items
+#[derive(Clone, PartialEq)]
+pub struct List {
+    items: Rc<Vec<Type>>,
+    charge: Charge,
+}
+
+impl List {
+    pub fn new(items: Vec<Type>) -> Self {
+        Self {
+            items: Rc::new(items),
+            charge: Charge::default(),
+        }
+    }
+
+    pub fn get_items(&self) -> &[Type] {
+        &self.items
+    }
+
+    pub fn get_charge(&self) -> &Charge {
+        &self.charge
+    }
+
+    /// the value with its memory counted by `charge`
+    pub fn with_charge(self, charge: Charge) -> Self {
+        Self { charge, ..self }
+    }
+}
+
+impl Display for List {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let items: Vec<String> = self
+            .items
+            .iter()
+            .map(|item| match item {
+                // quoted, so `["a, b"]` and `["a", "b"]` can be told apart
+                Type::String(v) => format!("{:?}", v.get_value()),
+                item => item.to_string(),
+            })
+            .collect();
+        write!(f, "[{}]", items.join(", "))
+    }
+}
+
+impl Cmp for List {
+    fn cmp(&self, other: Type) -> Result<CmpResult, Error> {
+        match other {
+            Type::List(v) if *self == v => Ok(CmpResult::Equal),
+            _ => Err(Error::new(
+                ErrorKind::Unimplemented,
+                "can't compare lists",
+                None,
+            )),
+        }
+    }
+}
+
+impl Operators for List {
+    fn add(&self, other: Type) -> Result<Type, Error> {
+        match other {
+            Type::List(v) => {
+                let items = self.items.iter().chain(v.items.iter()).cloned().collect();
+                Ok(Type::List(Self::new(items)))
+            }
+            ty @ Type::Object(_) => Err(unsupported(&ty)),
+            ty => Err(Error::new(
+                ErrorKind::TypeError,
+                &format!("can only add list to list, not {}", ty.type_name()),
+                None,
+            )),
+        }
+    }
+
+    fn eq(&self, other: Type) -> Result<Type, Error> {
+        Ok(matches!(other, Type::List(v) if *self == v).into())
+    }
+
+    fn neq(&self, other: Type) -> Result<Type, Error> {
+        Ok((!matches!(other, Type::List(v) if *self == v)).into())
+    }
+}
+
+impl Conversion for List {
+    fn __bool__(&self) -> Result<bool, Error> {
+        Ok(!self.items.is_empty())
+    }
+}
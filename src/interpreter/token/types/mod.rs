@@ -1,10 +1,35 @@
 extern crate derive_more;
 
+pub mod builtin;
+pub mod function;
+pub mod list;
+pub mod module;
 pub mod number;
+pub mod object;
+pub mod string;
+pub mod tuple;
 
+use crate::interpreter::limits::Charge;
 use crate::interpreter::{Error, ErrorKind};
+pub use builtin::{Args, Builtin, NativeFn};
+pub use function::Function;
+pub use list::List;
+pub use module::Module;
 pub use number::{Float, Integer};
+pub use object::{Enum, Object, Struct};
 use std::cmp::Ordering;
+pub use string::Str;
+pub use tuple::Tuple;
+
+/// the error of an operation with a value the type doesn't know. For objects
+/// it is `Unimplemented`, so their reflected method is tried instead
+pub fn unsupported(other: &Type) -> Error {
+    let kind = match other {
+        Type::Object(_) => ErrorKind::Unimplemented,
+        _ => ErrorKind::TypeError,
+    };
+    Error::new(kind, "No valid type", None)
+}
 
 pub trait Cmp {
     fn cmp(&self, other: Type) -> Result<CmpResult, Error>;
@@ -27,6 +52,9 @@ impl From<Ordering> for CmpResult {
     }
 }
 
+/// How a value is converted to other types. Objects of user defined types
+/// convert with their methods of the same name instead, see `visit::is_true`
+/// and `visit::to_str`
 pub trait Conversion
 where
     Self: std::fmt::Display,
@@ -54,6 +82,11 @@ where
             None,
         ))
     }
+
+    /// every value can be shown, by default as it is displayed
+    fn __str__(&self) -> Result<Str, Error> {
+        Ok(Str::new(&self.to_string()))
+    }
 }
 
 macro_rules! new_op {
@@ -122,6 +155,15 @@ where
 pub enum Type {
     Integer(Integer),
     Float(Float),
+    String(Str),
+    List(List),
+    Tuple(Tuple),
+    Function(Function),
+    Builtin(Builtin),
+    Module(Module),
+    Struct(Struct),
+    Object(Object),
+    Enum(Enum),
     None(NoneType),
 }
 
@@ -136,6 +178,15 @@ impl Type {
         match self {
             Type::Integer(v) => v,
             Type::Float(v) => v,
+            Type::String(v) => v,
+            Type::List(v) => v,
+            Type::Tuple(v) => v,
+            Type::Function(v) => v,
+            Type::Builtin(v) => v,
+            Type::Module(v) => v,
+            Type::Struct(v) => v,
+            Type::Object(v) => v,
+            Type::Enum(v) => v,
             Type::None(v) => v,
         }
     }
@@ -143,6 +194,15 @@ impl Type {
         match self {
             Type::Integer(v) => v,
             Type::Float(v) => v,
+            Type::String(v) => v,
+            Type::List(v) => v,
+            Type::Tuple(v) => v,
+            Type::Function(v) => v,
+            Type::Builtin(v) => v,
+            Type::Module(v) => v,
+            Type::Struct(v) => v,
+            Type::Object(v) => v,
+            Type::Enum(v) => v,
             Type::None(v) => v,
         }
     }
@@ -150,6 +210,59 @@ impl Type {
     pub fn none() -> Self {
         Self::None(NoneType::new())
     }
+
+    /// the bytes of a string or of the items of a list or tuple, without
+    /// what the items themselves use
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            Type::String(v) => Some(v.get_value().len()),
+            Type::List(v) => Some(std::mem::size_of_val(v.get_items())),
+            Type::Tuple(v) => Some(std::mem::size_of_val(v.get_items())),
+            _ => None,
+        }
+    }
+
+    /// the bytes of a string, list or tuple whose memory isn't counted yet,
+    /// including the items which aren't counted either
+    pub fn uncounted_size(&self) -> Option<usize> {
+        let (charge, items) = match self {
+            Type::String(v) => (v.get_charge(), &[][..]),
+            Type::List(v) => (v.get_charge(), v.get_items()),
+            Type::Tuple(v) => (v.get_charge(), v.get_items()),
+            _ => return None,
+        };
+        if charge.is_counted() {
+            return None;
+        }
+        let items: usize = items.iter().filter_map(Type::uncounted_size).sum();
+        Some(self.size().unwrap_or_default() + items)
+    }
+
+    /// a string, list or tuple with its memory counted by `charge`
+    pub fn with_charge(self, charge: Charge) -> Self {
+        match self {
+            Type::String(v) => Type::String(v.with_charge(charge)),
+            Type::List(v) => Type::List(v.with_charge(charge)),
+            Type::Tuple(v) => Type::Tuple(v.with_charge(charge)),
+            value => value,
+        }
+    }
+
+    pub fn type_name(&self) -> &str {
+        match self {
+            Type::Integer(_) => "int",
+            Type::Float(_) => "float",
+            Type::String(_) => "str",
+            Type::List(_) => "list",
+            Type::Tuple(_) => "tuple",
+            Type::Function(_) | Type::Builtin(_) => "function",
+            Type::Module(_) => "module",
+            Type::Struct(_) => "struct",
+            Type::Object(v) => v.get_struct().type_name(),
+            Type::Enum(_) => "enum",
+            Type::None(_) => "None",
+        }
+    }
 }
 
 impl Cmp for Type {
@@ -157,6 +270,15 @@ impl Cmp for Type {
         match self {
             Self::Integer(v) => v.cmp(other),
             Self::Float(v) => v.cmp(other),
+            Self::String(v) => v.cmp(other),
+            Self::List(v) => v.cmp(other),
+            Self::Tuple(v) => v.cmp(other),
+            Self::Function(v) => v.cmp(other),
+            Self::Builtin(v) => v.cmp(other),
+            Self::Module(v) => v.cmp(other),
+            Self::Struct(v) => v.cmp(other),
+            Self::Object(v) => v.cmp(other),
+            Self::Enum(v) => v.cmp(other),
             Self::None(v) => v.cmp(other),
         }
     }
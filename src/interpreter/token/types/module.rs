@@ -0,0 +1,77 @@
+use crate::interpreter::token::ident::Ident;
+use crate::interpreter::token::types::{Cmp, CmpResult, Conversion, Operators, Type};
+use crate::interpreter::{Error, ErrorKind};
+use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
+
+/// A named collection of values, its members are accessed with `module.name`
+#[derive(Clone)]
+pub struct Module {
+    name: Rc<str>,
+    members: Rc<Vec<(Ident, Type)>>,
+}
+
+impl Module {
+    pub fn new(name: &str, members: Vec<(Ident, Type)>) -> Self {
+        Self {
+            name: name.into(),
+            members: Rc::new(members),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get(&self, name: &Ident) -> Option<Type> {
+        self.members
+            .iter()
+            .find(|(member, _)| member == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn members(&self) -> &[(Ident, Type)] {
+        &self.members
+    }
+}
+
+impl PartialEq for Module {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.members, &other.members)
+    }
+}
+
+impl Display for Module {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
+
+impl Cmp for Module {
+    fn cmp(&self, other: Type) -> Result<CmpResult, Error> {
+        match other {
+            Type::Module(v) if *self == v => Ok(CmpResult::Equal),
+            _ => Err(Error::new(
+                ErrorKind::Unimplemented,
+                "can't compare modules",
+                None,
+            )),
+        }
+    }
+}
+
+impl Operators for Module {
+    fn eq(&self, other: Type) -> Result<Type, Error> {
+        Ok(matches!(other, Type::Module(v) if *self == v).into())
+    }
+
+    fn neq(&self, other: Type) -> Result<Type, Error> {
+        Ok((!matches!(other, Type::Module(v) if *self == v)).into())
+    }
+}
+
+impl Conversion for Module {
+    fn __bool__(&self) -> Result<bool, Error> {
+        Ok(true)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::interpreter::token::types::{Cmp, CmpResult, Conversion, Operators, Type};
+use crate::interpreter::token::types::{unsupported, Cmp, CmpResult, Conversion, Operators, Type};
 use crate::interpreter::{Error, ErrorKind};
 use std::fmt::{self, Display, Formatter};
 
@@ -11,6 +11,21 @@ impl Integer {
     pub fn new(value: i64) -> Self {
         Integer { value }
     }
+
+    pub fn get_value(&self) -> i64 {
+        self.value
+    }
+
+    /// the result of an operation, unless it doesn't fit in an int
+    fn checked(value: Option<i64>) -> Result<Type, Error> {
+        value.map(|v| Type::Integer(Self::new(v))).ok_or_else(|| {
+            Error::new(
+                ErrorKind::ValueError,
+                "the result is too large for an int",
+                None,
+            )
+        })
+    }
 }
 
 impl Display for Integer {
@@ -23,7 +38,7 @@ impl Cmp for Integer {
     fn cmp(&self, other: Type) -> Result<CmpResult, Error> {
         match other {
             Type::Integer(v) => Ok(self.value.cmp(&v.value).into()),
-            _ => Err(Error::new(ErrorKind::TypeError, "No valid type", None)),
+            ty => Err(unsupported(&ty)),
         }
     }
 }
@@ -31,22 +46,22 @@ impl Cmp for Integer {
 impl Operators for Integer {
     fn add(&self, other: Type) -> Result<Type, Error> {
         match other {
-            Type::Integer(v) => Ok(Type::Integer(Self::new(self.value + v.value))),
-            _ => Err(Error::new(ErrorKind::TypeError, "No valid type", None)),
+            Type::Integer(v) => Self::checked(self.value.checked_add(v.value)),
+            ty => Err(unsupported(&ty)),
         }
     }
 
     fn sub(&self, other: Type) -> Result<Type, Error> {
         match other {
-            Type::Integer(v) => Ok(Type::Integer(Self::new(self.value - v.value))),
-            _ => Err(Error::new(ErrorKind::TypeError, "No valid type", None)),
+            Type::Integer(v) => Self::checked(self.value.checked_sub(v.value)),
+            ty => Err(unsupported(&ty)),
         }
     }
 
     fn mul(&self, other: Type) -> Result<Type, Error> {
         match other {
-            Type::Integer(v) => Ok(Type::Integer(Self::new(self.value * v.value))),
-            _ => Err(Error::new(ErrorKind::TypeError, "No valid type", None)),
+            Type::Integer(v) => Self::checked(self.value.checked_mul(v.value)),
+            ty => Err(unsupported(&ty)),
         }
     }
     fn div(&self, other: Type) -> Result<Type, Error> {
@@ -58,14 +73,14 @@ impl Operators for Integer {
             ))
         } else {
             match other {
-                Type::Integer(v) => Ok(Type::Integer(Self::new(self.value / v.value))),
-                _ => Err(Error::new(ErrorKind::TypeError, "No valid type", None)),
+                Type::Integer(v) => Self::checked(self.value.checked_div(v.value)),
+                ty => Err(unsupported(&ty)),
             }
         }
     }
 
     fn neg(&self) -> Result<Type, Error> {
-        Ok(Type::Integer(Self::new(-self.value)))
+        Self::checked(self.value.checked_neg())
     }
 
     fn and(&self, other: Type) -> Result<Type, Error> {
@@ -77,7 +92,7 @@ impl Operators for Integer {
                     0
                 },
             ))),
-            _ => Err(Error::new(ErrorKind::TypeError, "No valid type", None)),
+            ty => Err(unsupported(&ty)),
         }
     }
 
@@ -90,7 +105,7 @@ impl Operators for Integer {
                     0
                 },
             ))),
-            _ => Err(Error::new(ErrorKind::TypeError, "No valid type", None)),
+            ty => Err(unsupported(&ty)),
         }
     }
 
@@ -118,6 +133,10 @@ impl Float {
     pub fn new(value: f64) -> Self {
         Float { value }
     }
+
+    pub fn get_value(&self) -> f64 {
+        self.value
+    }
 }
 
 impl Display for Float {
@@ -140,7 +159,7 @@ impl Cmp for Float {
                     None,
                 )),
             },
-            _ => Err(Error::new(ErrorKind::TypeError, "No valid type", None)),
+            ty => Err(unsupported(&ty)),
         }
     }
 }
@@ -149,21 +168,21 @@ impl Operators for Float {
     fn add(&self, other: Type) -> Result<Type, Error> {
         match other {
             Type::Float(v) => Ok(Type::Float(Self::new(self.value + v.value))),
-            _ => Err(Error::new(ErrorKind::TypeError, "No valid type", None)),
+            ty => Err(unsupported(&ty)),
         }
     }
 
     fn sub(&self, other: Type) -> Result<Type, Error> {
         match other {
             Type::Float(v) => Ok(Type::Float(Self::new(self.value - v.value))),
-            _ => Err(Error::new(ErrorKind::TypeError, "No valid type", None)),
+            ty => Err(unsupported(&ty)),
         }
     }
 
     fn mul(&self, other: Type) -> Result<Type, Error> {
         match other {
             Type::Float(v) => Ok(Type::Float(Self::new(self.value * v.value))),
-            _ => Err(Error::new(ErrorKind::TypeError, "No valid type", None)),
+            ty => Err(unsupported(&ty)),
         }
     }
 
@@ -177,7 +196,7 @@ impl Operators for Float {
         } else {
             match other {
                 Type::Float(v) => Ok(Type::Float(Self::new(self.value / v.value))),
-                _ => Err(Error::new(ErrorKind::TypeError, "No valid type", None)),
+                ty => Err(unsupported(&ty)),
             }
         }
     }
@@ -0,0 +1,305 @@
+use crate::interpreter::token::ident::Ident;
+use crate::interpreter::token::types::{Cmp, CmpResult, Conversion, Function, Operators, Type};
+use crate::interpreter::{Error, ErrorKind};
+use std::cell::RefCell;
+use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
+
+thread_local! {
+    // the objects which are being displayed, an object which contains itself
+    // is only shown once
+    static DISPLAYED: RefCell<Vec<*const RefCell<Vec<Type>>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A user defined type, `struct Point { x, y }`. Calling it creates an object
+#[derive(Clone)]
+pub struct Struct {
+    name: Ident,
+    // the enum if this is one of its variants
+    owner: Option<Ident>,
+    fields: Rc<[Ident]>,
+    methods: Rc<Vec<(Ident, Function)>>,
+}
+
+impl Struct {
+    pub fn new(name: Ident, fields: Vec<Ident>, methods: Vec<(Ident, Function)>) -> Self {
+        Self {
+            name,
+            owner: None,
+            fields: fields.into(),
+            methods: Rc::new(methods),
+        }
+    }
+
+    /// the variant `name` of the enum `owner`, like `Shape.Circle`
+    pub fn variant(owner: Ident, name: Ident, fields: Vec<Ident>) -> Self {
+        Self {
+            owner: Some(owner),
+            ..Self::new(name, fields, Vec::new())
+        }
+    }
+
+    pub fn get_name(&self) -> Ident {
+        self.name.clone()
+    }
+
+    /// the enum the struct is a variant of
+    pub fn get_owner(&self) -> Option<Ident> {
+        self.owner.clone()
+    }
+
+    /// the name of the type of its objects, the enum for variants
+    pub fn type_name(&self) -> &str {
+        self.owner.as_ref().unwrap_or(&self.name).as_str()
+    }
+
+    pub fn get_fields(&self) -> &[Ident] {
+        &self.fields
+    }
+
+    /// the method without a receiver, it gets the object as first argument
+    pub fn get_method(&self, name: &Ident) -> Option<&Function> {
+        self.methods
+            .iter()
+            .find(|(method, _)| method == name)
+            .map(|(_, function)| function)
+    }
+
+    /// creates an object with a value for every field
+    pub fn instantiate(&self, values: Vec<Type>) -> Object {
+        Object {
+            ty: self.clone(),
+            values: Rc::new(RefCell::new(values)),
+        }
+    }
+}
+
+impl PartialEq for Struct {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.methods, &other.methods)
+    }
+}
+
+impl Display for Struct {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.owner {
+            Some(owner) => write!(f, "<variant {}.{}>", owner, self.name),
+            None => write!(f, "<struct {}>", self.name),
+        }
+    }
+}
+
+impl Cmp for Struct {
+    fn cmp(&self, other: Type) -> Result<CmpResult, Error> {
+        match other {
+            Type::Struct(v) if *self == v => Ok(CmpResult::Equal),
+            _ => Err(Error::new(
+                ErrorKind::Unimplemented,
+                "can't compare structs",
+                None,
+            )),
+        }
+    }
+}
+
+impl Operators for Struct {
+    fn eq(&self, other: Type) -> Result<Type, Error> {
+        Ok(matches!(other, Type::Struct(v) if *self == v).into())
+    }
+
+    fn neq(&self, other: Type) -> Result<Type, Error> {
+        Ok((!matches!(other, Type::Struct(v) if *self == v)).into())
+    }
+}
+
+impl Conversion for Struct {
+    fn __bool__(&self) -> Result<bool, Error> {
+        Ok(true)
+    }
+}
+
+/// An instance of a struct. Copies share the fields, so changing a field
+/// through one of them changes it for all
+#[derive(Clone)]
+pub struct Object {
+    ty: Struct,
+    values: Rc<RefCell<Vec<Type>>>,
+}
+
+impl Object {
+    pub fn get_struct(&self) -> &Struct {
+        &self.ty
+    }
+
+    /// the value of a field or the method bound to the object
+    pub fn get(&self, name: &Ident) -> Option<Type> {
+        match self.ty.fields.iter().position(|field| field == name) {
+            Some(index) => Some(self.values.borrow()[index].clone()),
+            None => (self.ty.get_method(name))
+                .map(|method| Type::Function(method.clone().bind(Type::Object(self.clone())))),
+        }
+    }
+
+    /// changes a field, returns false if the struct has no such field
+    pub fn set(&self, name: &Ident, value: Type) -> bool {
+        match self.ty.fields.iter().position(|field| field == name) {
+            Some(index) => {
+                self.values.borrow_mut()[index] = value;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// objects are the same if they share their fields, variants of enums are
+/// equal if they have equal fields
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.values, &other.values)
+            || (self.ty.owner.is_some()
+                && self.ty == other.ty
+                && *self.values.borrow() == *other.values.borrow())
+    }
+}
+
+impl Display for Object {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match &self.ty.owner {
+            Some(owner) if self.ty.fields.is_empty() => {
+                return write!(f, "{}.{}", owner, self.ty.name)
+            }
+            Some(owner) => format!("{}.{}", owner, self.ty.name),
+            None => self.ty.name.to_string(),
+        };
+        let ptr = Rc::as_ptr(&self.values);
+        if DISPLAYED.with(|displayed| displayed.borrow().contains(&ptr)) {
+            return write!(f, "{}(...)", name);
+        }
+        DISPLAYED.with(|displayed| displayed.borrow_mut().push(ptr));
+        let fields: Vec<String> = (self.ty.fields.iter())
+            .zip(self.values.borrow().iter())
+            .map(|(field, value)| match value {
+                Type::String(v) => format!("{} = {:?}", field, v.get_value()),
+                value => format!("{} = {}", field, value),
+            })
+            .collect();
+        DISPLAYED.with(|displayed| displayed.borrow_mut().pop());
+        write!(f, "{}({})", name, fields.join(", "))
+    }
+}
+
+impl Cmp for Object {
+    fn cmp(&self, other: Type) -> Result<CmpResult, Error> {
+        match other {
+            Type::Object(v) if *self == v => Ok(CmpResult::Equal),
+            _ => Err(Error::new(
+                ErrorKind::Unimplemented,
+                &format!("can't compare {}", self.ty.type_name()),
+                None,
+            )),
+        }
+    }
+}
+
+impl Operators for Object {
+    fn eq(&self, other: Type) -> Result<Type, Error> {
+        Ok(matches!(other, Type::Object(v) if *self == v).into())
+    }
+
+    fn neq(&self, other: Type) -> Result<Type, Error> {
+        Ok((!matches!(other, Type::Object(v) if *self == v)).into())
+    }
+}
+
+impl Conversion for Object {
+    fn __bool__(&self) -> Result<bool, Error> {
+        Ok(true)
+    }
+}
+
+/// A type whose values are one of its variants, `enum Shape { Circle(r), Empty }`.
+/// Variants with fields are structs, the others are objects without fields
+#[derive(Clone)]
+pub struct Enum {
+    name: Ident,
+    variants: Rc<Vec<(Ident, Type)>>,
+}
+
+impl Enum {
+    /// the names of the variants with the names of their fields, None for
+    /// variants without parentheses
+    pub fn new(name: Ident, variants: Vec<(Ident, Option<Vec<Ident>>)>) -> Self {
+        let variants = variants
+            .into_iter()
+            .map(|(variant, fields)| {
+                let ty = Struct::variant(
+                    name.clone(),
+                    variant.clone(),
+                    fields.clone().unwrap_or_default(),
+                );
+                match fields {
+                    Some(_) => (variant, Type::Struct(ty)),
+                    None => (variant, Type::Object(ty.instantiate(Vec::new()))),
+                }
+            })
+            .collect();
+        Self {
+            name,
+            variants: Rc::new(variants),
+        }
+    }
+
+    pub fn get_name(&self) -> Ident {
+        self.name.clone()
+    }
+
+    /// the struct creating the variant, or the value for variants without fields
+    pub fn get(&self, name: &Ident) -> Option<Type> {
+        self.variants
+            .iter()
+            .find(|(variant, _)| variant == name)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+impl PartialEq for Enum {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.variants, &other.variants)
+    }
+}
+
+impl Display for Enum {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "<enum {}>", self.name)
+    }
+}
+
+impl Cmp for Enum {
+    fn cmp(&self, other: Type) -> Result<CmpResult, Error> {
+        match other {
+            Type::Enum(v) if *self == v => Ok(CmpResult::Equal),
+            _ => Err(Error::new(
+                ErrorKind::Unimplemented,
+                "can't compare enums",
+                None,
+            )),
+        }
+    }
+}
+
+impl Operators for Enum {
+    fn eq(&self, other: Type) -> Result<Type, Error> {
+        Ok(matches!(other, Type::Enum(v) if *self == v).into())
+    }
+
+    fn neq(&self, other: Type) -> Result<Type, Error> {
+        Ok((!matches!(other, Type::Enum(v) if *self == v)).into())
+    }
+}
+
+impl Conversion for Enum {
+    fn __bool__(&self) -> Result<bool, Error> {
+        Ok(true)
+    }
+}
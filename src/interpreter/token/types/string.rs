@@ -0,0 +1,83 @@
+use crate::interpreter::limits::Charge;
+use crate::interpreter::token::types::{unsupported, Cmp, CmpResult, Conversion, Operators, Type};
+use crate::interpreter::{Error, ErrorKind};
+use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
+
+/// An immutable string, copies share the text
+#[derive(Clone, PartialEq)]
+pub struct Str {
+    value: Rc<str>,
+    charge: Charge,
+}
+
+impl Str {
+    pub fn new(value: &str) -> Self {
+        Self {
+            value: value.into(),
+            charge: Charge::default(),
+        }
+    }
+
+    pub fn get_value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn get_charge(&self) -> &Charge {
+        &self.charge
+    }
+
+    /// the value with its memory counted by `charge`
+    pub fn with_charge(self, charge: Charge) -> Self {
+        Self { charge, ..self }
+    }
+}
+
+impl Display for Str {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl Cmp for Str {
+    fn cmp(&self, other: Type) -> Result<CmpResult, Error> {
+        match other {
+            Type::String(v) => Ok(self.value.cmp(&v.value).into()),
+            ty => Err(unsupported(&ty)),
+        }
+    }
+}
+
+impl Operators for Str {
+    fn add(&self, other: Type) -> Result<Type, Error> {
+        match other {
+            Type::String(v) => Ok(Type::String(Self::new(
+                &(self.value.to_string() + &v.value),
+            ))),
+            ty @ Type::Object(_) => Err(unsupported(&ty)),
+            ty => Err(Error::new(
+                ErrorKind::TypeError,
+                &format!("can only add str to str, not {}", ty.type_name()),
+                None,
+            )),
+        }
+    }
+
+    fn eq(&self, other: Type) -> Result<Type, Error> {
+        Ok(matches!(other, Type::String(v) if *self == v).into())
+    }
+
+    fn neq(&self, other: Type) -> Result<Type, Error> {
+        Ok((!matches!(other, Type::String(v) if *self == v)).into())
+    }
+}
+
+impl Conversion for Str {
+    fn __bool__(&self) -> Result<bool, Error> {
+        Ok(!self.value.is_empty())
+    }
+
+    fn __str__(&self) -> Result<Str, Error> {
+        Ok(self.clone())
+    }
+}
@@ -0,0 +1,97 @@
+use crate::interpreter::limits::Charge;
+use crate::interpreter::token::types::{unsupported, Cmp, CmpResult, Conversion, Operators, Type};
+use crate::interpreter::{Error, ErrorKind};
+use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
+
+/// A fixed group of values, `(1, "a")`. Unlike lists they are written with
+/// parentheses and used to return or pass several values at once
+#[derive(Clone, PartialEq)]
+pub struct Tuple {
+    items: Rc<Vec<Type>>,
+    charge: Charge,
+}
+
+impl Tuple {
+    pub fn new(items: Vec<Type>) -> Self {
+        Self {
+            items: Rc::new(items),
+            charge: Charge::default(),
+        }
+    }
+
+    pub fn get_items(&self) -> &[Type] {
+        &self.items
+    }
+
+    pub fn get_charge(&self) -> &Charge {
+        &self.charge
+    }
+
+    /// the value with its memory counted by `charge`
+    pub fn with_charge(self, charge: Charge) -> Self {
+        Self { charge, ..self }
+    }
+}
+
+impl Display for Tuple {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let items: Vec<String> = self
+            .items
+            .iter()
+            .map(|item| match item {
+                Type::String(v) => format!("{:?}", v.get_value()),
+                item => item.to_string(),
+            })
+            .collect();
+        match items.len() {
+            // the comma tells it apart from an expression in parentheses
+            1 => write!(f, "({},)", items[0]),
+            _ => write!(f, "({})", items.join(", ")),
+        }
+    }
+}
+
+impl Cmp for Tuple {
+    fn cmp(&self, other: Type) -> Result<CmpResult, Error> {
+        match other {
+            Type::Tuple(v) if *self == v => Ok(CmpResult::Equal),
+            _ => Err(Error::new(
+                ErrorKind::Unimplemented,
+                "can't compare tuples",
+                None,
+            )),
+        }
+    }
+}
+
+impl Operators for Tuple {
+    fn add(&self, other: Type) -> Result<Type, Error> {
+        match other {
+            Type::Tuple(v) => {
+                let items = self.items.iter().chain(v.items.iter()).cloned().collect();
+                Ok(Type::Tuple(Self::new(items)))
+            }
+            ty @ Type::Object(_) => Err(unsupported(&ty)),
+            ty => Err(Error::new(
+                ErrorKind::TypeError,
+                &format!("can only add tuple to tuple, not {}", ty.type_name()),
+                None,
+            )),
+        }
+    }
+
+    fn eq(&self, other: Type) -> Result<Type, Error> {
+        Ok(matches!(other, Type::Tuple(v) if *self == v).into())
+    }
+
+    fn neq(&self, other: Type) -> Result<Type, Error> {
+        Ok((!matches!(other, Type::Tuple(v) if *self == v)).into())
+    }
+}
+
+impl Conversion for Tuple {
+    fn __bool__(&self) -> Result<bool, Error> {
+        Ok(!self.items.is_empty())
+    }
+}
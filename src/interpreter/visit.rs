@@ -1,167 +1,930 @@
+use crate::interpreter::ast::arity_error;
 use crate::interpreter::ast::nodes::{
-    Assignment, BinaryOperationNode, ForNode, IfNode, Node, NodeType, OperationType,
-    UnaryOperation, UnaryOperationNode, WhileNode,
+    Assignment, AttributeNode, BinaryOperationNode, CallNode, Constructor, Destructure,
+    EnumDefNode, ForNode, FuncDefNode, IfNode, Import, Imported, IndexNode, Interpolation,
+    ListPattern, MatchNode, Node, NodeType, OperationType, Part, Pattern, SetAttributeNode,
+    Statements, StructDefNode, TupleNode, UnaryOperation, UnaryOperationNode, Variable, WhileNode,
 };
+use crate::interpreter::stack::{Slot, Stack};
 use crate::interpreter::token::ident::Ident;
-use crate::interpreter::token::types::{Cmp, CmpResult, Integer};
+use crate::interpreter::token::types::{
+    Args, Cmp, CmpResult, Enum, Function, Integer, List, Str, Struct, Tuple,
+};
 use crate::interpreter::token::Token;
-use crate::interpreter::{Context, Error, ErrorKind, Position, Type};
+use crate::interpreter::{builtins, Context, Error, ErrorKind, Position, Type};
+use std::convert::TryFrom;
+use std::fmt::Display;
 
-pub fn visit(node: Node, context: Context) -> Result<(Type, Context), Error> {
-    let position = node.get_pos();
-    match node.get_type() {
-        NodeType::Node(ty) => Ok((ty, context)),
+pub fn visit(node: &Node, context: &Context) -> Result<Type, Error> {
+    context.run.budget.step(&node.get_pos())?;
+    if let Some(hook) = &context.run.hook {
+        hook.before(node, context)?;
+    }
+    let value = match node.get_type() {
+        NodeType::Node(ty) => Ok(ty.clone()),
         NodeType::Operation(op) => match op {
-            OperationType::BinaryOperationNode(op) => visit_binary_operation(*op, context),
-            OperationType::UnaryOperationNode(op) => visit_unary_operation(*op, position, context),
+            OperationType::BinaryOperationNode(op) => {
+                visit_binary_operation(op, &node.get_pos(), context)
+            }
+            OperationType::UnaryOperationNode(op) => {
+                visit_unary_operation(op, &node.get_pos(), context)
+            }
         },
         NodeType::Var(id) => visit_var(id, context),
         NodeType::Assign(a) => visit_assignment(a, context),
+        NodeType::Destructure(node) => visit_destructure(node, context),
+        NodeType::Tuple(node) => visit_tuple(node, context),
         NodeType::IfNode(node) => visit_if_node(node, context),
         NodeType::ForNode(node) => visit_for_node(node, context),
         NodeType::WhileNode(node) => visit_while_node(node, context),
+        NodeType::FuncDefNode(node) => visit_func_def_node(node, context),
+        NodeType::CallNode(call) => visit_call_node(call, &node.get_pos(), context),
+        NodeType::AttributeNode(node) => visit_attribute_node(node, context),
+        NodeType::IndexNode(index) => visit_index(index, &node.get_pos(), context),
+        NodeType::SetAttribute(node) => visit_set_attribute(node, context),
+        NodeType::StructDef(node) => visit_struct_def(node, context),
+        NodeType::EnumDef(node) => visit_enum_def(node, context),
+        NodeType::Match(arms) => visit_match(arms, &node.get_pos(), context),
+        NodeType::Interpolation(node) => visit_interpolation(node, context),
+        NodeType::Import(node) => visit_import(node, context),
+        NodeType::Statements(node) => visit_statements(node, context),
+    }?;
+    context.run.budget.charge(value, &node.get_pos())
+}
+
+fn get_var(var: &Variable, context: &Context) -> Option<Type> {
+    let stack = context.stack.borrow();
+    match var.get_slot() {
+        // a local which isn't assigned yet, e.g. in `let n = n + 1`, is
+        // looked up in the surrounding scopes
+        Some(slot) => stack.get_slot(slot).or_else(|| stack.get(var.get_name())),
+        None => stack.get(var.get_name()),
+    }
+}
+
+fn set_var(var: &Variable, value: Type, context: &Context) {
+    let mut stack = context.stack.borrow_mut();
+    match var.get_slot() {
+        Some(slot) => stack.set_slot(slot, value),
+        None => stack.set(var.get_name().clone(), value),
+    }
+}
+
+fn visit_statements(node: &Statements, context: &Context) -> Result<Type, Error> {
+    let mut ty = Type::none();
+    for statement in node.get_nodes() {
+        ty = visit(statement, context)?;
+    }
+    Ok(ty)
+}
+
+/// creates the function defined by `node`, it sees the stack of `context`
+fn function(node: &FuncDefNode, context: &Context) -> Function {
+    let (name, params, body) = node.get_all();
+    let params: Vec<Ident> = params.iter().map(|p| p.get_name().clone()).collect();
+    Function::new(
+        name.map(|name| name.get_name().clone()),
+        params.clone(),
+        node.get_locals().unwrap_or_else(|| params.into()),
+        body.clone(),
+        context.stack.clone(),
+    )
+}
+
+fn visit_func_def_node(node: &FuncDefNode, context: &Context) -> Result<Type, Error> {
+    let function = Type::Function(function(node, context));
+    if let (Some(name), _, _) = node.get_all() {
+        set_var(name, function.clone(), context);
+    }
+    Ok(function)
+}
+
+fn visit_struct_def(node: &StructDefNode, context: &Context) -> Result<Type, Error> {
+    let fields = node
+        .get_fields()
+        .iter()
+        .map(|f| f.get_name().clone())
+        .collect();
+    let methods = node
+        .get_funcs()
+        .filter_map(|func| {
+            let function = function(func, context);
+            Some((function.get_name()?, function))
+        })
+        .collect();
+    let name = node.get_name();
+    let ty = Type::Struct(Struct::new(name.get_name().clone(), fields, methods));
+    set_var(name, ty.clone(), context);
+    Ok(ty)
+}
+
+fn visit_enum_def(node: &EnumDefNode, context: &Context) -> Result<Type, Error> {
+    let variants = (node.get_variants().iter())
+        .map(|variant| {
+            let fields = (variant.get_fields())
+                .map(|fields| fields.iter().map(|f| f.get_name().clone()).collect());
+            (variant.get_name().clone(), fields)
+        })
+        .collect();
+    let name = node.get_name();
+    let ty = Type::Enum(Enum::new(name.get_name().clone(), variants));
+    set_var(name, ty.clone(), context);
+    Ok(ty)
+}
+
+fn visit_match(node: &MatchNode, position: &Position, context: &Context) -> Result<Type, Error> {
+    let value = visit(node.get_value(), context)?;
+    for arm in node.get_arms() {
+        if !match_pattern(arm.get_pattern(), &value, context)? {
+            continue;
+        }
+        if let Some(guard) = arm.get_guard() {
+            let condition = visit(guard, context)?;
+            if !is_true(&condition, &guard.get_pos(), context)? {
+                continue;
+            }
+        }
+        return visit(arm.get_body(), context);
     }
+    Err(Error::new(
+        ErrorKind::MatchError,
+        &format!("no arm matches {}", value),
+        Some(position.clone()),
+    ))
 }
 
-fn visit_assignment(node: Assignment, context: Context) -> Result<(Type, Context), Error> {
-    let value = visit(*node.get_expr(), context)?;
-    let mut context = value.1;
-    context.stack.set(node.get_name(), value.0.clone());
-    Ok((value.0, context))
+/// whether `value` fits `pattern`, the names in the pattern are assigned
+/// the parts of the value they stand for
+fn match_pattern(pattern: &Pattern, value: &Type, context: &Context) -> Result<bool, Error> {
+    match pattern {
+        Pattern::Wildcard(_) => Ok(true),
+        Pattern::Binding(var) => {
+            set_var(var, value.clone(), context);
+            Ok(true)
+        }
+        Pattern::Literal(literal, _) => Ok(literal == value),
+        Pattern::Tuple(patterns, _) => match items(value) {
+            Some(items) if items.len() == patterns.len() => {
+                for (pattern, item) in patterns.iter().zip(items) {
+                    if !match_pattern(pattern, item, context)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        },
+        Pattern::List(list) => match items(value).and_then(|items| list.split(items)) {
+            Some(parts) => match_list(list, parts, context, &match_pattern),
+            None => Ok(false),
+        },
+        Pattern::Constructor(constructor) => match_constructor(constructor, value, context),
+    }
 }
 
-fn visit_var(node: Ident, context: Context) -> Result<(Type, Context), Error> {
-    let value = context.stack.get(&node);
+/// the items of a tuple or a list
+fn items(value: &Type) -> Option<&[Type]> {
     match value {
-        Some(val) => Ok((val, context)),
+        Type::Tuple(tuple) => Some(tuple.get_items()),
+        Type::List(list) => Some(list.get_items()),
+        _ => None,
+    }
+}
+
+/// matches the parts `split` gave for a list pattern with `matcher`
+fn match_list(
+    list: &ListPattern,
+    (before, rest, after): (&[Type], &[Type], &[Type]),
+    context: &Context,
+    matcher: &dyn Fn(&Pattern, &Type, &Context) -> Result<bool, Error>,
+) -> Result<bool, Error> {
+    let values = before.iter().chain(after);
+    for (pattern, value) in list.get_items().iter().zip(values) {
+        if !matcher(pattern, value, context)? {
+            return Ok(false);
+        }
+    }
+    match list.get_rest() {
+        Some(pattern) => matcher(pattern, &Type::List(List::new(rest.to_vec())), context),
+        None => Ok(true),
+    }
+}
+
+fn visit_destructure(node: &Destructure, context: &Context) -> Result<Type, Error> {
+    let value = visit(node.get_expr(), context)?;
+    destructure(node.get_pattern(), &value, context)?;
+    Ok(value)
+}
+
+/// assigns the parts of `value` to the names in `pattern`, fails if the
+/// value doesn't have the shape of the pattern. It never returns false, the
+/// result is only there to use it like `match_pattern`
+fn destructure(pattern: &Pattern, value: &Type, context: &Context) -> Result<bool, Error> {
+    let pos = pattern.get_pos();
+    let error = |kind, message: String| Error::new(kind, &message, Some(pos.clone()));
+    let not_destructurable = || {
+        error(
+            ErrorKind::TypeError,
+            format!("can't destructure {} into {}", value.type_name(), pattern),
+        )
+    };
+    match pattern {
+        Pattern::Tuple(patterns, _) => {
+            let items = items(value).ok_or_else(not_destructurable)?;
+            if items.len() != patterns.len() {
+                return Err(error(
+                    ErrorKind::ValueError,
+                    format!("expected {} values but got {}", patterns.len(), items.len()),
+                ));
+            }
+            for (pattern, item) in patterns.iter().zip(items) {
+                destructure(pattern, item, context)?;
+            }
+            Ok(true)
+        }
+        Pattern::List(list) => {
+            let items = items(value).ok_or_else(not_destructurable)?;
+            let parts = list.split(items).ok_or_else(|| {
+                error(
+                    ErrorKind::ValueError,
+                    format!("expected {} but got {}", list.describe_arity(), items.len()),
+                )
+            })?;
+            match_list(list, parts, context, &destructure)
+        }
+        pattern if match_pattern(pattern, value, context)? => Ok(true),
+        pattern => Err(error(
+            ErrorKind::MatchError,
+            format!("{} doesn't match {}", value, pattern),
+        )),
+    }
+}
+
+fn visit_tuple(node: &TupleNode, context: &Context) -> Result<Type, Error> {
+    let items = node
+        .get_items()
+        .iter()
+        .map(|item| visit(item, context))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Type::Tuple(Tuple::new(items)))
+}
+
+fn match_constructor(
+    constructor: &Constructor,
+    value: &Type,
+    context: &Context,
+) -> Result<bool, Error> {
+    let pos = constructor.get_pos();
+    let error = |kind, message: String| Error::new(kind, &message, Some(pos.clone()));
+    let var = constructor.get_type();
+    let ty = get_var(var, context).ok_or_else(|| {
+        error(
+            ErrorKind::NameError,
+            format!("{} is not defined", var.get_name()),
+        )
+    })?;
+    let ty = match (ty, constructor.get_variant()) {
+        (ty, None) => ty,
+        (Type::Enum(ty), Some((variant, _))) => ty.get(variant).ok_or_else(|| {
+            error(
+                ErrorKind::NameError,
+                format!("{} has no variant {}", ty.get_name(), variant),
+            )
+        })?,
+        (ty, Some(_)) => {
+            return Err(error(
+                ErrorKind::TypeError,
+                format!("{} has no variants", ty.type_name()),
+            ))
+        }
+    };
+    let args = constructor.get_args();
+    match ty {
+        Type::Struct(ty) => {
+            let object = match value {
+                Type::Object(object) if *object.get_struct() == ty => object,
+                _ => return Ok(false),
+            };
+            let args = match args {
+                Some(args) => args,
+                None => return Ok(true),
+            };
+            let fields = ty.get_fields();
+            if args.len() != fields.len() {
+                let name = match ty.get_owner() {
+                    Some(owner) => format!("{}.{}", owner, ty.get_name()),
+                    None => ty.get_name().to_string(),
+                };
+                return Err(arity_error(&name, fields.len(), args.len(), pos));
+            }
+            for (pattern, field) in args.iter().zip(fields) {
+                let field = object.get(field).unwrap_or_else(Type::none);
+                if !match_pattern(pattern, &field, context)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        Type::Object(variant) if variant.get_struct().get_owner().is_some() => match args {
+            Some(args) => Err(arity_error(&variant.to_string(), 0, args.len(), pos)),
+            None => Ok(*value == Type::Object(variant)),
+        },
+        ty => Err(error(
+            ErrorKind::TypeError,
+            format!("{} is not a struct or a variant", ty),
+        )),
+    }
+}
+
+fn visit_call_node(node: &CallNode, position: &Position, context: &Context) -> Result<Type, Error> {
+    let (callee, args) = node.get_all();
+    let callee = visit(callee, context)?;
+    let values = args
+        .iter()
+        .map(|arg| visit(arg, context))
+        .collect::<Result<Vec<_>, _>>()?;
+    let named = node
+        .get_named()
+        .iter()
+        .map(|(name, arg)| Ok((name.clone(), visit(arg, context)?)))
+        .collect::<Result<Vec<_>, Error>>()?;
+    match callee {
+        Type::Function(function) => call_function(&function, values, named, position, context),
+        Type::Builtin(builtin) => builtin.call(&Args::new(values, named), context, position),
+        Type::Struct(ty) => {
+            let values = bind_arguments(&ty, ty.get_fields(), values, named, position)?;
+            Ok(Type::Object(ty.instantiate(values)))
+        }
+        ty => Err(Error::new(
+            ErrorKind::TypeError,
+            &format!("{} is not callable", ty),
+            Some(position.clone()),
+        )),
+    }
+}
+
+/// runs the body of a user defined function in a new frame
+fn call_function(
+    function: &Function,
+    mut values: Vec<Type>,
+    named: Vec<(Ident, Type)>,
+    position: &Position,
+    context: &Context,
+) -> Result<Type, Error> {
+    if let Some(receiver) = function.get_receiver() {
+        values.insert(0, receiver.clone());
+    }
+    let values = bind_arguments(function, function.get_params(), values, named, position)?;
+    context.run.budget.call(context.depth + 1, position)?;
+    let locals = function.get_locals();
+    let allocation = context
+        .run
+        .budget
+        .allocate(Stack::frame_size(locals.len()), position)?;
+    let stack = Stack::frame(locals, function.get_stack(), allocation);
+    for (index, value) in values.into_iter().enumerate() {
+        stack.borrow_mut().set_slot(Slot::new(0, index), value);
+    }
+    let call_context = Context::new(position.clone(), stack, Some(context.clone()));
+    visit(&function.get_body(), &call_context).map_err(|e| e.or_context(call_context))
+}
+
+/// orders the arguments like the parameters of the function or the fields
+/// of the struct
+fn bind_arguments(
+    function: &impl Display,
+    params: &[Ident],
+    values: Vec<Type>,
+    named: Vec<(Ident, Type)>,
+    position: &Position,
+) -> Result<Vec<Type>, Error> {
+    let error =
+        |message: String| Error::new(ErrorKind::TypeError, &message, Some(position.clone()));
+    if values.len() > params.len() {
+        return Err(error(format!(
+            "{} takes {} arguments but {} were given",
+            function,
+            params.len(),
+            values.len() + named.len()
+        )));
+    }
+    let mut bound: Vec<Option<Type>> = values.into_iter().map(Some).collect();
+    bound.resize(params.len(), None);
+    for (name, value) in named {
+        match params.iter().position(|param| *param == name) {
+            Some(index) if bound[index].is_none() => bound[index] = Some(value),
+            Some(_) => return Err(error(format!("{} got {} more than once", function, name))),
+            None => return Err(error(format!("{} has no parameter {}", function, name))),
+        }
+    }
+    bound
+        .into_iter()
+        .zip(params)
+        .map(|(value, param)| match value {
+            Some(value) => Ok(value),
+            None => Err(error(format!(
+                "{} is missing the argument {}",
+                function, param
+            ))),
+        })
+        .collect()
+}
+
+fn visit_attribute_node(node: &AttributeNode, context: &Context) -> Result<Type, Error> {
+    let value = visit(node.get_value(), context)?;
+    let name = node.get_name();
+    match &value {
+        Type::Module(module) => module.get(name).ok_or_else(|| {
+            Error::new(
+                ErrorKind::NameError,
+                &format!("module {} has no member {}", module.get_name(), name),
+                Some(node.get_pos()),
+            )
+        }),
+        Type::Object(object) => object.get(name).ok_or_else(|| {
+            Error::new(
+                ErrorKind::NameError,
+                &format!("{} has no member {}", value.type_name(), name),
+                Some(node.get_pos()),
+            )
+        }),
+        Type::Enum(ty) => ty.get(name).ok_or_else(|| {
+            Error::new(
+                ErrorKind::NameError,
+                &format!("{} has no variant {}", ty.get_name(), name),
+                Some(node.get_pos()),
+            )
+        }),
+        Type::Struct(ty) => (ty.get_method(name))
+            .map(|method| Type::Function(method.clone()))
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NameError,
+                    &format!("{} has no method {}", ty.get_name(), name),
+                    Some(node.get_pos()),
+                )
+            }),
+        value => builtins::method(value, name).ok_or_else(|| {
+            Error::new(
+                ErrorKind::TypeError,
+                &format!("{} has no member {}", value.type_name(), name),
+                Some(node.get_pos()),
+            )
+        }),
+    }
+}
+
+fn visit_index(node: &IndexNode, position: &Position, context: &Context) -> Result<Type, Error> {
+    let (value, index) = node.get_all();
+    let value = visit(value, context)?;
+    let index = visit(index, context)?;
+    get_item(&value, index, position, context)
+}
+
+fn visit_set_attribute(node: &SetAttributeNode, context: &Context) -> Result<Type, Error> {
+    let attribute = node.get_attribute();
+    let target = visit(attribute.get_value(), context)?;
+    let value = visit(node.get_expr(), context)?;
+    let name = attribute.get_name();
+    match &target {
+        Type::Object(object) if object.get_struct().get_owner().is_some() => Err(Error::new(
+            ErrorKind::TypeError,
+            &format!("can't change the fields of {}", object.get_struct()),
+            Some(attribute.get_pos()),
+        )),
+        Type::Object(object) if object.set(name, value.clone()) => Ok(value),
+        Type::Object(_) => Err(Error::new(
+            ErrorKind::NameError,
+            &format!("{} has no field {}", target.type_name(), name),
+            Some(attribute.get_pos()),
+        )),
+        target => Err(Error::new(
+            ErrorKind::TypeError,
+            &format!("can't set members of {}", target.type_name()),
+            Some(attribute.get_pos()),
+        )),
+    }
+}
+
+fn visit_interpolation(node: &Interpolation, context: &Context) -> Result<Type, Error> {
+    let mut text = String::new();
+    for part in node.get_parts() {
+        match part {
+            Part::Text(value, _) => text.push_str(value),
+            Part::Expr(expr) => {
+                let value = visit(expr, context)?;
+                text.push_str(to_str(&value, &expr.get_pos(), context)?.get_value());
+            }
+        }
+    }
+    Ok(Type::String(Str::new(&text)))
+}
+
+fn visit_import(node: &Import, context: &Context) -> Result<Type, Error> {
+    let module = (context.run.modules).import(node.get_source(), &node.get_pos(), context)?;
+    match node.get_imported() {
+        Imported::Module(var) => set_var(var, Type::Module(module.clone()), context),
+        Imported::Members(vars) => {
+            for var in vars {
+                let value = module.get(var.get_name()).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::ImportError,
+                        &format!("module {} has no member {}", module.get_name(), var),
+                        Some(var.get_pos()),
+                    )
+                })?;
+                set_var(var, value, context);
+            }
+        }
+    }
+    Ok(Type::Module(module))
+}
+
+fn visit_assignment(node: &Assignment, context: &Context) -> Result<Type, Error> {
+    let value = visit(node.get_expr(), context)?;
+    set_var(node.get_var(), value.clone(), context);
+    Ok(value)
+}
+
+fn visit_var(node: &Variable, context: &Context) -> Result<Type, Error> {
+    match get_var(node, context) {
+        Some(val) => Ok(val),
         None => Err(Error::new(
             ErrorKind::NameError,
-            &*format!("{} is not defined", node.get()),
+            &format!("{} is not defined", node.get_name()),
             Some(context.get_position()),
         )),
     }
 }
 
-fn visit_if_node(node: IfNode, context: Context) -> Result<(Type, Context), Error> {
-    for (condition, expr) in node.get_cases().into_iter() {
-        let condition_value = visit(condition, context.clone())?;
-        if condition_value.0.as_conversion().__bool__()? {
+fn visit_if_node(node: &IfNode, context: &Context) -> Result<Type, Error> {
+    for (condition, expr) in node.get_cases() {
+        let condition_value = visit(condition, context)?;
+        if is_true(&condition_value, &condition.get_pos(), context)? {
             return visit(expr, context);
         }
     }
-    if let Some(else_case) = *node.get_else_case() {
+    if let Some(else_case) = node.get_else_case() {
         visit(else_case, context)
     } else {
-        Ok((Type::none(), context))
+        Ok(Type::none())
     }
 }
 
-fn visit_for_node(node: ForNode, context: Context) -> Result<(Type, Context), Error> {
+fn visit_for_node(node: &ForNode, context: &Context) -> Result<Type, Error> {
     // TODO user can define step
-    let step = 1;
+    let step = Type::Integer(Integer::new(1));
     let (var_name, start, end, body) = node.get_all();
-    let (start, context) = visit(start, context)?;
-    let (end, mut context) = visit(end, context)?;
-    context.stack.set(
-        var_name.clone(),
-        start.as_operators().sub(Type::Integer(Integer::new(1)))?,
-    );
+    let start = visit(start, context)?;
+    let end = match end {
+        Some(end) => visit(end, context)?,
+        None => return visit_for_each(var_name, start, body, context),
+    };
+    set_var(var_name, start.as_operators().sub(step.clone())?, context);
     let mut ty = Type::none();
-    while end.cmp(
-        context
-            .stack
-            .get(&var_name)
+    loop {
+        let next = get_var(var_name, context)
             .unwrap()
             .as_operators()
-            .add(Type::Integer(Integer::new(step)))?,
-    )? == CmpResult::Greater
-    {
-        context.stack.set(
-            var_name.clone(),
-            context
-                .stack
-                .get(&var_name)
-                .unwrap()
-                .as_operators()
-                .add(Type::Integer(Integer::new(step)))?,
-        );
-        let visit_res = visit(body.clone(), context)?;
-        ty = visit_res.0;
-        context = visit_res.1;
+            .add(step.clone())?;
+        if end.cmp(next.clone())? != CmpResult::Greater {
+            break Ok(ty);
+        }
+        set_var(var_name, next, context);
+        ty = visit(body, context)?;
+        repeat(context);
     }
-    Ok((ty, context))
 }
 
-fn visit_while_node(node: WhileNode, context: Context) -> Result<(Type, Context), Error> {
+/// tells the hook that a loop starts another iteration
+fn repeat(context: &Context) {
+    if let Some(hook) = &context.run.hook {
+        hook.repeat(context);
+    }
+}
+
+/// runs the body for every item of a list or tuple or every char of a string
+fn visit_for_each(
+    var_name: &Variable,
+    iterable: Type,
+    body: &Node,
+    context: &Context,
+) -> Result<Type, Error> {
+    let items: Vec<Type> = match iterable {
+        Type::List(list) => list.get_items().to_vec(),
+        Type::Tuple(tuple) => tuple.get_items().to_vec(),
+        Type::String(string) => string
+            .get_value()
+            .chars()
+            .map(|c| Type::String(Str::new(c.encode_utf8(&mut [0; 4]))))
+            .collect(),
+        // objects with `__len__` and `__getitem__` give their items one by one
+        Type::Object(_) => {
+            let pos = var_name.get_pos();
+            let mut ty = Type::none();
+            for index in 0..length(&iterable, &pos, context)? {
+                let index = Type::Integer(Integer::new(index));
+                set_var(
+                    var_name,
+                    get_item(&iterable, index, &pos, context)?,
+                    context,
+                );
+                ty = visit(body, context)?;
+                repeat(context);
+            }
+            return Ok(ty);
+        }
+        ty => {
+            return Err(Error::new(
+                ErrorKind::TypeError,
+                &format!("can't iterate over {}", ty.type_name()),
+                Some(context.get_position()),
+            ))
+        }
+    };
+    let mut ty = Type::none();
+    for item in items {
+        set_var(var_name, item, context);
+        ty = visit(body, context)?;
+        repeat(context);
+    }
+    Ok(ty)
+}
+
+fn visit_while_node(node: &WhileNode, context: &Context) -> Result<Type, Error> {
     let (cond, body) = node.get_all();
-    let mut context = context;
     let mut ty = Type::none();
     loop {
-        let res = visit(cond.clone(), context)?;
-        context = res.1;
-        if !res.0.as_conversion().__bool__()? {
-            break Ok((ty, context));
+        if !is_true(&visit(cond, context)?, &cond.get_pos(), context)? {
+            break Ok(ty);
         }
-        let res = visit(body.clone(), context)?;
-        ty = res.0;
-        context = res.1;
+        ty = visit(body, context)?;
+        repeat(context);
     }
 }
 
+/// the method of a user defined type which implements the operation and the
+/// one of the right operand which is tried if the left one doesn't implement it
+fn operator_methods(operation: &Token) -> Option<(&'static str, &'static str)> {
+    Some(match operation {
+        Token::Plus => ("__add__", "__radd__"),
+        Token::Minus => ("__sub__", "__rsub__"),
+        Token::Star => ("__mul__", "__rmul__"),
+        Token::Slash => ("__div__", "__rdiv__"),
+        Token::DoubleEqual => ("__eq__", "__eq__"),
+        Token::NonEqual => ("__ne__", "__ne__"),
+        Token::LessThan => ("__lt__", "__gt__"),
+        Token::GreaterThan => ("__gt__", "__lt__"),
+        Token::LessThanEq => ("__le__", "__ge__"),
+        Token::GreaterThanEq => ("__ge__", "__le__"),
+        _ => return None,
+    })
+}
+
+/// calls the method `name` of an object, None if the value isn't an object
+/// or its struct doesn't define the method
+fn call_method(
+    value: &Type,
+    name: &str,
+    args: Vec<Type>,
+    position: &Position,
+    context: &Context,
+) -> Option<Result<Type, Error>> {
+    let object = match value {
+        Type::Object(object) => object,
+        _ => return None,
+    };
+    let method = (object.get_struct()).get_method(&Ident::new(name))?;
+    let method = method.clone().bind(value.clone());
+    Some(call_function(&method, args, Vec::new(), position, context))
+}
+
+/// calls the operator method `name` of `value`, without `__ne__` objects are
+/// unequal if they aren't equal
+fn call_operator(
+    value: &Type,
+    name: &str,
+    other: Type,
+    position: &Position,
+    context: &Context,
+) -> Option<Result<Type, Error>> {
+    match call_method(value, name, vec![other.clone()], position, context) {
+        None if name == "__ne__" => {
+            let equal = call_method(value, "__eq__", vec![other], position, context)?;
+            Some(equal.and_then(|equal| Ok((!is_true(&equal, position, context)?).into())))
+        }
+        res => res,
+    }
+}
+
+thread_local! {
+    // the methods every condition on an object looks for
+    static BOOL: Ident = Ident::new("__bool__");
+    static LEN: Ident = Ident::new("__len__");
+}
+
+/// whether the value counts as true, objects decide with `__bool__` or else
+/// with `__len__`
+pub fn is_true(value: &Type, position: &Position, context: &Context) -> Result<bool, Error> {
+    let ty = match value {
+        Type::Object(object) => object.get_struct(),
+        value => return value.as_conversion().__bool__(),
+    };
+    if let Some(method) = BOOL.with(|name| ty.get_method(name).cloned()) {
+        let method = method.bind(value.clone());
+        let res = call_function(&method, Vec::new(), Vec::new(), position, context)?;
+        return res.as_conversion().__bool__();
+    }
+    if LEN.with(|name| ty.get_method(name).is_some()) {
+        return Ok(length(value, position, context)? != 0);
+    }
+    value.as_conversion().__bool__()
+}
+
+/// the value as a string, objects can change it with `__str__`
+pub fn to_str(value: &Type, position: &Position, context: &Context) -> Result<Str, Error> {
+    match call_method(value, "__str__", Vec::new(), position, context) {
+        Some(res) => match res? {
+            Type::String(text) => Ok(text),
+            ty => Err(Error::new(
+                ErrorKind::TypeError,
+                &format!("__str__ returned {} instead of str", ty.type_name()),
+                Some(position.clone()),
+            )),
+        },
+        None => value.as_conversion().__str__(),
+    }
+}
+
+/// the number of items of a list or tuple, the chars of a string or what the
+/// `__len__` method of an object returns
+pub fn length(value: &Type, position: &Position, context: &Context) -> Result<i64, Error> {
+    let error =
+        |message: String| Error::new(ErrorKind::TypeError, &message, Some(position.clone()));
+    match value {
+        Type::String(text) => Ok(text.get_value().chars().count() as i64),
+        Type::List(list) => Ok(list.get_items().len() as i64),
+        Type::Tuple(tuple) => Ok(tuple.get_items().len() as i64),
+        value => match call_method(value, "__len__", Vec::new(), position, context) {
+            Some(res) => match res? {
+                Type::Integer(len) if len.get_value() >= 0 => Ok(len.get_value()),
+                ty => Err(error(format!(
+                    "__len__ returned {} instead of a positive int",
+                    ty
+                ))),
+            },
+            None => Err(error(format!("{} has no length", value.type_name()))),
+        },
+    }
+}
+
+/// the item of a list or tuple or the char of a string at `index`, objects can be
+/// indexed with `__getitem__`
+pub fn get_item(
+    value: &Type,
+    index: Type,
+    position: &Position,
+    context: &Context,
+) -> Result<Type, Error> {
+    if !matches!(value, Type::List(_) | Type::Tuple(_) | Type::String(_)) {
+        return call_method(value, "__getitem__", vec![index], position, context).unwrap_or_else(
+            || {
+                Err(Error::new(
+                    ErrorKind::TypeError,
+                    &format!("{} can't be indexed", value.type_name()),
+                    Some(position.clone()),
+                ))
+            },
+        );
+    }
+    let index = match index {
+        Type::Integer(index) => index.get_value(),
+        ty => {
+            return Err(Error::new(
+                ErrorKind::TypeError,
+                &format!("indices must be int, not {}", ty.type_name()),
+                Some(position.clone()),
+            ))
+        }
+    };
+    let item = usize::try_from(index).ok().and_then(|i| match value {
+        Type::List(list) => list.get_items().get(i).cloned(),
+        Type::Tuple(tuple) => tuple.get_items().get(i).cloned(),
+        Type::String(text) => (text.get_value().chars().nth(i))
+            .map(|c| Type::String(Str::new(c.encode_utf8(&mut [0; 4])))),
+        _ => None,
+    });
+    item.ok_or_else(|| {
+        let len = match value {
+            Type::List(list) => list.get_items().len(),
+            Type::Tuple(tuple) => tuple.get_items().len(),
+            Type::String(text) => text.get_value().chars().count(),
+            _ => 0,
+        };
+        Error::new(
+            ErrorKind::ValueError,
+            &format!(
+                "index {} is out of range for a {} of length {}",
+                index,
+                value.type_name(),
+                len
+            ),
+            Some(position.clone()),
+        )
+    })
+}
+
 // TODO improve position marking
 fn visit_binary_operation(
-    node: BinaryOperationNode,
-    context: Context,
-) -> Result<(Type, Context), Error> {
-    let (left_ty, left_ctx) = visit(node.get_left(), context.clone())?;
-    let (right_ty, right_ctx) = visit(node.get_right(), context)?;
-    let ctx = left_ctx.combine(right_ctx);
-    let full = match node.get_operation() {
-        Token::Plus => left_ty.as_operators().add(right_ty),
-        Token::Minus => left_ty.as_operators().sub(right_ty),
-        Token::Star => left_ty.as_operators().mul(right_ty),
-        Token::Slash => left_ty.as_operators().div(right_ty),
-        Token::DoubleEqual => left_ty.as_operators().eq(right_ty),
-        Token::NonEqual => left_ty.as_operators().neq(right_ty),
-        Token::LessThan => left_ty.as_operators().lt(right_ty),
-        Token::GreaterThan => left_ty.as_operators().gt(right_ty),
-        Token::LessThanEq => left_ty.as_operators().lte(right_ty),
-        Token::GreaterThanEq => left_ty.as_operators().gte(right_ty),
-        Token::DoubleAnd => left_ty.as_operators().and(right_ty),
-        Token::DoubleOr => left_ty.as_operators().or(right_ty),
+    node: &BinaryOperationNode,
+    position: &Position,
+    context: &Context,
+) -> Result<Type, Error> {
+    let left_ty = visit(node.get_left(), context)?;
+    let right_ty = visit(node.get_right(), context)?;
+    let operation = node.get_operation();
+    let methods = operator_methods(operation);
+    if let Some((method, _)) = methods {
+        if let Some(res) = call_operator(&left_ty, method, right_ty.clone(), position, context) {
+            return res;
+        }
+    }
+    let is_object = |ty: &Type| matches!(ty, Type::Object(_));
+    if matches!(operation, Token::DoubleAnd | Token::DoubleOr)
+        && (is_object(&left_ty) || is_object(&right_ty))
+    {
+        let left = is_true(&left_ty, position, context)?;
+        let right = is_true(&right_ty, position, context)?;
+        let value = match operation {
+            Token::DoubleAnd => left && right,
+            _ => left || right,
+        };
+        return Ok(value.into());
+    }
+    let same = std::mem::discriminant(&left_ty) == std::mem::discriminant(&right_ty);
+    if let (Token::Plus, true, Some(left), Some(right)) =
+        (operation, same, left_ty.size(), right_ty.size())
+    {
+        // check before copying both operands into the result
+        context.run.budget.reserve(left + right, position)?;
+    }
+    let right = right_ty.clone();
+    let full = match operation {
+        Token::Plus => left_ty.as_operators().add(right),
+        Token::Minus => left_ty.as_operators().sub(right),
+        Token::Star => left_ty.as_operators().mul(right),
+        Token::Slash => left_ty.as_operators().div(right),
+        Token::DoubleEqual => left_ty.as_operators().eq(right),
+        Token::NonEqual => left_ty.as_operators().neq(right),
+        Token::LessThan => left_ty.as_operators().lt(right),
+        Token::GreaterThan => left_ty.as_operators().gt(right),
+        Token::LessThanEq => left_ty.as_operators().lte(right),
+        Token::GreaterThanEq => left_ty.as_operators().gte(right),
+        Token::DoubleAnd => left_ty.as_operators().and(right),
+        Token::DoubleOr => left_ty.as_operators().or(right),
         t => Err(Error::new(
             ErrorKind::Undefined,
-            &*format!("can't operate on token {}", t),
-            Some(ctx.get_position()),
+            &format!("can't operate on token {}", t),
+            Some(position.clone()),
         )),
     };
-    // fill position of full
-    match full {
-        Ok(f) => Ok((f, ctx)),
-        Err(e) => Err(e.with_context(ctx)),
+    // the right operand may implement what the left one doesn't
+    if let (Err(e), Some((_, reflected))) = (&full, methods) {
+        if *e.kind() == ErrorKind::Unimplemented && (is_object(&left_ty) || is_object(&right_ty)) {
+            let message = format!(
+                "unsupported operand types for {}: {} and {}",
+                operation,
+                left_ty.type_name(),
+                right_ty.type_name()
+            );
+            return call_operator(&right_ty, reflected, left_ty, position, context).unwrap_or_else(
+                || {
+                    let error = Error::new(ErrorKind::TypeError, &message, Some(position.clone()));
+                    Err(error.or_context(context.clone()))
+                },
+            );
+        }
     }
+    // the operations don't know where they happened
+    full.map_err(|e| e.or_position(position).or_context(context.clone()))
 }
 
 fn visit_unary_operation(
-    node: UnaryOperationNode,
-    _pos: Position,
-    context: Context,
-) -> Result<(Type, Context), Error> {
-    let (ty, ctx) = visit(node.get_node(), context.clone())?;
-    let new_pos = Position::new(
-        ctx.pos.index - 1,
-        ctx.pos.filename,
-        ctx.pos.line,
-        ctx.pos.column,
-        ctx.pos.len + 1,
-    );
-    let new_ctx = Context::new(new_pos, context.stack.clone(), Some(context));
-    Ok((
-        match node.get_operation() {
-            UnaryOperation::Plus => ty,
-            UnaryOperation::Minus => ty.as_operators().neg()?,
-            UnaryOperation::Not => ty.as_operators().not()?,
+    node: &UnaryOperationNode,
+    position: &Position,
+    context: &Context,
+) -> Result<Type, Error> {
+    let ty = visit(node.get_node(), context)?;
+    match node.get_operation() {
+        UnaryOperation::Plus => Ok(ty),
+        UnaryOperation::Minus => match call_method(&ty, "__neg__", Vec::new(), position, context) {
+            Some(res) => return res,
+            None => ty.as_operators().neg(),
         },
-        new_ctx,
-    ))
+        UnaryOperation::Not if matches!(ty, Type::Object(_)) => {
+            return Ok((!is_true(&ty, position, context)?).into())
+        }
+        UnaryOperation::Not => ty.as_operators().not(),
+    }
+    .map_err(|e| e.or_position(position).or_context(context.clone()))
 }
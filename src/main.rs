@@ -1,36 +1,262 @@
-#![feature(try_trait)]
-
-use std::io::Write;
-
-mod interpreter;
-
-macro_rules! input {
-    () => {{
-        let mut buffer = String::new();
-        std::io::stdin()
-            .read_line(&mut buffer)
-            .expect("Error reading input");
-        buffer.trim().to_string()
-    }};
-    ($x:expr) => {{
-        print!("{}", $x);
-        std::io::stdout().flush().expect("Error writing output");
-        input!()
-    }};
+mod debug;
+mod repl;
+
+use phoenix::interpreter;
+use phoenix::interpreter::capabilities::Capabilities;
+use phoenix::interpreter::dump;
+use phoenix::interpreter::limits::Limits;
+use phoenix::interpreter::ErrorKind;
+use std::io::Read;
+use std::process::exit;
+
+const USAGE: &str = "\
+Usage: phoenix [OPTIONS] [FILE]
+       phoenix fmt [--check] [FILE...]
+       phoenix check [--strict] [FILE...]
+       phoenix debug [-b [FILE:]LINE]... FILE
+       phoenix debug --dap
+
+Runs FILE or starts the REPL if no file is given. FILE may be - to read from stdin.
+
+Options:
+  --dump-tokens  print the tokens of FILE instead of running it
+  --dump-ast     print the syntax tree of FILE instead of running it
+  --json         print the dump as json
+  --max-steps N  stop the script after evaluating N nodes
+  --max-depth N  allow at most N nested calls
+  --max-memory N allow the stacks, strings, lists and tuples of the script to
+                 use at most N bytes
+  --max-nesting N
+                 allow expressions to be nested N times in the source
+  --path DIR     look for imported files in DIR too, after the directory
+                 of the importing file and before PHOENIX_PATH
+  --sandbox      don't allow the script to access files, environment
+                 variables, the clock or randomness or to exit
+  -h, --help     show this help
+
+fmt formats the files in place or stdin to stdout if no file is given.
+With --check nothing is written, the files which aren't formatted are listed.
+
+check reports type errors in the files or stdin without running them. Names,
+parameters and results may be annotated like `let x: int = 1` and
+`fn f(a: float): float -> a`. With --strict values whose type can't be
+inferred can't be used as operands or called.
+
+debug runs FILE step by step, pausing at its first line and at every
+breakpoint set with -b, in FILE or in an imported one. With --dap it speaks
+the Debug Adapter Protocol on stdio instead, the file is given by the launch
+request.";
+
+#[derive(PartialEq)]
+enum Mode {
+    Run,
+    DumpTokens,
+    DumpAst,
 }
 
-fn main() {
-    let mut stack = interpreter::new_stack();
-    let version = env!("CARGO_PKG_VERSION");
-    println!("Phoenix v{}", version);
-    loop {
-        let text = input!(">");
-        let res = interpreter::run(text, "\"<stdin>\"".to_string(), Some(stack));
-        match res.res {
-            Ok(ty) if ty != interpreter::Type::none() => println!("{}", ty),
-            Err(e) => println!("{}", e),
-            _ => {} // don't show None Results
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    exit(2)
+}
+
+/// parses the value of `option`
+fn number<T: std::str::FromStr>(option: &str, value: Option<String>) -> T {
+    match value.and_then(|value| value.parse().ok()) {
+        Some(number) => number,
+        None => fail(&format!("{} needs a number", option)),
+    }
+}
+
+/// returns the name used in error messages together with the source
+fn read_source(file: &str) -> (String, String) {
+    let res = if file == "-" {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text).map(|_| text)
+    } else {
+        std::fs::read_to_string(file)
+    };
+    match res {
+        Ok(text) if file == "-" => (repl::STDIN.to_string(), text),
+        Ok(text) => (file.to_string(), text),
+        Err(e) => {
+            eprintln!("can't read {}: {}", file, e);
+            exit(1)
+        }
+    }
+}
+
+/// formats the given files, returns the exit code
+fn fmt(args: impl Iterator<Item = String>) -> i32 {
+    let mut check = false;
+    let mut files = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            option if option.starts_with('-') && option != "-" => {
+                fail(&format!("unknown option {}", option))
+            }
+            _ => files.push(arg),
+        }
+    }
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+
+    let mut code = 0;
+    for file in files {
+        let (file_name, text) = read_source(&file);
+        let formatted = match interpreter::formatter::format(&text, file_name.clone()) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprintln!("{}", e);
+                code = 1;
+                continue;
+            }
+        };
+        if check {
+            if formatted != text {
+                println!("{} is not formatted", file_name);
+                code = 1;
+            }
+        } else if file == "-" {
+            print!("{}", formatted);
+        } else if formatted != text {
+            if let Err(e) = std::fs::write(&file, formatted) {
+                eprintln!("can't write {}: {}", file, e);
+                code = 1;
+            }
+        }
+    }
+    code
+}
+
+/// checks the types of the given files, returns the exit code
+fn check(args: impl Iterator<Item = String>) -> i32 {
+    let mut strict = false;
+    let mut files = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--strict" => strict = true,
+            option if option.starts_with('-') && option != "-" => {
+                fail(&format!("unknown option {}", option))
+            }
+            _ => files.push(arg),
+        }
+    }
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+
+    let mut code = 0;
+    for file in files {
+        let (file_name, text) = read_source(&file);
+        let errors = match interpreter::parse(&text, file_name) {
+            Ok(ast) => interpreter::check::check(&ast, strict),
+            Err(e) => vec![e],
         };
-        stack = res.stack;
+        for e in &errors {
+            match e.position() {
+                Some(pos) => println!(
+                    "{}, line {}, column {}: {:?}: {}",
+                    pos.filename(),
+                    pos.line() + 1,
+                    pos.column(),
+                    e.kind(),
+                    e.message()
+                ),
+                None => println!("{:?}: {}", e.kind(), e.message()),
+            }
+        }
+        if !errors.is_empty() {
+            code = 1;
+        }
+    }
+    code
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1).peekable();
+    match args.peek().map(String::as_str) {
+        Some("fmt") => {
+            args.next();
+            exit(fmt(args));
+        }
+        Some("check") => {
+            args.next();
+            exit(check(args));
+        }
+        Some("debug") => {
+            args.next();
+            exit(debug::run(args));
+        }
+        _ => {}
+    }
+
+    let mut mode = Mode::Run;
+    let mut format = dump::Format::Text;
+    let mut file = None;
+    let mut limits = Limits::new();
+    let mut capabilities = Capabilities::all();
+    let mut search_paths = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "--dump-tokens" => mode = Mode::DumpTokens,
+            "--dump-ast" => mode = Mode::DumpAst,
+            "--json" => format = dump::Format::Json,
+            "--max-steps" => limits = limits.with_steps(number(&arg, args.next())),
+            "--max-depth" => limits = limits.with_depth(number(&arg, args.next())),
+            "--max-memory" => limits = limits.with_memory(number(&arg, args.next())),
+            "--max-nesting" => limits = limits.with_nesting(number(&arg, args.next())),
+            "--path" => match args.next() {
+                Some(dir) => search_paths.push(dir.into()),
+                None => fail("--path needs a directory"),
+            },
+            "--sandbox" => capabilities = Capabilities::none(),
+            option if option.starts_with('-') && option != "-" => {
+                fail(&format!("unknown option {}", option))
+            }
+            _ if file.is_some() => fail("only one file can be given"),
+            _ => file = Some(arg),
+        }
+    }
+    if let Some(paths) = std::env::var_os("PHOENIX_PATH") {
+        search_paths.extend(std::env::split_paths(&paths));
+    }
+    if format == dump::Format::Json && mode == Mode::Run {
+        fail("--json needs --dump-tokens or --dump-ast");
+    }
+
+    let options = interpreter::Options::new()
+        .with_limits(limits)
+        .with_capabilities(capabilities)
+        .with_search_paths(search_paths);
+
+    let file = match (file, &mode) {
+        (Some(file), _) => file,
+        (None, Mode::Run) => return repl::run(options),
+        (None, _) => "-".to_string(),
+    };
+    let (file_name, text) = read_source(&file);
+    let res = match mode {
+        Mode::Run => interpreter::run_with(text, file_name, None, options)
+            .res
+            .map(|ty| (ty != interpreter::Type::none()).then(|| ty.to_string())),
+        Mode::DumpTokens => dump::tokens(&text, file_name, format).map(Some),
+        Mode::DumpAst => dump::ast(&text, file_name, format).map(Some),
+    };
+    match res {
+        Ok(Some(out)) => println!("{}", out.trim_end()),
+        Ok(None) => {}
+        Err(e) => match e.kind() {
+            ErrorKind::Exit(code) => exit(*code as i32),
+            _ => {
+                eprintln!("{}", e);
+                exit(1)
+            }
+        },
     }
 }
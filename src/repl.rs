@@ -0,0 +1,171 @@
+use phoenix::interpreter;
+use phoenix::interpreter::{ErrorKind, Options, StackRef, Type};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::path::PathBuf;
+
+const HISTORY_FILE: &str = ".phoenix_history";
+pub const STDIN: &str = "\"<stdin>\"";
+
+const HELP: &str = "\
+Enter an expression to evaluate it. Unfinished input continues on the next line.
+
+Commands:
+  :help        show this help
+  :exit        leave the REPL (or press Ctrl-D)
+  :vars        list all defined variables
+  :reset       forget all variables
+  :load FILE   run FILE, keeping its variables
+  :type EXPR   show the type of EXPR without running it
+  :ast EXPR    show the syntax tree of EXPR without running it";
+
+fn history_path() -> Option<PathBuf> {
+    std::env::home_dir().map(|home| home.join(HISTORY_FILE))
+}
+
+/// prints the result, returns the exit code if the script called `exit`
+fn print_result(res: Result<Type, interpreter::Error>) -> Option<i32> {
+    match res {
+        Ok(ty) if ty != Type::none() => println!("{}", ty),
+        Err(e) => match e.kind() {
+            ErrorKind::Exit(code) => return Some(*code as i32),
+            _ => println!("{}", e),
+        },
+        _ => {} // don't show None Results
+    };
+    None
+}
+
+/// handles a line starting with `:`, returns the exit code if the REPL
+/// should exit
+fn run_command(line: &str, stack: &mut StackRef, options: &Options) -> Option<i32> {
+    let (command, arg) = match line.split_once(char::is_whitespace) {
+        Some((command, arg)) => (command, arg.trim()),
+        None => (line, ""),
+    };
+    match command {
+        ":help" => println!("{}", HELP),
+        ":exit" | ":quit" => return Some(0),
+        ":vars" => {
+            for (name, value) in stack.borrow().variables() {
+                println!("{}: {} = {}", name, value.type_name(), value);
+            }
+        }
+        ":reset" => *stack = interpreter::new_stack(),
+        ":load" if !arg.is_empty() => match std::fs::read_to_string(arg) {
+            Ok(text) => {
+                let res = interpreter::run_with(
+                    text,
+                    arg.to_string(),
+                    Some(stack.clone()),
+                    options.clone(),
+                );
+                return print_result(res.res);
+            }
+            Err(e) => println!("can't read {}: {}", arg, e),
+        },
+        // the expression isn't run, so it has no effects
+        ":type" if !arg.is_empty() => match interpreter::parse(arg, STDIN.to_string()) {
+            Ok(node) => {
+                let variables = stack.borrow().variables();
+                match interpreter::check::type_with(&node, &variables) {
+                    Ok(Some(ty)) => println!("{}", ty),
+                    Ok(None) => println!("the type of {} can't be told without running it", arg),
+                    Err(e) => println!("{}", e),
+                }
+            }
+            Err(e) => println!("{}", e),
+        },
+        ":ast" if !arg.is_empty() => match interpreter::parse(arg, STDIN.to_string()) {
+            Ok(node) => println!("{}", node),
+            Err(e) => println!("{}", e),
+        },
+        ":load" | ":type" | ":ast" => println!("{} needs an argument, see :help", command),
+        _ => println!("unknown command {}, see :help", command),
+    }
+    None
+}
+
+/// reads expressions from the terminal and evaluates them with `options`
+/// until the user exits
+pub fn run(options: Options) {
+    let mut editor = DefaultEditor::new().expect("Error initializing the terminal");
+    let history = history_path();
+    if let Some(history) = &history {
+        // there is no history on the first start
+        let _ = editor.load_history(history);
+    }
+
+    let mut stack = interpreter::new_stack();
+    let version = env!("CARGO_PKG_VERSION");
+    println!("Phoenix v{}", version);
+    println!("Type :help for more information");
+    // input of a statement spanning multiple lines
+    let mut buffer = String::new();
+    let mut code = 0;
+    loop {
+        let prompt = if buffer.is_empty() { ">" } else { "..." };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            // Ctrl-C discards the current statement
+            Err(ReadlineError::Interrupted) => {
+                buffer.clear();
+                continue;
+            }
+            // Ctrl-D discards the current statement or exits if there is none
+            Err(ReadlineError::Eof) if !buffer.is_empty() => {
+                buffer.clear();
+                println!();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                println!("Error reading input: {}", e);
+                break;
+            }
+        };
+        if buffer.is_empty() && line.trim().is_empty() {
+            continue;
+        }
+        if buffer.is_empty() && line.trim_start().starts_with(':') {
+            let _ = editor.add_history_entry(line.trim());
+            if let Some(exit) = run_command(line.trim(), &mut stack, &options) {
+                code = exit;
+                break;
+            }
+            continue;
+        }
+        buffer.push_str(&line);
+        buffer.push('\n');
+
+        let res = interpreter::run_with(
+            buffer.clone(),
+            STDIN.to_string(),
+            Some(stack),
+            options.clone(),
+        );
+        stack = res.stack;
+        match res.res {
+            // the statement isn't finished yet, so wait for the next line
+            Err(e) if *e.kind() == ErrorKind::EndOfFile => continue,
+            res => {
+                let exit = print_result(res);
+                let _ = editor.add_history_entry(buffer.trim_end());
+                buffer.clear();
+                if let Some(exit) = exit {
+                    code = exit;
+                    break;
+                }
+            }
+        };
+    }
+
+    if let Some(history) = &history {
+        if let Err(e) = editor.save_history(history) {
+            println!("Error saving history: {}", e);
+        }
+    }
+    if code != 0 {
+        std::process::exit(code);
+    }
+}
@@ -0,0 +1,81 @@
+use phoenix::interpreter::analysis::{analyze, DefinitionKind, Severity};
+
+/// the byte offset of the `n`th occurrence of `part` in `text`
+fn offset(text: &str, part: &str, n: usize) -> usize {
+    text.match_indices(part).nth(n).unwrap().0
+}
+
+#[test]
+fn diagnostics_cover_syntax_names_and_types() {
+    let analysis = analyze("let x = (1", "test".to_string());
+    assert!(!analysis.is_parsed());
+    assert_eq!(analysis.get_diagnostics().len(), 1);
+    assert_eq!(
+        analysis.get_diagnostics()[0].get_severity(),
+        Severity::Error
+    );
+
+    let analysis = analyze("print(y)\nlet z = 1 + \"a\"", "test".to_string());
+    assert!(analysis.is_parsed());
+    let diagnostics: Vec<(Severity, &str, u64)> = (analysis.get_diagnostics().iter())
+        .map(|d| (d.get_severity(), d.get_message(), d.get_pos().line()))
+        .collect();
+    assert_eq!(
+        diagnostics,
+        vec![
+            (Severity::Warning, "y is not defined", 0),
+            (
+                Severity::Error,
+                "TypeError: unsupported operand types for +: int and str",
+                1
+            ),
+        ]
+    );
+    // builtins and the prelude are always defined
+    let analysis = analyze("print(sum(range(0, 3)), math.pi)", "test".to_string());
+    assert!(analysis.get_diagnostics().is_empty());
+}
+
+#[test]
+fn uses_find_the_definition_they_refer_to() {
+    let text = "let x = 1\nfn f(x) -> x + 1\nlet x = \"a\"\nprint(f(x))";
+    let analysis = analyze(text, "test".to_string());
+    // the parameter shadows the global inside the function
+    let inner = analysis.definition_at(offset(text, "x", 2)).unwrap();
+    assert_eq!(inner.get_kind(), DefinitionKind::Parameter);
+    assert_eq!(inner.get_pos().index() as usize, offset(text, "x", 1));
+    // the last assignment before the use counts
+    let outer = analysis.definition_at(offset(text, "x", 4)).unwrap();
+    assert_eq!(outer.get_pos().index() as usize, offset(text, "x", 3));
+    assert_eq!(outer.get_detail(), "let x: str");
+    let function = analysis.definition_at(offset(text, "f(x)", 1)).unwrap();
+    assert_eq!(function.get_detail(), "fn f(x) -> unknown");
+    assert!(analysis.definition_at(offset(text, "print", 0)).is_none());
+}
+
+#[test]
+fn names_and_symbols_follow_the_scopes() {
+    let text = "struct P {\n    x\n}\nfn f(a) -> {\n    let b = a\n    b\n}\nlet c = P(1)";
+    let analysis = analyze(text, "test".to_string());
+    let names = |index| -> Vec<&str> {
+        (analysis.names_at(index).into_iter())
+            .map(|definition| definition.get_name())
+            .collect()
+    };
+    assert_eq!(
+        names(offset(text, "b\n}", 0)),
+        vec!["a", "b", "P", "f", "c"]
+    );
+    assert_eq!(names(text.len()), vec!["P", "f", "c"]);
+    let symbols: Vec<(&str, DefinitionKind, &str)> = (analysis.symbols().into_iter())
+        .map(|d| (d.get_name(), d.get_kind(), d.get_detail()))
+        .collect();
+    assert_eq!(
+        symbols,
+        vec![
+            ("P", DefinitionKind::Struct, "struct P { x }"),
+            ("f", DefinitionKind::Function, "fn f(a) -> unknown"),
+            ("c", DefinitionKind::Variable, "let c: P"),
+        ]
+    );
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use common::run_with_options;
+use phoenix::interpreter::capabilities::Capabilities;
+use phoenix::interpreter::console::Buffer;
+use phoenix::interpreter::{new_stack, run_with, ErrorKind, Options};
+use std::rc::Rc;
+
+/// runs `text` with only `capabilities`, returns the kind of the error
+fn run(text: &str, capabilities: Capabilities) -> Result<(), ErrorKind> {
+    run_with_options(text, Options::new().with_capabilities(capabilities)).0
+}
+
+#[test]
+fn missing_capabilities_raise_permission_errors() {
+    for (text, allowed) in [
+        ("time()", Capabilities::none().with_clock()),
+        ("random()", Capabilities::none().with_random()),
+        ("exit(0)", Capabilities::none().with_exit()),
+        (
+            "import \"/etc/hostname\" as h",
+            Capabilities::none().with_read("/etc"),
+        ),
+    ] {
+        assert_eq!(
+            run(text, Capabilities::none()),
+            Err(ErrorKind::PermissionError),
+            "{}",
+            text
+        );
+        assert_ne!(
+            run(text, allowed),
+            Err(ErrorKind::PermissionError),
+            "{}",
+            text
+        );
+    }
+    assert_eq!(run("print(1 + 2)", Capabilities::none()), Ok(()));
+}
+
+#[test]
+fn exit_codes_fit_in_the_status_of_a_process() {
+    assert_eq!(run("exit()", Capabilities::all()), Err(ErrorKind::Exit(0)));
+    assert_eq!(
+        run("exit(255)", Capabilities::all()),
+        Err(ErrorKind::Exit(255))
+    );
+    for text in ["exit(256)", "exit(4294967296)", "exit(-1)"] {
+        assert_eq!(
+            run(text, Capabilities::all()),
+            Err(ErrorKind::ValueError),
+            "{}",
+            text
+        );
+    }
+}
+
+#[test]
+fn redefined_builtins_stay_redefined_in_later_runs() {
+    let stack = new_stack();
+    for text in ["let print = 5", "print"] {
+        let options = Options::new().with_console(Rc::new(Buffer::new()));
+        let res = run_with(
+            text.to_string(),
+            "test".to_string(),
+            Some(stack.clone()),
+            options,
+        );
+        assert_eq!(res.res.unwrap().to_string(), "5");
+    }
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use common::run;
+use phoenix::interpreter::analysis::analyze;
+use phoenix::interpreter::check::check;
+use phoenix::interpreter::{formatter, parse, ErrorKind};
+
+/// the messages of the type errors in `text` with the lines they are on
+fn errors(text: &str, strict: bool) -> Vec<(u64, String)> {
+    let ast = match parse(text, "test".to_string()) {
+        Ok(ast) => ast,
+        Err(e) => panic!("{} can't be parsed: {}", text, e),
+    };
+    check(&ast, strict)
+        .iter()
+        .map(|e| {
+            assert_eq!(*e.kind(), ErrorKind::TypeError);
+            (e.position().unwrap().line(), e.message().to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn annotations_are_checked() {
+    let text = "let x: int = \"a\"
+fn add(a: int, b: int): int -> a + b
+add(1, 2.5)
+fn name(n: int): str -> n
+let n = 5
+n(1)
+add(1)";
+    assert_eq!(
+        errors(text, false),
+        vec![
+            (0, "x should be int but is str".to_string()),
+            (
+                2,
+                "argument b of <function add> should be int but is float".to_string()
+            ),
+            (
+                3,
+                "the result of <function name> should be str but is int".to_string()
+            ),
+            (5, "int is not callable".to_string()),
+            (6, "<function add> is missing the argument b".to_string()),
+        ]
+    );
+    // a name keeps its declared type when it gets a new value
+    assert_eq!(
+        errors("let x: float = 1.5\nlet x = 2", false),
+        vec![(1, "x should be float but is int".to_string())]
+    );
+}
+
+#[test]
+fn strict_mode_rejects_values_of_unknown_type() {
+    let mixed = "let a = 1 + 2.5";
+    let message = "unsupported operand types for +: int and float".to_string();
+    assert_eq!(errors(mixed, false), vec![(0, message.clone())]);
+    assert_eq!(errors(mixed, true), vec![(0, message)]);
+
+    let untyped = "fn double(a) -> a * 2\nfn apply(f, a) -> f(a)";
+    assert_eq!(errors(untyped, false), Vec::new());
+    assert_eq!(
+        errors(untyped, true),
+        vec![
+            (0, "can't tell the type of a".to_string()),
+            (1, "can't tell the type of f".to_string()),
+        ]
+    );
+    let typed = "fn double(a: int) -> a * 2\nfn apply(f: function, a: any): any -> f(a)";
+    assert_eq!(errors(typed, true), Vec::new());
+}
+
+#[test]
+fn annotations_are_formatted_and_ignored_when_running() {
+    let text = "let x: int = 3\nfn half(a: float): float -> a / 2.0\nprint(x, half(3.0))\n";
+    assert_eq!(formatter::format(text, "test".to_string()).unwrap(), text);
+
+    let (res, output) = run("let x: str = 1\nprint(x)");
+    assert_eq!(res, Ok(()));
+    assert_eq!(output, "1\n");
+}
+
+#[test]
+fn builtins_are_checked_with_the_signatures_they_declare() {
+    let text = "len(1, 2)
+let x: int = str(1)
+let y: str = \"a\".upper()
+let z: int = \"a\".split()
+\"a\".nope
+let n: float = math.floor(1.5)";
+    assert_eq!(
+        errors(text, false),
+        vec![
+            (
+                0,
+                "<builtin function len> takes 1 arguments but 2 were given".to_string()
+            ),
+            (1, "x should be int but is str".to_string()),
+            (3, "z should be int but is list".to_string()),
+            (4, "str has no member nope".to_string()),
+            (5, "n should be float but is int".to_string()),
+        ]
+    );
+    // the prelude is checked like any other script
+    assert_eq!(errors("let total = sum(list(1, 2))", false), Vec::new());
+}
+
+#[test]
+fn hover_shows_the_inferred_and_annotated_types() {
+    let text = "let x: float = f(1)\nfn f(a: int) -> a * 2\nlet s = str(x)\nfor c in s then {}";
+    let analysis = analyze(text, "test".to_string());
+    let details: Vec<&str> = (analysis.get_definitions().iter())
+        .map(|definition| definition.get_detail())
+        .collect();
+    assert_eq!(
+        details,
+        vec![
+            "let x: float",
+            "fn f(a: int) -> int",
+            "a: int (parameter)",
+            "let s: str",
+            "let c: str",
+        ]
+    );
+}
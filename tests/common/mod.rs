@@ -0,0 +1,32 @@
+// every test file only uses some of the helpers
+#![allow(dead_code)]
+
+use phoenix::interpreter::console::Buffer;
+use phoenix::interpreter::{run_with, Error, ErrorKind, Options, Type};
+use std::rc::Rc;
+
+/// runs `text` as the file `file_name` with `options`, printing to
+/// `console`; returns the result and what was printed
+pub fn run_file(
+    text: &str,
+    file_name: &str,
+    options: Options,
+    console: Buffer,
+) -> (Result<Type, Error>, String) {
+    let console = Rc::new(console);
+    let options = options.with_console(console.clone());
+    let res = run_with(text.to_string(), file_name.to_string(), None, options).res;
+    (res, console.get_output())
+}
+
+/// runs `text` with `options`, returns the kind of the error and what it
+/// printed
+pub fn run_with_options(text: &str, options: Options) -> (Result<(), ErrorKind>, String) {
+    let (res, output) = run_file(text, "test", options, Buffer::new());
+    (res.map(|_| ()).map_err(|e| e.kind().clone()), output)
+}
+
+/// runs `text`, returns the kind of the error and what it printed
+pub fn run(text: &str) -> (Result<(), ErrorKind>, String) {
+    run_with_options(text, Options::new())
+}
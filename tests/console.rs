@@ -0,0 +1,88 @@
+mod common;
+
+use common::{run, run_file};
+use phoenix::interpreter::console::Buffer;
+use phoenix::interpreter::{parse, ErrorKind, Options, Type};
+
+#[test]
+fn print_writes_to_the_console() {
+    let (res, output) =
+        run("print(1, 2.5, \"three\")\nprint(\"a\", \"b\", sep = \", \", end = \"!\")");
+    assert_eq!(res, Ok(()));
+    assert_eq!(output, "1 2.5 three\na, b!");
+}
+
+#[test]
+fn input_reads_from_the_console() {
+    let (res, output) = run_file(
+        "let name = input(\"name? \")\nlet rest = input()\nprint(\"hello \" + name)\nrest",
+        "test",
+        Options::new(),
+        Buffer::new().with_input(["phoenix"]),
+    );
+    assert_eq!(res.ok(), Some(Type::none()));
+    assert_eq!(output, "name? hello phoenix\n");
+}
+
+#[test]
+fn format_fills_the_fields() {
+    let (_, output) =
+        run("print(format(\"{} is {:.2}|{:>4}|{:-<4}|{1}|{{x}}\", \"pi\", math.pi, 7, \"ab\"))");
+    assert_eq!(output, "pi is 3.14|   7|ab--|3.141592653589793|{x}\n");
+}
+
+#[test]
+fn format_rejects_invalid_fields() {
+    for text in [
+        "format(\"{2}\", 1)",
+        "format(\"{} {}\", 1)",
+        "format(\"{:x}\", 1)",
+    ] {
+        assert_eq!(run(text).0, Err(ErrorKind::ValueError), "{}", text);
+    }
+}
+
+#[test]
+fn literal_braces_survive_interpolation_and_format() {
+    let text = "let braces = \"{{x}} {{ }}\"
+print(braces)
+print(format(braces))
+print(format(\"{} {{ {} }}\", braces, \"{}\"))";
+    let (res, output) = run(text);
+    assert!(res.is_ok());
+    assert_eq!(output, "{x} { }\n{x} { }\n{x} { } { {} }\n");
+}
+
+#[test]
+fn string_methods_count_chars() {
+    let (_, output) = run(
+        "let s = \" Héllo, wörld \".trim()\nprint(s.len(), s.find(\"w\"), s.substring(1, 4), s.upper())\nprint(\"-\".join(s.split(\", \")), str(1.5) + \"!\")\nfor c in \"ö1\" then print(c, end = \".\")",
+    );
+    assert_eq!(output, "12 7 éll HÉLLO, WÖRLD\nHéllo-wörld 1.5!\nö.1.");
+}
+
+#[test]
+fn string_index_out_of_range() {
+    let (res, _) = run("\"abc\".substring(1, 4)");
+    assert_eq!(res, Err(ErrorKind::ValueError));
+    let (res, _) = run("\"abc\".size()");
+    assert_eq!(res, Err(ErrorKind::TypeError));
+}
+
+#[test]
+fn strings_interpolate_expressions() {
+    let (_, output) = run(
+        "let name = \"phoenix\"\nlet n = 2\nprint(\"{name.upper()} has {n + 1} {\"item{\"s\"}\"} {{not}} {1.5}\")",
+    );
+    assert_eq!(output, "PHOENIX has 3 items {not} 1.5\n");
+}
+
+#[test]
+fn interpolation_errors_point_into_the_braces() {
+    let error = parse("\"a\nb {1 + } c\"", "test".to_string())
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), &ErrorKind::SyntaxError);
+    let pos = error.position().unwrap();
+    assert_eq!((pos.line(), pos.column()), (1, 7));
+}
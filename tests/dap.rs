@@ -0,0 +1,125 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// `phoenix debug --dap` running in its own process, spoken to over stdio
+struct Client {
+    adapter: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    seq: u64,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut adapter = Command::new(env!("CARGO_BIN_EXE_phoenix"))
+            .args(["debug", "--dap"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = adapter.stdin.take().unwrap();
+        let stdout = BufReader::new(adapter.stdout.take().unwrap());
+        Self {
+            adapter,
+            stdin,
+            stdout,
+            seq: 0,
+        }
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut len = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            match line.trim().strip_prefix("Content-Length: ") {
+                Some(value) => len = value.parse().unwrap(),
+                None if line.trim().is_empty() => break,
+                None => {}
+            }
+        }
+        let mut body = vec![0; len];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    /// sends a request and returns the response to it
+    fn request(&mut self, command: &str, arguments: Value) -> Value {
+        self.seq += 1;
+        let request = json!({
+            "seq": self.seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        });
+        let text = request.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", text.len(), text).unwrap();
+        self.stdin.flush().unwrap();
+        loop {
+            let message = self.receive();
+            if message["type"] == "response" && message["request_seq"] == self.seq {
+                return message;
+            }
+        }
+    }
+
+    /// waits for the event and returns its body
+    fn event(&mut self, event: &str) -> Value {
+        loop {
+            let message = self.receive();
+            if message["type"] == "event" && message["event"] == event {
+                return message["body"].clone();
+            }
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let _ = self.adapter.kill();
+        let _ = self.adapter.wait();
+    }
+}
+
+#[test]
+fn breakpoints_pause_in_imported_files() {
+    let dir = std::env::temp_dir().join("phoenix-dap");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let (main, util) = (dir.join("main.phx"), dir.join("util.phx"));
+    fs::write(&main, "import util\nprint(util.twice(2))").unwrap();
+    fs::write(&util, "fn twice(x) -> {\n    x * 2\n}").unwrap();
+
+    let mut client = Client::start();
+    let response = client.request("initialize", json!({ "adapterID": "phoenix" }));
+    assert_eq!(response["success"], true);
+    client.event("initialized");
+    client.request("launch", json!({ "program": main }));
+    let source = json!({ "path": util });
+    let response = client.request(
+        "setBreakpoints",
+        json!({ "source": source, "breakpoints": [{ "line": 2 }] }),
+    );
+    assert_eq!(response["body"]["breakpoints"][0]["verified"], true);
+    client.request("configurationDone", json!({}));
+
+    assert_eq!(client.event("stopped")["reason"], "breakpoint");
+    let trace = client.request("stackTrace", json!({ "threadId": 1 }));
+    let frames = &trace["body"]["stackFrames"];
+    assert_eq!(frames[0]["source"]["path"], json!(util));
+    assert_eq!(frames[0]["line"], 2);
+    assert_eq!(frames[0]["name"], "util.twice");
+    assert_eq!(frames[1]["source"]["path"], json!(main));
+    let scopes = client.request("scopes", json!({ "frameId": 0 }));
+    let reference = scopes["body"]["scopes"][0]["variablesReference"].clone();
+    let variables = client.request("variables", json!({ "variablesReference": reference }));
+    assert_eq!(variables["body"]["variables"][0]["name"], "x");
+    assert_eq!(variables["body"]["variables"][0]["value"], "2");
+
+    client.request("continue", json!({ "threadId": 1 }));
+    assert_eq!(client.event("output")["output"], "4\n");
+    assert_eq!(client.event("exited")["exitCode"], 0);
+    assert_eq!(client.request("disconnect", json!({}))["success"], true);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::run;
+use phoenix::interpreter::{formatter, parse, Error, ErrorKind};
+
+/// the error of parsing `text`
+fn parse_error(text: &str) -> Error {
+    match parse(text, "test".to_string()) {
+        Ok(_) => panic!("{} was parsed", text),
+        Err(e) => e,
+    }
+}
+
+const SHAPE: &str = "enum Shape { Circle(r), Rect(w, h), Empty }
+fn area(shape) -> match shape {
+    Shape.Circle(r) -> 3 * r * r
+    Shape.Rect(w, h) if w == h -> w * w
+    Shape.Rect(w, h) -> w * h
+    Shape.Empty -> 0
+}
+";
+
+#[test]
+fn match_picks_the_first_fitting_arm() {
+    let text = SHAPE.to_string()
+        + "print(area(Shape.Circle(2)), area(Shape.Rect(2, 3)), area(Shape.Rect(4, 4)), area(Shape.Empty))\n"
+        + "print(Shape.Circle(2), Shape.Empty, Shape.Circle(2) == Shape.Circle(2))";
+    let (res, output) = run(&text);
+    assert_eq!(res, Ok(()));
+    assert_eq!(output, "12 6 16 0\nShape.Circle(r = 2) Shape.Empty 1\n");
+}
+
+#[test]
+fn patterns_match_literals_tuples_and_structs() {
+    let text = "struct P { x, y }
+fn describe(v) -> match v {
+    0 -> \"zero\"
+    -1 -> \"minus one\"
+    \"hi\" -> \"greeting\"
+    (a, b) -> \"pair {a} {b}\"
+    P(0, y) -> \"on the y axis at {y}\"
+    P(_, _) -> \"point\"
+    _ -> \"other\"
+}
+print(describe(0), describe(-1), describe(\"hi\"), describe(list(1, 2)), sep = \", \")
+print(describe(P(0, 5)), describe(P(1, 2)), describe(2.5), sep = \", \")";
+    let (res, output) = run(text);
+    assert_eq!(res, Ok(()));
+    assert_eq!(
+        output,
+        "zero, minus one, greeting, pair 1 2\non the y axis at 5, point, other\n"
+    );
+}
+
+#[test]
+fn matches_on_enums_must_cover_every_variant() {
+    let missing = "fn f(s) -> match s { Shape.Circle(1) -> 1, Shape.Empty -> 0 }
+enum Shape { Circle(r), Rect(w, h), Empty }";
+    let e = parse_error(missing);
+    assert_eq!(*e.kind(), ErrorKind::MatchError);
+    assert_eq!(
+        e.message(),
+        "the match doesn't cover Shape.Circle, Shape.Rect"
+    );
+    assert_eq!(e.position().unwrap().line(), 0);
+
+    let unknown = SHAPE.to_string() + "match Shape.Empty { Shape.Square -> 1, _ -> 0 }";
+    let e = parse_error(&unknown);
+    assert_eq!(*e.kind(), ErrorKind::NameError);
+
+    let arity = SHAPE.to_string() + "match Shape.Empty { Shape.Rect(w) -> 1, _ -> 0 }";
+    let e = parse_error(&arity);
+    assert_eq!(e.message(), "Shape.Rect has 2 fields but the pattern has 1");
+
+    let (res, output) = run("match 5 { 1 -> 2 }");
+    assert_eq!(res, Err(ErrorKind::MatchError));
+    assert_eq!(output, "");
+}
+
+#[test]
+fn enums_and_matches_are_formatted() {
+    let text = "enum Shape { Circle(r), Empty }\nlet a = match Shape.Empty {\n    Shape.Circle(r) if r > 1 -> r\n    _ -> 0\n}\n";
+    assert_eq!(formatter::format(text, "test".to_string()).unwrap(), text);
+}
@@ -0,0 +1,97 @@
+use phoenix::interpreter::{formatter, parse};
+
+const SOURCES: &[&str] = &[
+    "1+2*3",
+    "(1 + 2) * 3\n-(1+2) * -x\n!a == b && c || d\n(!a) == b",
+    "let x = let y = 3\nlet z = (let a = 1) + 2",
+    "f(1)(2, g(3))\n(fn (a) -> a + 1)(2)\n(-f)(1)",
+    "fn f(a) -> a\n(fn f(a) -> a) + 1\n1 + fn (a) -> a",
+    "if a then b elif c then d else e\nif (if a then b) then c else d\nif a then (if b then c) else d",
+    "if a {1} else {2}",
+    "for i in 0 to 10 then i * 2\n(for i in 0 to 10 then i) + 1",
+    "for c in \"abc\".upper() then print(c)\n(for x in xs then x) + 1",
+    "while i < 3 then { let i = i + 1 }\n{}\n{ 1; 2 }\n{ { 1 } }",
+    "math.sqrt(2) + (a + b).c\n1.x.y(3)\n(-a).b",
+    "print(\"a\\n\", 1, sep = \", \", end = \"\")\nf(a = \"\\u{1F600}\\\"\")",
+    "\"Hello {name}, {{x}} {count + 1} {\"nested {a.b(\"c\")}\"}\"\n\"{{}}\" + \"}}\\n\"",
+    "import \"lib/util.phx\" as util\nimport math\nimport math as m\nfrom helper import a, b",
+    "0x1F + 0b1010 + 1_000 + 1.5e3 + .5",
+    "a - (b - c)\na - b - c\na / (b * c)\n1 + (2 + 3)",
+    "fn fib(n) -> {\n  if n < 2 then n else fib(n - 1) + fib(n - 2)\n}\nfib(10)",
+    "# only a comment\n",
+    "",
+    "let x = 1 # one\n\n\n# two\nlet y = {  # three\n  # four\n  x\n  # five\n}\nf(1, # six\n  # seven\n  2)\n# eight",
+    "let long = some_function_name(argument_number_one, argument_number_two, argument_number_three, 4, 5, 6)",
+    "fn h(x) -> if x then { aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa } else { bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb }",
+    "let total = first_value_of_the_list * 2 + second_value_of_the_list - (third_value - fourth_value) * 3",
+    "if aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa && bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb || (fn (x) -> x)(ccccccccccccccc) then 1",
+];
+
+fn format(text: &str) -> String {
+    formatter::format(text, "test".to_string()).unwrap()
+}
+
+fn ast(text: &str) -> String {
+    parse(text, "test".to_string()).unwrap().to_string()
+}
+
+#[test]
+fn formatting_keeps_the_ast() {
+    for source in SOURCES {
+        assert_eq!(ast(source), ast(&format(source)), "source: {}", source);
+    }
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    for source in SOURCES {
+        let formatted = format(source);
+        assert_eq!(formatted, format(&formatted), "source: {}", source);
+    }
+}
+
+#[test]
+fn formatting_keeps_comments_in_order() {
+    for source in SOURCES {
+        let comments = |text: &str| -> Vec<String> {
+            text.lines()
+                .filter_map(|line| line.find('#').map(|i| line[i..].trim().to_string()))
+                .collect()
+        };
+        assert_eq!(
+            comments(source),
+            comments(&format(source)),
+            "source: {}",
+            source
+        );
+    }
+}
+
+#[test]
+fn canonical_style() {
+    assert_eq!(format("let x=1+2*3"), "let x = 1 + 2 * 3\n");
+    assert_eq!(format("1;2\n\n\n3"), "1\n2\n\n3\n");
+    assert_eq!(
+        format("fn f(a,b)->{a\nb}"),
+        "fn f(a, b) -> {\n    a\n    b\n}\n"
+    );
+    assert_eq!(format("let x = 0xff # hex"), "let x = 0xff  # hex\n");
+}
+
+#[test]
+fn formatting_reports_syntax_errors() {
+    assert!(formatter::format("let = 1", "test".to_string()).is_err());
+}
+
+#[test]
+fn long_chains_of_operators_are_broken_up() {
+    assert_eq!(
+        format(SOURCES[SOURCES.len() - 2]),
+        "let total = (\n    first_value_of_the_list * 2\n    + second_value_of_the_list\n    - (third_value - fourth_value) * 3\n)\n"
+    );
+    let chain = vec!["a"; 8000].join(" + ");
+    let formatted = format(&chain);
+    assert_eq!(formatted.lines().count(), 8002);
+    assert!(formatted.lines().all(|line| line.chars().count() <= 100));
+    assert_eq!(ast(&chain), ast(&formatted));
+}
@@ -0,0 +1,144 @@
+use phoenix::interpreter::limits::{CancelHandle, Limits};
+use phoenix::interpreter::{run_with, ErrorKind, Options};
+use std::thread;
+use std::time::Duration;
+
+/// runs `text` with `limits` on a thread with `stack` bytes of stack,
+/// returns the kind of the error
+fn run_on(stack: usize, text: &str, limits: Limits) -> Result<(), ErrorKind> {
+    let text = text.to_string();
+    let thread = thread::Builder::new().stack_size(stack);
+    let handle = thread.spawn(move || {
+        let options = Options::new().with_limits(limits);
+        let res = run_with(text, "test".to_string(), None, options).res;
+        res.map(|_| ()).map_err(|e| e.kind().clone())
+    });
+    handle.unwrap().join().unwrap()
+}
+
+/// runs `text` with `limits` on a thread with enough stack for deep calls
+fn run(text: &str, limits: Limits) -> Result<(), ErrorKind> {
+    run_on(64 << 20, text, limits)
+}
+
+const RECURSION: &str = "fn down(n) -> if n == 0 then 0 else 1 + down(n - 1)\ndown(950)";
+
+#[test]
+fn steps_are_limited() {
+    let text = "let i = 0\nwhile i < 100 then { let i = i + 1 }";
+    assert_eq!(run(text, Limits::new().with_steps(100_000)), Ok(()));
+    assert_eq!(
+        run(text, Limits::new().with_steps(100)),
+        Err(ErrorKind::Timeout)
+    );
+}
+
+#[test]
+fn calls_are_limited() {
+    assert_eq!(run(RECURSION, Limits::new()), Ok(()));
+    assert_eq!(
+        run(RECURSION, Limits::new().with_depth(500)),
+        Err(ErrorKind::RecursionLimit)
+    );
+    let endless = "fn f() -> f()\nf()";
+    assert_eq!(run(endless, Limits::new()), Err(ErrorKind::RecursionLimit));
+}
+
+#[test]
+fn the_stack_of_the_current_thread_is_respected() {
+    // far less than the recursion needs, so the run must stop in time
+    // instead of overflowing the stack of the thread
+    for stack in [1 << 20, 2 << 20] {
+        assert_eq!(
+            run_on(stack, RECURSION, Limits::new()),
+            Err(ErrorKind::RecursionLimit)
+        );
+    }
+    assert_eq!(
+        run(RECURSION, Limits::new().with_stack(64 << 10)),
+        Err(ErrorKind::RecursionLimit)
+    );
+}
+
+#[test]
+fn runs_can_be_cancelled() {
+    let cancel = CancelHandle::new();
+    let handle = cancel.clone();
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        handle.cancel();
+    });
+    let limits = Limits::new().with_cancel(cancel.clone());
+    assert_eq!(
+        run("while 1 then {}", limits.clone()),
+        Err(ErrorKind::Cancelled)
+    );
+    canceller.join().unwrap();
+    // a cancelled handle stops runs right away until it is reset
+    assert_eq!(run("1", limits.clone()), Err(ErrorKind::Cancelled));
+    cancel.reset();
+    assert_eq!(run("1", limits), Ok(()));
+}
+
+#[test]
+fn memory_is_limited() {
+    assert_eq!(run(RECURSION, Limits::new().with_memory(1 << 20)), Ok(()));
+    assert_eq!(
+        run(RECURSION, Limits::new().with_memory(1000)),
+        Err(ErrorKind::MemoryLimit)
+    );
+    // values count as long as they are alive
+    let doubling = [
+        "let s = \"ab\"\nwhile 1 then { let s = s + s }",
+        "let xs = list(1, 2)\nwhile 1 then { let xs = xs + xs }",
+        "let t = (1, 2)\nwhile 1 then { let t = t + t }",
+        "let s = \"ab\"\nwhile 1 then { let s = \"{s}{s}\" }",
+    ];
+    for text in doubling {
+        assert_eq!(
+            run(text, Limits::new().with_memory(1000)),
+            Err(ErrorKind::MemoryLimit)
+        );
+    }
+    assert_eq!(
+        run("range(0, 1000000000)", Limits::new().with_memory(1 << 20)),
+        Err(ErrorKind::MemoryLimit)
+    );
+    let replaced = "let i = 0\nwhile i < 1000 then { let s = str(i) + \"abc\"; let i = i + 1 }";
+    assert_eq!(run(replaced, Limits::new().with_memory(1000)), Ok(()));
+}
+
+#[test]
+fn only_nested_expressions_count_towards_the_nesting() {
+    let chain = format!("{}\n", vec!["1"; 150].join(" + "));
+    let calls = "fn f() -> f\nf".to_string() + &"()".repeat(150);
+    let parens = "(".repeat(120) + "1" + &")".repeat(120);
+    for text in [chain, calls, parens.clone()] {
+        assert_eq!(run(&text, Limits::new()), Ok(()));
+    }
+    assert_eq!(
+        run(&parens, Limits::new().with_nesting(100)),
+        Err(ErrorKind::RecursionLimit)
+    );
+    let blocks = "{".repeat(300) + &"}".repeat(300);
+    assert_eq!(run(&blocks, Limits::new()), Err(ErrorKind::RecursionLimit));
+}
+
+#[test]
+fn interpolated_strings_count_towards_the_nesting() {
+    let nested = |n| format!("{}1{}", "\"{".repeat(n), "}\"".repeat(n));
+    assert_eq!(run_on(1 << 20, &nested(50), Limits::new()), Ok(()));
+    for n in [300, 5000] {
+        assert_eq!(
+            run_on(1 << 20, &nested(n), Limits::new()),
+            Err(ErrorKind::RecursionLimit)
+        );
+    }
+    // lexing and parsing get by with the stack of the thread, running stops
+    // once it is used up
+    let limits = Limits::new().with_nesting(20_000);
+    assert_eq!(
+        run_on(1 << 20, &nested(5000), limits),
+        Err(ErrorKind::RecursionLimit)
+    );
+}
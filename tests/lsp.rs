@@ -0,0 +1,150 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// The language server running in its own process, spoken to over stdio
+struct Client {
+    server: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl Client {
+    /// starts the server and initializes it
+    fn start() -> Self {
+        let mut server = Command::new(env!("CARGO_BIN_EXE_phoenix-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let stdin = server.stdin.take().unwrap();
+        let stdout = BufReader::new(server.stdout.take().unwrap());
+        let mut client = Self {
+            server,
+            stdin,
+            stdout,
+            next_id: 0,
+        };
+        client.request("initialize", json!({ "capabilities": {} }));
+        client.notify("initialized", json!({}));
+        client
+    }
+
+    fn send(&mut self, message: Value) {
+        let text = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", text.len(), text).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut len = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            match line.trim().strip_prefix("Content-Length: ") {
+                Some(value) => len = value.parse().unwrap(),
+                None if line.trim().is_empty() => break,
+                None => {}
+            }
+        }
+        let mut body = vec![0; len];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    /// sends a request and returns the response to it
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let message = self.receive();
+            if message["id"] == id {
+                return message;
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// opens a document and returns the diagnostics published for it
+    fn open(&mut self, uri: &str, text: &str) -> Value {
+        let document = json!({ "uri": uri, "languageId": "phoenix", "version": 1, "text": text });
+        self.notify("textDocument/didOpen", json!({ "textDocument": document }));
+        loop {
+            let message = self.receive();
+            if message["method"] == "textDocument/publishDiagnostics" {
+                return message["params"]["diagnostics"].clone();
+            }
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let _ = self.server.kill();
+        let _ = self.server.wait();
+    }
+}
+
+fn at(uri: &str, line: u32, character: u32) -> Value {
+    json!({ "textDocument": { "uri": uri }, "position": { "line": line, "character": character } })
+}
+
+const URI: &str = "file:///test.phx";
+
+#[test]
+fn diagnostics_are_published() {
+    let mut client = Client::start();
+    let diagnostics = client.open(URI, "let x: int = \"a\"\nprint(y)");
+    let messages: Vec<&str> = (diagnostics.as_array().unwrap().iter())
+        .map(|d| d["message"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        messages,
+        vec!["y is not defined", "TypeError: x should be int but is str"]
+    );
+    assert_eq!(
+        diagnostics[0]["range"]["start"],
+        json!({ "line": 1, "character": 6 })
+    );
+}
+
+#[test]
+fn hover_and_definition_find_the_definition() {
+    let mut client = Client::start();
+    client.open(URI, "let x: int = 1\nfn f(a) -> a\nprint(f(x))");
+    let hover = client.request("textDocument/hover", at(URI, 2, 8));
+    assert_eq!(
+        hover["result"]["contents"]["value"],
+        "```phoenix\nlet x: int\n```"
+    );
+    let definition = client.request("textDocument/definition", at(URI, 2, 6));
+    assert_eq!(
+        definition["result"]["range"]["start"],
+        json!({ "line": 1, "character": 3 })
+    );
+    let nothing = client.request("textDocument/hover", at(URI, 0, 0));
+    assert_eq!(nothing["result"], Value::Null);
+}
+
+#[test]
+fn invalid_messages_dont_stop_the_server() {
+    let mut client = Client::start();
+    let response = client.request("textDocument/hover", json!({ "nope": 1 }));
+    assert_eq!(response["error"]["code"], -32602);
+    client.notify("textDocument/didOpen", json!({ "nope": 1 }));
+    let response = client.request("textDocument/unknown", json!({}));
+    assert_eq!(response["error"]["code"], -32601);
+    // the server still answers afterwards
+    assert_eq!(client.open(URI, "let x = 1"), json!([]));
+    let hover = client.request("textDocument/hover", at(URI, 0, 4));
+    assert_eq!(
+        hover["result"]["contents"]["value"],
+        "```phoenix\nlet x: int\n```"
+    );
+}
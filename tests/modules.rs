@@ -0,0 +1,121 @@
+mod common;
+
+use common::run_file;
+use phoenix::interpreter::capabilities::Capabilities;
+use phoenix::interpreter::console::Buffer;
+use phoenix::interpreter::{Error, ErrorKind, Options};
+use std::fs;
+
+/// writes the files into a new directory, runs the first one and returns
+/// the error and the output
+fn run(name: &str, files: &[(&str, &str)]) -> (Result<(), ErrorKind>, String) {
+    let (res, output) = run_as(name, files, Capabilities::all());
+    (res.map_err(|e| e.kind().clone()), output)
+}
+
+/// like `run`, but only with `capabilities` and keeps the whole error
+fn run_as(
+    name: &str,
+    files: &[(&str, &str)],
+    capabilities: Capabilities,
+) -> (Result<(), Error>, String) {
+    let dir = std::env::temp_dir().join(format!("phoenix-modules-{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    for (file, text) in files {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+    let options = Options::new()
+        .with_capabilities(capabilities)
+        .with_search_paths(vec![dir.join("lib")]);
+    let main = dir.join(files[0].0).display().to_string();
+    let (res, output) = run_file(files[0].1, &main, options, Buffer::new());
+    (res.map(|_| ()), output)
+}
+
+#[test]
+fn imports_are_run_once() {
+    let (res, output) = run(
+        "once",
+        &[
+            (
+                "main.phx",
+                "import \"util.phx\" as util\nfrom \"util.phx\" import greet\nimport helper\nprint(util.answer, greet(\"a\"), helper.twice(2))",
+            ),
+            ("util.phx", "print(\"loading\")\nlet answer = 42\nfn greet(x) -> \"hi {x}\""),
+            ("lib/helper.phx", "fn twice(x) -> x * 2"),
+        ],
+    );
+    assert_eq!(res, Ok(()));
+    assert_eq!(output, "loading\n42 hi a 4\n");
+}
+
+#[test]
+fn circular_imports_fail() {
+    let (res, _) = run(
+        "cycle",
+        &[
+            ("a.phx", "import \"b.phx\" as b"),
+            ("b.phx", "import \"a.phx\" as a"),
+        ],
+    );
+    assert_eq!(res, Err(ErrorKind::ImportError));
+}
+
+#[test]
+fn missing_modules_and_members_fail() {
+    for (name, text) in [
+        ("missing", "import \"nope.phx\" as nope"),
+        ("member", "from math import nope"),
+    ] {
+        let (res, _) = run(name, &[("main.phx", text)]);
+        assert_eq!(res, Err(ErrorKind::ImportError), "{}", text);
+    }
+}
+
+#[test]
+fn errors_in_modules_point_into_the_module() {
+    let (res, _) = run_as(
+        "traceback",
+        &[
+            ("main.phx", "import util\nutil.bad(1)"),
+            ("util.phx", "fn bad(x) -> {\n    x + \"a\"\n}"),
+        ],
+        Capabilities::all(),
+    );
+    let error = res.unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::TypeError);
+    let traceback = error.to_string();
+    assert!(
+        traceback.contains("util.phx, line 2, column 4"),
+        "{}",
+        traceback
+    );
+}
+
+#[test]
+fn unfinished_modules_are_syntax_errors() {
+    let files = [("main.phx", "import util"), ("util.phx", "fn f(x) -> {")];
+    let (res, _) = run_as("unfinished", &files, Capabilities::all());
+    let error = res.unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::SyntaxError);
+    let traceback = error.to_string();
+    assert!(traceback.contains("main.phx, line 1"), "{}", traceback);
+    assert!(traceback.contains("util.phx, line 1"), "{}", traceback);
+}
+
+#[test]
+fn modules_can_only_be_found_with_read_access() {
+    let files = [("main.phx", "import util"), ("util.phx", "let x = 1")];
+    let (res, _) = run_as("sandboxed", &files, Capabilities::none());
+    assert_eq!(res.unwrap_err().kind(), &ErrorKind::PermissionError);
+    // a missing file fails the same way, so its existence stays hidden
+    let files = [("main.phx", "import nope")];
+    let (res, _) = run_as("sandboxed-missing", &files, Capabilities::none());
+    assert_eq!(res.unwrap_err().kind(), &ErrorKind::PermissionError);
+    let dir = std::env::temp_dir().join("phoenix-modules-readable");
+    let files = [("main.phx", "import util"), ("util.phx", "let x = 1")];
+    let (res, _) = run_as("readable", &files, Capabilities::none().with_read(dir));
+    assert!(res.is_ok());
+}
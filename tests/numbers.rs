@@ -0,0 +1,29 @@
+mod common;
+
+use common::{run, run_file};
+use phoenix::interpreter::console::Buffer;
+use phoenix::interpreter::{ErrorKind, Options};
+
+#[test]
+fn ints_report_overflows_where_they_happen() {
+    let text = "let x = 1\nprint(x)\n9223372036854775807 + x";
+    let (res, output) = run_file(text, "test", Options::new(), Buffer::new());
+    assert_eq!(output, "1\n");
+    let e = res.unwrap_err();
+    assert_eq!(*e.kind(), ErrorKind::ValueError);
+    assert_eq!(e.message(), "the result is too large for an int");
+    assert_eq!(e.position().unwrap().line(), 2);
+
+    let min = "(-9223372036854775807 - 1)";
+    for text in [
+        format!("{} - 1", min),
+        "4611686018427387904 * 2".to_string(),
+        format!("{} / -1", min),
+        format!("-{}", min),
+    ] {
+        assert_eq!(run(&text).0, Err(ErrorKind::ValueError), "{}", text);
+    }
+    let (res, output) = run("print(9223372036854775807 - 1, -9223372036854775807 * 1)");
+    assert!(res.is_ok());
+    assert_eq!(output, "9223372036854775806 -9223372036854775807\n");
+}
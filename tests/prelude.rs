@@ -0,0 +1,62 @@
+mod common;
+
+use common::{run, run_with_options};
+use phoenix::interpreter::builtins::prelude;
+use phoenix::interpreter::{formatter, ErrorKind, Options};
+
+#[test]
+fn prelude_functions_work_on_lists() {
+    let (res, output) = run(
+        "let xs = list(3, 1, 2)\nprint(range(0, 3), map(xs, fn (x) -> x * 2), filter(xs, fn (x) -> x > 1))\nprint(reduce(xs, fn (a, b) -> a * b, 1), sum(xs), sorted(xs), reversed(xs))\nprint(enumerate(list(\"a\")), zip(xs, list(\"x\", \"y\")), sorted(\"b c a\".split()))",
+    );
+    assert_eq!(res, Ok(()));
+    assert_eq!(
+        output,
+        "[0, 1, 2] [6, 2, 4] [3, 2]\n6 6 [1, 2, 3] [2, 1, 3]\n[[0, \"a\"]] [[3, \"x\"], [1, \"y\"]] [\"a\", \"b\", \"c\"]\n"
+    );
+}
+
+#[test]
+fn sorting_uses_the_operators_of_objects() {
+    let text = "struct P {\n    x\n    fn __lt__(self, other) -> self.x < other.x\n}\nprint(sorted(list(P(2), P(1), P(3))))";
+    let (res, output) = run(text);
+    assert_eq!(res, Ok(()));
+    assert_eq!(output, "[P(x = 1), P(x = 2), P(x = 3)]\n");
+    let (res, _) = run("sorted(list(1, \"a\"))");
+    assert_eq!(res, Err(ErrorKind::TypeError));
+    let (res, _) = run("map(5, fn (x) -> x)");
+    assert_eq!(res, Err(ErrorKind::TypeError));
+}
+
+#[test]
+fn prelude_can_be_disabled() {
+    let mut names = prelude::names();
+    names.sort();
+    let expected = [
+        "enumerate",
+        "filter",
+        "map",
+        "range",
+        "reduce",
+        "reversed",
+        "sorted",
+        "sum",
+        "zip",
+    ];
+    assert_eq!(names, expected);
+    for name in names {
+        let (res, _) = run_with_options(&name, Options::new().without_prelude());
+        assert_eq!(res, Err(ErrorKind::NameError), "{} is defined", name);
+    }
+    let (res, output) = run_with_options("print(list(1))", Options::new().without_prelude());
+    assert_eq!((res, output.as_str()), (Ok(()), "[1]\n"));
+}
+
+#[test]
+fn prelude_is_formatted() {
+    let source = include_str!("../src/interpreter/builtins/prelude.phx");
+    assert_eq!(
+        formatter::format(source, "prelude".to_string()).unwrap(),
+        source
+    );
+}
@@ -0,0 +1,57 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// the output of the REPL started with `args` after reading `input`
+fn repl(args: &[&str], input: &str) -> String {
+    // the history is saved in the home directory
+    let home = std::env::temp_dir().join(format!("phoenix-repl-{}", std::process::id()));
+    std::fs::create_dir_all(&home).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_phoenix"))
+        .args(args)
+        .env("HOME", &home)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(input.as_bytes()).unwrap();
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn the_repl_is_sandboxed_too() {
+    let output = repl(&["--sandbox"], "random()\n");
+    assert!(output.contains("PermissionError: the script has no access to randomness"));
+    let output = repl(&[], "random() < 1.0\n");
+    assert!(output.ends_with("1\n"), "{}", output);
+}
+
+#[test]
+fn broken_imports_dont_wait_for_more_input() {
+    let dir = std::env::temp_dir().join(format!("phoenix-repl-import-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let broken = dir.join("broken.phx");
+    std::fs::write(&broken, "fn f(x) -> {").unwrap();
+    let input = format!(
+        "print(1)\nimport \"{}\" as broken\nprint(2)\n",
+        broken.display()
+    );
+    let output = repl(&[], &input);
+    assert!(output.contains("SyntaxError"), "{}", output);
+    // the first line isn't run again with the next one
+    assert_eq!(output.lines().filter(|line| *line == "1").count(), 1);
+    assert!(output.ends_with("2\n"), "{}", output);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn type_doesnt_run_the_expression() {
+    let input = "let x = 1\nfn f() -> { print(\"ran\"); let x = 2 }\n:type f()\n:type x + 1\n:type \"a\" + x\nx\n";
+    let output = repl(&[], input);
+    assert!(!output.lines().any(|line| line == "ran"), "{}", output);
+    assert!(output.contains("\nint\n"), "{}", output);
+    assert!(output.contains("TypeError"), "{}", output);
+    assert!(output.ends_with("1\n"), "{}", output);
+}
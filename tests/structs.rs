@@ -0,0 +1,95 @@
+mod common;
+
+use common::run;
+use phoenix::interpreter::{formatter, ErrorKind};
+
+const POINT: &str = "struct Point {
+    x, y
+    fn norm2(self) -> self.x * self.x + self.y * self.y
+    fn __add__(self, other) -> Point(self.x + other.x, self.y + other.y)
+    fn __eq__(self, other) -> self.x == other.x && self.y == other.y
+}
+";
+
+#[test]
+fn structs_have_fields_and_methods() {
+    let text = POINT.to_string()
+        + "let p = Point(1, y = 2)\nlet q = p\nq.x = 3\nprint(p, p.norm2(), Point.norm2(p))";
+    let (res, output) = run(&text);
+    assert_eq!(res, Ok(()));
+    assert_eq!(output, "Point(x = 3, y = 2) 13 13\n");
+}
+
+#[test]
+fn structs_override_operators() {
+    let text = POINT.to_string()
+        + "let p = Point(1, 2) + Point(3, 4)\nprint(p, p == Point(4, 6), p != Point(4, 6))";
+    let (res, output) = run(&text);
+    assert_eq!(res, Ok(()));
+    assert_eq!(output, "Point(x = 4, y = 6) 1 0\n");
+}
+
+#[test]
+fn structs_reject_unknown_fields() {
+    let (res, _) = run(&(POINT.to_string() + "Point(1, 2).z = 3"));
+    assert_eq!(res, Err(ErrorKind::NameError));
+    let (res, _) = run(&(POINT.to_string() + "Point(1)"));
+    assert_eq!(res, Err(ErrorKind::TypeError));
+    let (res, _) = run("struct A { a, fn f(x) -> x }");
+    assert_eq!(res, Err(ErrorKind::SyntaxError));
+}
+
+#[test]
+fn structs_are_formatted() {
+    assert_eq!(formatter::format(POINT, "test".to_string()).unwrap(), POINT);
+    let formatted = formatter::format("struct   A{a,b}\n", "test".to_string()).unwrap();
+    assert_eq!(formatted, "struct A { a, b }\n");
+}
+
+const VEC: &str = "struct Vec2 {
+    x, y
+    fn __add__(self, other) -> Vec2(self.x + other.x, self.y + other.y)
+    fn __radd__(self, n) -> Vec2(self.x + n, self.y + n)
+    fn __lt__(self, other) -> self.x < other.x
+    fn __str__(self) -> \"<{self.x}, {self.y}>\"
+}
+struct Bag {
+    items
+    fn __len__(self) -> len(self.items)
+    fn __getitem__(self, i) -> self.items[i] * 10
+}
+";
+
+#[test]
+fn operator_methods_fall_back_to_the_reflected_one() {
+    let text =
+        VEC.to_string() + "print(Vec2(1, 2) + Vec2(1, 1), 1 + Vec2(1, 2), Vec2(0, 5) < Vec2(1, 0))";
+    let (res, output) = run(&text);
+    assert_eq!(res, Ok(()));
+    assert_eq!(output, "<2, 3> <2, 3> 1\n");
+    let (res, _) = run(&(VEC.to_string() + "Vec2(1, 2) * 2"));
+    assert_eq!(res, Err(ErrorKind::TypeError));
+}
+
+#[test]
+fn objects_convert_and_index_with_methods() {
+    let text = VEC.to_string()
+        + "let b = Bag(list(1, 2, 3))\nprint(len(b), b[1], \"{Vec2(1, 2)}\", !Bag(list()))\nfor x in b then print(x, end = \" \")\nif b then print(str(Vec2(0, 0)))";
+    let (res, output) = run(&text);
+    assert_eq!(res, Ok(()));
+    assert_eq!(output, "3 20 <1, 2> 1\n10 20 30 <0, 0>\n");
+}
+
+#[test]
+fn strings_and_lists_are_indexed() {
+    let (res, output) = run("print(\"héllo\"[1], list(4, 5)[1], len(\"héllo\"))");
+    assert_eq!((res, output.as_str()), (Ok(()), "é 5 5\n"));
+    let (res, output) = run("print((1, \"a\")[1], \"héllo\"[4])");
+    assert_eq!((res, output.as_str()), (Ok(()), "a o\n"));
+    for text in ["list(1)[1]", "\"héllo\"[5]", "(1, 2)[-1]"] {
+        let (res, _) = run(text);
+        assert_eq!(res, Err(ErrorKind::ValueError));
+    }
+    let (res, _) = run("5[0]");
+    assert_eq!(res, Err(ErrorKind::TypeError));
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::{run, run_file};
+use phoenix::interpreter::console::Buffer;
+use phoenix::interpreter::{ErrorKind, Options};
+
+#[test]
+fn let_destructures_tuples_and_lists() {
+    let text = "fn divmod(a, b) -> (a / b, a - a / b * b)
+let (d, m) = divmod(17, 5)
+let [first, ...rest] = list(1, 2, 3)
+let ((a, b), [_, ...c, z]) = ((4, 5), (6, 7, 8, 9))
+print(d, m, first, rest, a, b, c, z, (1,), ())";
+    let (res, output) = run(text);
+    assert!(res.is_ok());
+    assert_eq!(output, "3 2 1 [2, 3] 4 5 [7, 8] 9 (1,) ()\n");
+}
+
+#[test]
+fn destructuring_reports_the_pattern_on_a_wrong_number_of_values() {
+    let text = "let pair = (1, 2)\nlet (x, y, z) = pair";
+    let e = run_file(text, "test", Options::new(), Buffer::new())
+        .0
+        .unwrap_err();
+    assert_eq!(*e.kind(), ErrorKind::ValueError);
+    assert_eq!(e.message(), "expected 3 values but got 2");
+    let pos = e.position().unwrap();
+    assert_eq!((pos.line(), pos.column()), (1, 4));
+
+    let (res, _) = run("let [a, b, ...rest] = list(1)");
+    assert_eq!(res, Err(ErrorKind::ValueError));
+    let (res, _) = run("let (a, b) = 3");
+    assert_eq!(res, Err(ErrorKind::TypeError));
+}